@@ -1 +1,427 @@
-(()=>{"use strict";function t(t,e){let n,r;if(void 0===e)for(const e of t)null!=e&&(void 0===n?e>=e&&(n=r=e):(n>e&&(n=e),r<e&&(r=e)));else{let i=-1;for(let o of t)null!=(o=e(o,++i,t))&&(void 0===n?o>=o&&(n=r=o):(n>o&&(n=o),r<o&&(r=o)))}return[n,r]}function e(t){return t}Object.defineProperty(e,"name",{value:"default",configurable:!0});var n=1e-6;function r(t){return"translate("+t+",0)"}function i(t){return"translate(0,"+t+")"}function o(t){return e=>+t(e)}function a(t,e){return e=Math.max(0,t.bandwidth()-2*e)/2,t.round()&&(e=Math.round(e)),n=>+t(n)+e}function u(){return!this.__axis}function l(t,l){var s=[],c=null,f=null,h=6,p=6,d=3,y="undefined"!=typeof window&&window.devicePixelRatio>1?0:.5,m=1===t||4===t?-1:1,v=4===t||2===t?"x":"y",g=1===t||3===t?r:i;function b(r){var i=c??(l.ticks?l.ticks.apply(l,s):l.domain()),b=f??(l.tickFormat?l.tickFormat.apply(l,s):e),w=Math.max(h,0)+d,_=l.range(),x=+_[0]+y,k=+_[_.length-1]+y,P=(l.bandwidth?a:o)(l.copy(),y),M=r.selection?r.selection():r,O=M.selectAll(".domain").data([null]),j=M.selectAll(".tick").data(i,l).order(),A=j.exit(),N=j.enter().append("g").attr("class","tick"),z=j.select("line"),E=j.select("text");O=O.merge(O.enter().insert("path",".tick").attr("class","domain").attr("stroke","currentColor")),j=j.merge(N),z=z.merge(N.append("line").attr("stroke","currentColor").attr(v+"2",m*h)),E=E.merge(N.append("text").attr("fill","currentColor").attr(v,m*w).attr("dy",1===t?"0em":3===t?"0.71em":"0.32em")),r!==M&&(O=O.transition(r),j=j.transition(r),z=z.transition(r),E=E.transition(r),A=A.transition(r).attr("opacity",n).attr("transform",function(t){return isFinite(t=P(t))?g(t+y):this.getAttribute("transform")}),N.attr("opacity",n).attr("transform",function(t){var e=this.parentNode.__axis;return g((e&&isFinite(e=e(t))?e:P(t))+y)})),A.remove(),O.attr("d",4===t||2===t?p?"M"+m*p+","+x+"H"+y+"V"+k+"H"+m*p:"M"+y+","+x+"V"+k:p?"M"+x+","+m*p+"V"+y+"H"+k+"V"+m*p:"M"+x+","+y+"H"+k),j.attr("opacity",1).attr("transform",function(t){return g(P(t)+y)}),z.attr(v+"2",m*h),E.attr(v,m*w).text(b),M.filter(u).attr("fill","none").attr("font-size",10).attr("font-family","sans-serif").attr("text-anchor",2===t?"start":4===t?"end":"middle"),M.each(function(){this.__axis=P})}return b.scale=function(t){return arguments.length?(l=t,b):l},b.ticks=function(){return s=Array.from(arguments),b},b.tickArguments=function(t){return arguments.length?(s=null==t?[]:Array.from(t),b):s.slice()},b.tickValues=function(t){return arguments.length?(c=null==t?null:Array.from(t),b):c&&c.slice()},b.tickFormat=function(t){return arguments.length?(f=t,b):f},b.tickSize=function(t){return arguments.length?(h=p=+t,b):h},b.tickSizeInner=function(t){return arguments.length?(h=+t,b):h},b.tickSizeOuter=function(t){return arguments.length?(p=+t,b):p},b.tickPadding=function(t){return arguments.length?(d=+t,b):d},b.offset=function(t){return arguments.length?(y=+t,b):y},b}function s(){}function c(t){return null==t?s:function(){return this.querySelector(t)}}function f(t){"function"!=typeof t&&(t=c(t));for(var e=this._groups,n=e.length,r=new Array(n),i=0;i<n;++i)for(var o,a,u=e[i],l=u.length,s=r[i]=new Array(l),f=0;f<l;++f)(o=u[f])&&(a=t.call(o,o.__data__,f,u))&&("__data__"in o&&(a.__data__=o.__data__),s[f]=a);return new Qt(r,this._parents)}function h(){return[]}function p(t){return null==t?h:function(){return this.querySelectorAll(t)}}function d(t){t="function"==typeof t?function(t){return function(){return null==(e=t.apply(this,arguments))?[]:Array.isArray(e)?e:Array.from(e);var e}}(t):p(t);for(var e=this._groups,n=e.length,r=[],i=[],o=0;o<n;++o)for(var a,u=e[o],l=u.length,s=0;s<l;++s)(a=u[s])&&(r.push(t.call(a,a.__data__,s,u)),i.push(a));return new Qt(r,i)}function y(t){return function(){return this.matches(t)}}function m(t){return function(e){return e.matches(t)}}Object.defineProperty(c,"name",{value:"default",configurable:!0}),Object.defineProperty(f,"name",{value:"default",configurable:!0}),Object.defineProperty(p,"name",{value:"default",configurable:!0}),Object.defineProperty(d,"name",{value:"default",configurable:!0}),Object.defineProperty(y,"name",{value:"default",configurable:!0}),Object.defineProperty(b,"name",{value:"default",configurable:!0});var v=Array.prototype.find;function g(){return this.firstElementChild}function b(t){return this.select(null==t?g:function(t){return function(){return v.call(this.children,t)}}("function"==typeof t?t:m(t)))}Object.defineProperty(x,"name",{value:"default",configurable:!0});var w=Array.prototype.filter;function _(){return Array.from(this.children)}function x(t){return this.selectAll(null==t?_:function(t){return function(){return w.call(this.children,t)}}("function"==typeof t?t:m(t)))}function k(t){"function"!=typeof t&&(t=y(t));for(var e=this._groups,n=e.length,r=new Array(n),i=0;i<n;++i)for(var o,a=e[i],u=a.length,l=r[i]=[],s=0;s<u;++s)(o=a[s])&&t.call(o,o.__data__,s,a)&&l.push(o);return new Qt(r,this._parents)}function P(t){return new Array(t.length)}function M(){return new Qt(this._enter||this._groups.map(P),this._parents)}function O(t,e){this.ownerDocument=t.ownerDocument,this.namespaceURI=t.namespaceURI,this._next=null,this._parent=t,this.__data__=e}function j(t){return function(){return t}}function A(t,e,n,r,i,o){for(var a,u=0,l=e.length,s=o.length;u<s;++u)(a=e[u])?(a.__data__=o[u],r[u]=a):n[u]=new O(t,o[u]);for(;u<l;++u)(a=e[u])&&(i[u]=a)}function N(t,e,n,r,i,o,a){var u,l,s,c=new Map,f=e.length,h=o.length,p=new Array(f);for(u=0;u<f;++u)(l=e[u])&&(p[u]=s=a.call(l,l.__data__,u,e)+"",c.has(s)?i[u]=l:c.set(s,l));for(u=0;u<h;++u)s=a.call(t,o[u],u,o)+"",(l=c.get(s))?(r[u]=l,l.__data__=o[u],c.delete(s)):n[u]=new O(t,o[u]);for(u=0;u<f;++u)(l=e[u])&&c.get(p[u])===l&&(i[u]=l)}function z(t){return t.__data__}function E(t,e){if(!arguments.length)return Array.from(this,z);var n=e?N:A,r=this._parents,i=this._groups;"function"!=typeof t&&(t=j(t));for(var o=i.length,a=new Array(o),u=new Array(o),l=new Array(o),s=0;s<o;++s){var c=r[s],f=i[s],h=f.length,p=$(t.call(c,c&&c.__data__,s,r)),d=p.length,y=u[s]=new Array(d),m=a[s]=new Array(d);n(c,f,y,m,l[s]=new Array(h),p,e);for(var v,g,b=0,w=0;b<d;++b)if(v=y[b]){for(b>=w&&(w=b+1);!(g=m[w])&&++w<d;);v._next=g||null}}return(a=new Qt(a,r))._enter=u,a._exit=l,a}function $(t){return"object"==typeof t&&"length"in t?t:Array.from(t)}function S(){return new Qt(this._exit||this._groups.map(P),this._parents)}function T(t,e,n){var r=this.enter(),i=this,o=this.exit();return"function"==typeof t?(r=t(r))&&(r=r.selection()):r=r.append(t+""),null!=e&&(i=e(i))&&(i=i.selection()),null==n?o.remove():n(o),r&&i?r.merge(i).order():i}function C(t){for(var e=t.selection?t.selection():t,n=this._groups,r=e._groups,i=n.length,o=r.length,a=Math.min(i,o),u=new Array(i),l=0;l<a;++l)for(var s,c=n[l],f=r[l],h=c.length,p=u[l]=new Array(h),d=0;d<h;++d)(s=c[d]||f[d])&&(p[d]=s);for(;l<i;++l)u[l]=n[l];return new Qt(u,this._parents)}function X(){for(var t=this._groups,e=-1,n=t.length;++e<n;)for(var r,i=t[e],o=i.length-1,a=i[o];--o>=0;)(r=i[o])&&(a&&4^r.compareDocumentPosition(a)&&a.parentNode.insertBefore(r,a),a=r);return this}function q(t){function e(e,n){return e&&n?t(e.__data__,n.__data__):!e-!n}t||(t=V);for(var n=this._groups,r=n.length,i=new Array(r),o=0;o<r;++o){for(var a,u=n[o],l=u.length,s=i[o]=new Array(l),c=0;c<l;++c)(a=u[c])&&(s[c]=a);s.sort(e)}return new Qt(i,this._parents).order()}function V(t,e){return t<e?-1:t>e?1:t>=e?0:NaN}function Y(){var t=arguments[0];return arguments[0]=this,t.apply(null,arguments),this}function H(){return Array.from(this)}function R(){for(var t=this._groups,e=0,n=t.length;e<n;++e)for(var r=t[e],i=0,o=r.length;i<o;++i){var a=r[i];if(a)return a}return null}function B(){let t=0;for(const e of this)++t;return t}function D(){return!this.node()}function I(t){for(var e=this._groups,n=0,r=e.length;n<r;++n)for(var i,o=e[n],a=0,u=o.length;a<u;++a)(i=o[a])&&t.call(i,i.__data__,a,o);return this}Object.defineProperty(k,"name",{value:"default",configurable:!0}),Object.defineProperty(P,"name",{value:"default",configurable:!0}),Object.defineProperty(M,"name",{value:"default",configurable:!0}),O.prototype={constructor:O,appendChild:function(t){return this._parent.insertBefore(t,this._next)},insertBefore:function(t,e){return this._parent.insertBefore(t,e)},querySelector:function(t){return this._parent.querySelector(t)},querySelectorAll:function(t){return this._parent.querySelectorAll(t)}},Object.defineProperty(j,"name",{value:"default",configurable:!0}),Object.defineProperty(E,"name",{value:"default",configurable:!0}),Object.defineProperty(S,"name",{value:"default",configurable:!0}),Object.defineProperty(T,"name",{value:"default",configurable:!0}),Object.defineProperty(C,"name",{value:"default",configurable:!0}),Object.defineProperty(X,"name",{value:"default",configurable:!0}),Object.defineProperty(q,"name",{value:"default",configurable:!0}),Object.defineProperty(Y,"name",{value:"default",configurable:!0}),Object.defineProperty(H,"name",{value:"default",configurable:!0}),Object.defineProperty(R,"name",{value:"default",configurable:!0}),Object.defineProperty(B,"name",{value:"default",configurable:!0}),Object.defineProperty(D,"name",{value:"default",configurable:!0}),Object.defineProperty(I,"name",{value:"default",configurable:!0});var F="http://www.w3.org/1999/xhtml";const L={svg:"http://www.w3.org/2000/svg",xhtml:F,xlink:"http://www.w3.org/1999/xlink",xml:"http://www.w3.org/XML/1998/namespace",xmlns:"http://www.w3.org/2000/xmlns/"};function K(t){var e=t+="",n=e.indexOf(":");return n>=0&&"xmlns"!==(e=t.slice(0,n))&&(t=t.slice(n+1)),L.hasOwnProperty(e)?{space:L[e],local:t}:t}function G(t){return function(){this.removeAttribute(t)}}function U(t){return function(){this.removeAttributeNS(t.space,t.local)}}function W(t,e){return function(){this.setAttribute(t,e)}}function Z(t,e){return function(){this.setAttributeNS(t.space,t.local,e)}}function J(t,e){return function(){var n=e.apply(this,arguments);null==n?this.removeAttribute(t):this.setAttribute(t,n)}}function Q(t,e){return function(){var n=e.apply(this,arguments);null==n?this.removeAttributeNS(t.space,t.local):this.setAttributeNS(t.space,t.local,n)}}function tt(t,e){var n=K(t);if(arguments.length<2){var r=this.node();return n.local?r.getAttributeNS(n.space,n.local):r.getAttribute(n)}return this.each((null==e?n.local?U:G:"function"==typeof e?n.local?Q:J:n.local?Z:W)(n,e))}function et(t){return t.ownerDocument&&t.ownerDocument.defaultView||t.document&&t||t.defaultView}function nt(t){return function(){this.style.removeProperty(t)}}function rt(t,e,n){return function(){this.style.setProperty(t,e,n)}}function it(t,e,n){return function(){var r=e.apply(this,arguments);null==r?this.style.removeProperty(t):this.style.setProperty(t,r,n)}}function ot(t,e,n){return arguments.length>1?this.each((null==e?nt:"function"==typeof e?it:rt)(t,e,n??"")):at(this.node(),t)}function at(t,e){return t.style.getPropertyValue(e)||et(t).getComputedStyle(t,null).getPropertyValue(e)}function ut(t){return function(){delete this[t]}}function lt(t,e){return function(){this[t]=e}}function st(t,e){return function(){var n=e.apply(this,arguments);null==n?delete this[t]:this[t]=n}}function ct(t,e){return arguments.length>1?this.each((null==e?ut:"function"==typeof e?st:lt)(t,e)):this.node()[t]}function ft(t){return t.trim().split(/^|\s+/)}function ht(t){return t.classList||new pt(t)}function pt(t){this._node=t,this._names=ft(t.getAttribute("class")||"")}function dt(t,e){for(var n=ht(t),r=-1,i=e.length;++r<i;)n.add(e[r])}function yt(t,e){for(var n=ht(t),r=-1,i=e.length;++r<i;)n.remove(e[r])}function mt(t){return function(){dt(this,t)}}function vt(t){return function(){yt(this,t)}}function gt(t,e){return function(){(e.apply(this,arguments)?dt:yt)(this,t)}}function bt(t,e){var n=ft(t+"");if(arguments.length<2){for(var r=ht(this.node()),i=-1,o=n.length;++i<o;)if(!r.contains(n[i]))return!1;return!0}return this.each(("function"==typeof e?gt:e?mt:vt)(n,e))}function wt(){this.textContent=""}function _t(t){return function(){this.textContent=t}}function xt(t){return function(){var e=t.apply(this,arguments);this.textContent=e??""}}function kt(t){return arguments.length?this.each(null==t?wt:("function"==typeof t?xt:_t)(t)):this.node().textContent}function Pt(){this.innerHTML=""}function Mt(t){return function(){this.innerHTML=t}}function Ot(t){return function(){var e=t.apply(this,arguments);this.innerHTML=e??""}}function jt(t){return arguments.length?this.each(null==t?Pt:("function"==typeof t?Ot:Mt)(t)):this.node().innerHTML}function At(){this.nextSibling&&this.parentNode.appendChild(this)}function Nt(){return this.each(At)}function zt(){this.previousSibling&&this.parentNode.insertBefore(this,this.parentNode.firstChild)}function Et(){return this.each(zt)}function $t(t){return function(){var e=this.ownerDocument,n=this.namespaceURI;return n===F&&e.documentElement.namespaceURI===F?e.createElement(t):e.createElementNS(n,t)}}function St(t){return function(){return this.ownerDocument.createElementNS(t.space,t.local)}}function Tt(t){var e=K(t);return(e.local?St:$t)(e)}function Ct(t){var e="function"==typeof t?t:Tt(t);return this.select(function(){return this.appendChild(e.apply(this,arguments))})}function Xt(){return null}function qt(t,e){var n="function"==typeof t?t:Tt(t),r=null==e?Xt:"function"==typeof e?e:c(e);return this.select(function(){return this.insertBefore(n.apply(this,arguments),r.apply(this,arguments)||null)})}function Vt(){var t=this.parentNode;t&&t.removeChild(this)}function Yt(){return this.each(Vt)}function Ht(){var t=this.cloneNode(!1),e=this.parentNode;return e?e.insertBefore(t,this.nextSibling):t}function Rt(){var t=this.cloneNode(!0),e=this.parentNode;return e?e.insertBefore(t,this.nextSibling):t}function Bt(t){return this.select(t?Rt:Ht)}function Dt(t){return arguments.length?this.property("__data__",t):this.node().__data__}function It(t){return function(){var e=this.__on;if(e){for(var n,r=0,i=-1,o=e.length;r<o;++r)n=e[r],t.type&&n.type!==t.type||n.name!==t.name?e[++i]=n:this.removeEventListener(n.type,n.listener,n.options);++i?e.length=i:delete this.__on}}}function Ft(t,e,n){return function(){var r,i=this.__on,o=function(t){return function(e){t.call(this,e,this.__data__)}}(e);if(i)for(var a=0,u=i.length;a<u;++a)if((r=i[a]).type===t.type&&r.name===t.name)return this.removeEventListener(r.type,r.listener,r.options),this.addEventListener(r.type,r.listener=o,r.options=n),void(r.value=e);this.addEventListener(t.type,o,n),r={type:t.type,name:t.name,value:e,listener:o,options:n},i?i.push(r):this.__on=[r]}}function Lt(t,e,n){var r,i,o=function(t){return t.trim().split(/^|\s+/).map(function(t){var e="",n=t.indexOf(".");return n>=0&&(e=t.slice(n+1),t=t.slice(0,n)),{type:t,name:e}})}(t+""),a=o.length;if(!(arguments.length<2)){for(u=e?Ft:It,r=0;r<a;++r)this.each(u(o[r],e,n));return this}var u=this.node().__on;if(u)for(var l,s=0,c=u.length;s<c;++s)for(r=0,l=u[s];r<a;++r)if((i=o[r]).type===l.type&&i.name===l.name)return l.value}function Kt(t,e,n){var r=et(t),i=r.CustomEvent;"function"==typeof i?i=new i(e,n):(i=r.document.createEvent("Event"),n?(i.initEvent(e,n.bubbles,n.cancelable),i.detail=n.detail):i.initEvent(e,!1,!1)),t.dispatchEvent(i)}function Gt(t,e){return function(){return Kt(this,t,e)}}function Ut(t,e){return function(){return Kt(this,t,e.apply(this,arguments))}}function Wt(t,e){return this.each(("function"==typeof e?Ut:Gt)(t,e))}function*Zt(){for(var t=this._groups,e=0,n=t.length;e<n;++e)for(var r,i=t[e],o=0,a=i.length;o<a;++o)(r=i[o])&&(yield r)}Object.defineProperty(K,"name",{value:"default",configurable:!0}),Object.defineProperty(tt,"name",{value:"default",configurable:!0}),Object.defineProperty(et,"name",{value:"default",configurable:!0}),Object.defineProperty(ot,"name",{value:"default",configurable:!0}),Object.defineProperty(ct,"name",{value:"default",configurable:!0}),Object.defineProperty(bt,"name",{value:"default",configurable:!0}),pt.prototype={add:function(t){this._names.indexOf(t)<0&&(this._names.push(t),this._node.setAttribute("class",this._names.join(" ")))},remove:function(t){var e=this._names.indexOf(t);e>=0&&(this._names.splice(e,1),this._node.setAttribute("class",this._names.join(" ")))},contains:function(t){return this._names.indexOf(t)>=0}},Object.defineProperty(kt,"name",{value:"default",configurable:!0}),Object.defineProperty(jt,"name",{value:"default",configurable:!0}),Object.defineProperty(Nt,"name",{value:"default",configurable:!0}),Object.defineProperty(Et,"name",{value:"default",configurable:!0}),Object.defineProperty(Tt,"name",{value:"default",configurable:!0}),Object.defineProperty(Ct,"name",{value:"default",configurable:!0}),Object.defineProperty(qt,"name",{value:"default",configurable:!0}),Object.defineProperty(Yt,"name",{value:"default",configurable:!0}),Object.defineProperty(Bt,"name",{value:"default",configurable:!0}),Object.defineProperty(Dt,"name",{value:"default",configurable:!0}),Object.defineProperty(Lt,"name",{value:"default",configurable:!0}),Object.defineProperty(Wt,"name",{value:"default",configurable:!0}),Object.defineProperty(Zt,"name",{value:"default",configurable:!0});var Jt=[null];function Qt(t,e){this._groups=t,this._parents=e}function te(){return new Qt([[document.documentElement]],Jt)}Qt.prototype=te.prototype={constructor:Qt,select:f,selectAll:d,selectChild:b,selectChildren:x,filter:k,data:E,enter:M,exit:S,join:T,merge:C,selection:function(){return this},order:X,sort:q,call:Y,nodes:H,node:R,size:B,empty:D,each:I,attr:tt,style:ot,property:ct,classed:bt,text:kt,html:jt,raise:Nt,lower:Et,append:Ct,insert:qt,remove:Yt,clone:Bt,datum:Dt,on:Lt,dispatch:Wt,[Symbol.iterator]:Zt};const ee=te;var ne={value:()=>{}};function re(){for(var t,e=0,n=arguments.length,r={};e<n;++e){if(!(t=arguments[e]+"")||t in r||/[\s.]/.test(t))throw new Error("illegal type: "+t);r[t]=[]}return new ie(r)}function ie(t){this._=t}function oe(t,e){for(var n,r=0,i=t.length;r<i;++r)if((n=t[r]).name===e)return n.value}function ae(t,e,n){for(var r=0,i=t.length;r<i;++r)if(t[r].name===e){t[r]=ne,t=t.slice(0,r).concat(t.slice(r+1));break}return null!=n&&t.push({name:e,value:n}),t}ie.prototype=re.prototype={constructor:ie,on:function(t,e){var n,r,i=this._,o=(r=i,(t+"").trim().split(/^|\s+/).map(function(t){var e="",n=t.indexOf(".");if(n>=0&&(e=t.slice(n+1),t=t.slice(0,n)),t&&!r.hasOwnProperty(t))throw new Error("unknown type: "+t);return{type:t,name:e}})),a=-1,u=o.length;if(!(arguments.length<2)){if(null!=e&&"function"!=typeof e)throw new Error("invalid callback: "+e);for(;++a<u;)if(n=(t=o[a]).type)i[n]=ae(i[n],t.name,e);else if(null==e)for(n in i)i[n]=ae(i[n],t.name,null);return this}for(;++a<u;)if((n=(t=o[a]).type)&&(n=oe(i[n],t.name)))return n},copy:function(){var t={},e=this._;for(var n in e)t[n]=e[n].slice();return new ie(t)},call:function(t,e){if((n=arguments.length-2)>0)for(var n,r,i=new Array(n),o=0;o<n;++o)i[o]=arguments[o+2];if(!this._.hasOwnProperty(t))throw new Error("unknown type: "+t);for(o=0,n=(r=this._[t]).length;o<n;++o)r[o].value.apply(e,i)},apply:function(t,e,n){if(!this._.hasOwnProperty(t))throw new Error("unknown type: "+t);for(var r=this._[t],i=0,o=r.length;i<o;++i)r[i].value.apply(e,n)}};const ue=re;var le,se,ce=0,fe=0,he=0,pe=0,de=0,ye=0,me="object"==typeof performance&&performance.now?performance:Date,ve="object"==typeof window&&window.requestAnimationFrame?window.requestAnimationFrame.bind(window):function(t){setTimeout(t,17)};function ge(){return de||(ve(be),de=me.now()+ye)}function be(){de=0}function we(){this._call=this._time=this._next=null}function _e(t,e,n){var r=new we;return r.restart(t,e,n),r}function xe(){de=(pe=me.now())+ye,ce=fe=0;try{!function(){ge(),++ce;for(var t,e=le;e;)(t=de-e._time)>=0&&e._call.call(void 0,t),e=e._next;--ce}()}finally{ce=0,function(){for(var t,e,n=le,r=1/0;n;)n._call?(r>n._time&&(r=n._time),t=n,n=n._next):(e=n._next,n._next=null,n=t?t._next=e:le=e);se=t,Pe(r)}(),de=0}}function ke(){var t=me.now(),e=t-pe;e>1e3&&(ye-=e,pe=t)}function Pe(t){ce||(fe&&(fe=clearTimeout(fe)),t-de>24?(t<1/0&&(fe=setTimeout(xe,t-me.now()-ye)),he&&(he=clearInterval(he))):(he||(pe=me.now(),he=setInterval(ke,1e3)),ce=1,ve(xe)))}function Me(t,e,n){var r=new we;return e=null==e?0:+e,r.restart(n=>{r.stop(),t(n+e)},e,n),r}we.prototype=_e.prototype={constructor:we,restart:function(t,e,n){if("function"!=typeof t)throw new TypeError("callback is not a function");n=(null==n?ge():+n)+(null==e?0:+e),this._next||se===this||(se?se._next=this:le=this,se=this),this._call=t,this._time=n,Pe()},stop:function(){this._call&&(this._call=null,this._time=1/0,Pe())}},Object.defineProperty(Me,"name",{value:"default",configurable:!0}),Object.defineProperty(Ce,"name",{value:"default",configurable:!0});var Oe=ue("start","end","cancel","interrupt"),je=[],Ae=0,Ne=1,ze=2,Ee=3,$e=4,Se=5,Te=6;function Ce(t,e,n,r,i,o){var a=t.__transition;if(a){if(n in a)return}else t.__transition={};!function(t,e,n){var r,i=t.__transition;function o(l){var s,c,f,h;if(n.state!==Ne)return u();for(s in i)if((h=i[s]).name===n.name){if(h.state===Ee)return Me(o);h.state===$e?(h.state=Te,h.timer.stop(),h.on.call("interrupt",t,t.__data__,h.index,h.group),delete i[s]):+s<e&&(h.state=Te,h.timer.stop(),h.on.call("cancel",t,t.__data__,h.index,h.group),delete i[s])}if(Me(function(){n.state===Ee&&(n.state=$e,n.timer.restart(a,n.delay,n.time),a(l))}),n.state=ze,n.on.call("start",t,t.__data__,n.index,n.group),n.state===ze){for(n.state=Ee,r=new Array(f=n.tween.length),s=0,c=-1;s<f;++s)(h=n.tween[s].value.call(t,t.__data__,n.index,n.group))&&(r[++c]=h);r.length=c+1}}function a(e){for(var i=e<n.duration?n.ease.call(null,e/n.duration):(n.timer.restart(u),n.state=Se,1),o=-1,a=r.length;++o<a;)r[o].call(t,i);n.state===Se&&(n.on.call("end",t,t.__data__,n.index,n.group),u())}function u(){for(var r in n.state=Te,n.timer.stop(),delete i[e],i)return;delete t.__transition}i[e]=n,n.timer=_e(function(t){n.state=Ne,n.timer.restart(o,n.delay,n.time),n.delay<=t&&o(t-n.delay)},0,n.time)}(t,n,{name:e,index:r,group:i,on:Oe,tween:je,time:o.time,delay:o.delay,duration:o.duration,ease:o.ease,timer:null,state:Ae})}function Xe(t,e){var n=Ve(t,e);if(n.state>Ae)throw new Error("too late; already scheduled");return n}function qe(t,e){var n=Ve(t,e);if(n.state>Ee)throw new Error("too late; already running");return n}function Ve(t,e){var n=t.__transition;if(!n||!(n=n[e]))throw new Error("transition not found");return n}function Ye(t,e){var n,r,i,o=t.__transition,a=!0;if(o){for(i in e=null==e?null:e+"",o)(n=o[i]).name===e?(r=n.state>ze&&n.state<Se,n.state=Te,n.timer.stop(),n.on.call(r?"interrupt":"cancel",t,t.__data__,n.index,n.group),delete o[i]):a=!1;a&&delete t.__transition}}function He(t){return this.each(function(){Ye(this,t)})}function Re(t,e){return t=+t,e=+e,function(n){return t*(1-n)+e*n}}Object.defineProperty(Ye,"name",{value:"default",configurable:!0}),Object.defineProperty(He,"name",{value:"default",configurable:!0}),Object.defineProperty(Re,"name",{value:"default",configurable:!0}),Object.defineProperty(Fe,"name",{value:"default",configurable:!0});var Be,De=180/Math.PI,Ie={translateX:0,translateY:0,rotate:0,skewX:0,scaleX:1,scaleY:1};function Fe(t,e,n,r,i,o){var a,u,l;return(a=Math.sqrt(t*t+e*e))&&(t/=a,e/=a),(l=t*n+e*r)&&(n-=t*l,r-=e*l),(u=Math.sqrt(n*n+r*r))&&(n/=u,r/=u,l/=u),t*r<e*n&&(t=-t,e=-e,l=-l,a=-a),{translateX:i,translateY:o,rotate:Math.atan2(e,t)*De,skewX:Math.atan(l)*De,scaleX:a,scaleY:u}}function Le(t,e,n,r){function i(t){return t.length?t.pop()+" ":""}return function(o,a){var u=[],l=[];return o=t(o),a=t(a),function(t,r,i,o,a,u){if(t!==i||r!==o){var l=a.push("translate(",null,e,null,n);u.push({i:l-4,x:Re(t,i)},{i:l-2,x:Re(r,o)})}else(i||o)&&a.push("translate("+i+e+o+n)}(o.translateX,o.translateY,a.translateX,a.translateY,u,l),function(t,e,n,o){t!==e?(t-e>180?e+=360:e-t>180&&(t+=360),o.push({i:n.push(i(n)+"rotate(",null,r)-2,x:Re(t,e)})):e&&n.push(i(n)+"rotate("+e+r)}(o.rotate,a.rotate,u,l),function(t,e,n,o){t!==e?o.push({i:n.push(i(n)+"skewX(",null,r)-2,x:Re(t,e)}):e&&n.push(i(n)+"skewX("+e+r)}(o.skewX,a.skewX,u,l),function(t,e,n,r,o,a){if(t!==n||e!==r){var u=o.push(i(o)+"scale(",null,",",null,")");a.push({i:u-4,x:Re(t,n)},{i:u-2,x:Re(e,r)})}else 1===n&&1===r||o.push(i(o)+"scale("+n+","+r+")")}(o.scaleX,o.scaleY,a.scaleX,a.scaleY,u,l),o=a=null,function(t){for(var e,n=-1,r=l.length;++n<r;)u[(e=l[n]).i]=e.x(t);return u.join("")}}}var Ke=Le(function(t){const e=new("function"==typeof DOMMatrix?DOMMatrix:WebKitCSSMatrix)(t+"");return e.isIdentity?Ie:Fe(e.a,e.b,e.c,e.d,e.e,e.f)},"px, ","px)","deg)"),Ge=Le(function(t){return null==t?Ie:(Be||(Be=document.createElementNS("http://www.w3.org/2000/svg","g")),Be.setAttribute("transform",t),(t=Be.transform.baseVal.consolidate())?Fe((t=t.matrix).a,t.b,t.c,t.d,t.e,t.f):Ie)},", ",")",")");function Ue(t,e){var n,r;return function(){var i=qe(this,t),o=i.tween;if(o!==n)for(var a=0,u=(r=n=o).length;a<u;++a)if(r[a].name===e){(r=r.slice()).splice(a,1);break}i.tween=r}}function We(t,e,n){var r,i;if("function"!=typeof n)throw new Error;return function(){var o=qe(this,t),a=o.tween;if(a!==r){i=(r=a).slice();for(var u={name:e,value:n},l=0,s=i.length;l<s;++l)if(i[l].name===e){i[l]=u;break}l===s&&i.push(u)}o.tween=i}}function Ze(t,e){var n=this._id;if(t+="",arguments.length<2){for(var r,i=Ve(this.node(),n).tween,o=0,a=i.length;o<a;++o)if((r=i[o]).name===t)return r.value;return null}return this.each((null==e?Ue:We)(n,t,e))}function Je(t,e,n){var r=t._id;return t.each(function(){var t=qe(this,r);(t.value||(t.value={}))[e]=n.apply(this,arguments)}),function(t){return Ve(t,r).value[e]}}function Qe(t,e,n){t.prototype=e.prototype=n,n.constructor=t}function tn(t,e){var n=Object.create(t.prototype);for(var r in e)n[r]=e[r];return n}function en(){}Object.defineProperty(Ze,"name",{value:"default",configurable:!0}),Object.defineProperty(Qe,"name",{value:"default",configurable:!0});var nn=.7,rn=1/nn,on="\\s*([+-]?\\d+)\\s*",an="\\s*([+-]?(?:\\d*\\.)?\\d+(?:[eE][+-]?\\d+)?)\\s*",un="\\s*([+-]?(?:\\d*\\.)?\\d+(?:[eE][+-]?\\d+)?)%\\s*",ln=/^#([0-9a-f]{3,8})$/,sn=new RegExp(`^rgb\\(${on},${on},${on}\\)$`),cn=new RegExp(`^rgb\\(${un},${un},${un}\\)$`),fn=new RegExp(`^rgba\\(${on},${on},${on},${an}\\)$`),hn=new RegExp(`^rgba\\(${un},${un},${un},${an}\\)$`),pn=new RegExp(`^hsl\\(${an},${un},${un}\\)$`),dn=new RegExp(`^hsla\\(${an},${un},${un},${an}\\)$`),yn={aliceblue:15792383,antiquewhite:16444375,aqua:65535,aquamarine:8388564,azure:15794175,beige:16119260,bisque:16770244,black:0,blanchedalmond:16772045,blue:255,blueviolet:9055202,brown:10824234,burlywood:14596231,cadetblue:6266528,chartreuse:8388352,chocolate:13789470,coral:16744272,cornflowerblue:6591981,cornsilk:16775388,crimson:14423100,cyan:65535,darkblue:139,darkcyan:35723,darkgoldenrod:12092939,darkgray:11119017,darkgreen:25600,darkgrey:11119017,darkkhaki:12433259,darkmagenta:9109643,darkolivegreen:5597999,darkorange:16747520,darkorchid:10040012,darkred:9109504,darksalmon:15308410,darkseagreen:9419919,darkslateblue:4734347,darkslategray:3100495,darkslategrey:3100495,darkturquoise:52945,darkviolet:9699539,deeppink:16716947,deepskyblue:49151,dimgray:6908265,dimgrey:6908265,dodgerblue:2003199,firebrick:11674146,floralwhite:16775920,forestgreen:2263842,fuchsia:16711935,gainsboro:14474460,ghostwhite:16316671,gold:16766720,goldenrod:14329120,gray:8421504,green:32768,greenyellow:11403055,grey:8421504,honeydew:15794160,hotpink:16738740,indianred:13458524,indigo:4915330,ivory:16777200,khaki:15787660,lavender:15132410,lavenderblush:16773365,lawngreen:8190976,lemonchiffon:16775885,lightblue:11393254,lightcoral:15761536,lightcyan:14745599,lightgoldenrodyellow:16448210,lightgray:13882323,lightgreen:9498256,lightgrey:13882323,lightpink:16758465,lightsalmon:16752762,lightseagreen:2142890,lightskyblue:8900346,lightslategray:7833753,lightslategrey:7833753,lightsteelblue:11584734,lightyellow:16777184,lime:65280,limegreen:3329330,linen:16445670,magenta:16711935,maroon:8388608,mediumaquamarine:6737322,mediumblue:205,mediumorchid:12211667,mediumpurple:9662683,mediumseagreen:3978097,mediumslateblue:8087790,mediumspringgreen:64154,mediumturquoise:4772300,mediumvioletred:13047173,midnightblue:1644912,mintcream:16121850,mistyrose:16770273,moccasin:16770229,navajowhite:16768685,navy:128,oldlace:16643558,olive:8421376,olivedrab:7048739,orange:16753920,orangered:16729344,orchid:14315734,palegoldenrod:15657130,palegreen:10025880,paleturquoise:11529966,palevioletred:14381203,papayawhip:16773077,peachpuff:16767673,peru:13468991,pink:16761035,plum:14524637,powderblue:11591910,purple:8388736,rebeccapurple:6697881,red:16711680,rosybrown:12357519,royalblue:4286945,saddlebrown:9127187,salmon:16416882,sandybrown:16032864,seagreen:3050327,seashell:16774638,sienna:10506797,silver:12632256,skyblue:8900331,slateblue:6970061,slategray:7372944,slategrey:7372944,snow:16775930,springgreen:65407,steelblue:4620980,tan:13808780,teal:32896,thistle:14204888,tomato:16737095,turquoise:4251856,violet:15631086,wheat:16113331,white:16777215,whitesmoke:16119285,yellow:16776960,yellowgreen:10145074};function mn(){return this.rgb().formatHex()}function vn(){return this.rgb().formatRgb()}function gn(t){var e,n;return t=(t+"").trim().toLowerCase(),(e=ln.exec(t))?(n=e[1].length,e=parseInt(e[1],16),6===n?bn(e):3===n?new xn(e>>8&15|e>>4&240,e>>4&15|240&e,(15&e)<<4|15&e,1):8===n?wn(e>>24&255,e>>16&255,e>>8&255,(255&e)/255):4===n?wn(e>>12&15|e>>8&240,e>>8&15|e>>4&240,e>>4&15|240&e,((15&e)<<4|15&e)/255):null):(e=sn.exec(t))?new xn(e[1],e[2],e[3],1):(e=cn.exec(t))?new xn(255*e[1]/100,255*e[2]/100,255*e[3]/100,1):(e=fn.exec(t))?wn(e[1],e[2],e[3],e[4]):(e=hn.exec(t))?wn(255*e[1]/100,255*e[2]/100,255*e[3]/100,e[4]):(e=pn.exec(t))?An(e[1],e[2]/100,e[3]/100,1):(e=dn.exec(t))?An(e[1],e[2]/100,e[3]/100,e[4]):yn.hasOwnProperty(t)?bn(yn[t]):"transparent"===t?new xn(NaN,NaN,NaN,0):null}function bn(t){return new xn(t>>16&255,t>>8&255,255&t,1)}function wn(t,e,n,r){return r<=0&&(t=e=n=NaN),new xn(t,e,n,r)}function _n(t,e,n,r){return 1===arguments.length?((i=t)instanceof en||(i=gn(i)),i?new xn((i=i.rgb()).r,i.g,i.b,i.opacity):new xn):new xn(t,e,n,r??1);var i}function xn(t,e,n,r){this.r=+t,this.g=+e,this.b=+n,this.opacity=+r}function kn(){return`#${jn(this.r)}${jn(this.g)}${jn(this.b)}`}function Pn(){const t=Mn(this.opacity);return`${1===t?"rgb(":"rgba("}${On(this.r)}, ${On(this.g)}, ${On(this.b)}${1===t?")":`, ${t})`}`}function Mn(t){return isNaN(t)?1:Math.max(0,Math.min(1,t))}function On(t){return Math.max(0,Math.min(255,Math.round(t)||0))}function jn(t){return((t=On(t))<16?"0":"")+t.toString(16)}function An(t,e,n,r){return r<=0?t=e=n=NaN:n<=0||n>=1?t=e=NaN:e<=0&&(t=NaN),new zn(t,e,n,r)}function Nn(t){if(t instanceof zn)return new zn(t.h,t.s,t.l,t.opacity);if(t instanceof en||(t=gn(t)),!t)return new zn;if(t instanceof zn)return t;var e=(t=t.rgb()).r/255,n=t.g/255,r=t.b/255,i=Math.min(e,n,r),o=Math.max(e,n,r),a=NaN,u=o-i,l=(o+i)/2;return u?(a=e===o?(n-r)/u+6*(n<r):n===o?(r-e)/u+2:(e-n)/u+4,u/=l<.5?o+i:2-o-i,a*=60):u=l>0&&l<1?0:a,new zn(a,u,l,t.opacity)}function zn(t,e,n,r){this.h=+t,this.s=+e,this.l=+n,this.opacity=+r}function En(t){return(t=(t||0)%360)<0?t+360:t}function $n(t){return Math.max(0,Math.min(1,t||0))}function Sn(t,e,n){return 255*(t<60?e+(n-e)*t/60:t<180?n:t<240?e+(n-e)*(240-t)/60:e)}function Tn(t,e,n,r,i){var o=t*t,a=o*t;return((1-3*t+3*o-a)*e+(4-6*o+3*a)*n+(1+3*t+3*o-3*a)*r+a*i)/6}function Cn(t){var e=t.length-1;return function(n){var r=n<=0?n=0:n>=1?(n=1,e-1):Math.floor(n*e),i=t[r],o=t[r+1],a=r>0?t[r-1]:2*i-o,u=r<e-1?t[r+2]:2*o-i;return Tn((n-r/e)*e,a,i,o,u)}}function Xn(t){var e=t.length;return function(n){var r=Math.floor(((n%=1)<0?++n:n)*e),i=t[(r+e-1)%e],o=t[r%e],a=t[(r+1)%e],u=t[(r+2)%e];return Tn((n-r/e)*e,i,o,a,u)}}Qe(en,gn,{copy(t){return Object.assign(new this.constructor,this,t)},displayable(){return this.rgb().displayable()},hex:mn,formatHex:mn,formatHex8:function(){return this.rgb().formatHex8()},formatHsl:function(){return Nn(this).formatHsl()},formatRgb:vn,toString:vn}),Qe(xn,_n,tn(en,{brighter(t){return t=null==t?rn:Math.pow(rn,t),new xn(this.r*t,this.g*t,this.b*t,this.opacity)},darker(t){return t=null==t?nn:Math.pow(nn,t),new xn(this.r*t,this.g*t,this.b*t,this.opacity)},rgb(){return this},clamp(){return new xn(On(this.r),On(this.g),On(this.b),Mn(this.opacity))},displayable(){return-.5<=this.r&&this.r<255.5&&-.5<=this.g&&this.g<255.5&&-.5<=this.b&&this.b<255.5&&0<=this.opacity&&this.opacity<=1},hex:kn,formatHex:kn,formatHex8:function(){return`#${jn(this.r)}${jn(this.g)}${jn(this.b)}${jn(255*(isNaN(this.opacity)?1:this.opacity))}`},formatRgb:Pn,toString:Pn})),Qe(zn,function(t,e,n,r){return 1===arguments.length?Nn(t):new zn(t,e,n,r??1)},tn(en,{brighter(t){return t=null==t?rn:Math.pow(rn,t),new zn(this.h,this.s,this.l*t,this.opacity)},darker(t){return t=null==t?nn:Math.pow(nn,t),new zn(this.h,this.s,this.l*t,this.opacity)},rgb(){var t=this.h%360+360*(this.h<0),e=isNaN(t)||isNaN(this.s)?0:this.s,n=this.l,r=n+(n<.5?n:1-n)*e,i=2*n-r;return new xn(Sn(t>=240?t-240:t+120,i,r),Sn(t,i,r),Sn(t<120?t+240:t-120,i,r),this.opacity)},clamp(){return new zn(En(this.h),$n(this.s),$n(this.l),Mn(this.opacity))},displayable(){return(0<=this.s&&this.s<=1||isNaN(this.s))&&0<=this.l&&this.l<=1&&0<=this.opacity&&this.opacity<=1},formatHsl(){const t=Mn(this.opacity);return`${1===t?"hsl(":"hsla("}${En(this.h)}, ${100*$n(this.s)}%, ${100*$n(this.l)}%${1===t?")":`, ${t})`}`}})),Object.defineProperty(Cn,"name",{value:"default",configurable:!0}),Object.defineProperty(Xn,"name",{value:"default",configurable:!0});const qn=t=>()=>t;function Vn(t,e){var n=e-t;return n?function(t,e){return function(n){return t+n*e}}(t,n):qn(isNaN(t)?e:t)}(Object.getOwnPropertyDescriptor(qn,"name")||{}).writable||Object.defineProperty(qn,"name",{value:"default",configurable:!0});const Yn=function t(e){var n=function(t){return 1===(t=+t)?Vn:function(e,n){return n-e?function(t,e,n){return t=Math.pow(t,n),e=Math.pow(e,n)-t,n=1/n,function(r){return Math.pow(t+r*e,n)}}(e,n,t):qn(isNaN(e)?n:e)}}(e);function r(t,e){var r=n((t=_n(t)).r,(e=_n(e)).r),i=n(t.g,e.g),o=n(t.b,e.b),a=Vn(t.opacity,e.opacity);return function(e){return t.r=r(e),t.g=i(e),t.b=o(e),t.opacity=a(e),t+""}}return r.gamma=t,r}(1);function Hn(t){return function(e){var n,r,i=e.length,o=new Array(i),a=new Array(i),u=new Array(i);for(n=0;n<i;++n)r=_n(e[n]),o[n]=r.r||0,a[n]=r.g||0,u[n]=r.b||0;return o=t(o),a=t(a),u=t(u),r.opacity=1,function(t){return r.r=o(t),r.g=a(t),r.b=u(t),r+""}}}Hn(Cn),Hn(Xn),Object.defineProperty(Dn,"name",{value:"default",configurable:!0});var Rn=/[-+]?(?:\d+\.?\d*|\.?\d+)(?:[eE][-+]?\d+)?/g,Bn=new RegExp(Rn.source,"g");function Dn(t,e){var n,r,i,o=Rn.lastIndex=Bn.lastIndex=0,a=-1,u=[],l=[];for(t+="",e+="";(n=Rn.exec(t))&&(r=Bn.exec(e));)(i=r.index)>o&&(i=e.slice(o,i),u[a]?u[a]+=i:u[++a]=i),(n=n[0])===(r=r[0])?u[a]?u[a]+=r:u[++a]=r:(u[++a]=null,l.push({i:a,x:Re(n,r)})),o=Bn.lastIndex;return o<e.length&&(i=e.slice(o),u[a]?u[a]+=i:u[++a]=i),u.length<2?l[0]?function(t){return function(e){return t(e)+""}}(l[0].x):function(t){return function(){return t}}(e):(e=l.length,function(t){for(var n,r=0;r<e;++r)u[(n=l[r]).i]=n.x(t);return u.join("")})}function In(t,e){var n;return("number"==typeof e?Re:e instanceof gn?Yn:(n=gn(e))?(e=n,Yn):Dn)(t,e)}function Fn(t){return function(){this.removeAttribute(t)}}function Ln(t){return function(){this.removeAttributeNS(t.space,t.local)}}function Kn(t,e,n){var r,i,o=n+"";return function(){var a=this.getAttribute(t);return a===o?null:a===r?i:i=e(r=a,n)}}function Gn(t,e,n){var r,i,o=n+"";return function(){var a=this.getAttributeNS(t.space,t.local);return a===o?null:a===r?i:i=e(r=a,n)}}function Un(t,e,n){var r,i,o;return function(){var a,u,l=n(this);if(null!=l)return(a=this.getAttribute(t))===(u=l+"")?null:a===r&&u===i?o:(i=u,o=e(r=a,l));this.removeAttribute(t)}}function Wn(t,e,n){var r,i,o;return function(){var a,u,l=n(this);if(null!=l)return(a=this.getAttributeNS(t.space,t.local))===(u=l+"")?null:a===r&&u===i?o:(i=u,o=e(r=a,l));this.removeAttributeNS(t.space,t.local)}}function Zn(t,e){var n=K(t),r="transform"===n?Ge:In;return this.attrTween(t,"function"==typeof e?(n.local?Wn:Un)(n,r,Je(this,"attr."+t,e)):null==e?(n.local?Ln:Fn)(n):(n.local?Gn:Kn)(n,r,e))}function Jn(t,e){var n,r;function i(){var i=e.apply(this,arguments);return i!==r&&(n=(r=i)&&function(t,e){return function(n){this.setAttributeNS(t.space,t.local,e.call(this,n))}}(t,i)),n}return i._value=e,i}function Qn(t,e){var n,r;function i(){var i=e.apply(this,arguments);return i!==r&&(n=(r=i)&&function(t,e){return function(n){this.setAttribute(t,e.call(this,n))}}(t,i)),n}return i._value=e,i}function tr(t,e){var n="attr."+t;if(arguments.length<2)return(n=this.tween(n))&&n._value;if(null==e)return this.tween(n,null);if("function"!=typeof e)throw new Error;var r=K(t);return this.tween(n,(r.local?Jn:Qn)(r,e))}function er(t,e){return function(){Xe(this,t).delay=+e.apply(this,arguments)}}function nr(t,e){return e=+e,function(){Xe(this,t).delay=e}}function rr(t){var e=this._id;return arguments.length?this.each(("function"==typeof t?er:nr)(e,t)):Ve(this.node(),e).delay}function ir(t,e){return function(){qe(this,t).duration=+e.apply(this,arguments)}}function or(t,e){return e=+e,function(){qe(this,t).duration=e}}function ar(t){var e=this._id;return arguments.length?this.each(("function"==typeof t?ir:or)(e,t)):Ve(this.node(),e).duration}function ur(t){var e=this._id;return arguments.length?this.each(function(t,e){if("function"!=typeof e)throw new Error;return function(){qe(this,t).ease=e}}(e,t)):Ve(this.node(),e).ease}function lr(t){if("function"!=typeof t)throw new Error;return this.each(function(t,e){return function(){var n=e.apply(this,arguments);if("function"!=typeof n)throw new Error;qe(this,t).ease=n}}(this._id,t))}function sr(t){"function"!=typeof t&&(t=y(t));for(var e=this._groups,n=e.length,r=new Array(n),i=0;i<n;++i)for(var o,a=e[i],u=a.length,l=r[i]=[],s=0;s<u;++s)(o=a[s])&&t.call(o,o.__data__,s,a)&&l.push(o);return new Mr(r,this._parents,this._name,this._id)}function cr(t){if(t._id!==this._id)throw new Error;for(var e=this._groups,n=t._groups,r=e.length,i=n.length,o=Math.min(r,i),a=new Array(r),u=0;u<o;++u)for(var l,s=e[u],c=n[u],f=s.length,h=a[u]=new Array(f),p=0;p<f;++p)(l=s[p]||c[p])&&(h[p]=l);for(;u<r;++u)a[u]=e[u];return new Mr(a,this._parents,this._name,this._id)}function fr(t,e){var n=this._id;return arguments.length<2?Ve(this.node(),n).on.on(t):this.each(function(t,e,n){var r,i,o=function(t){return(t+"").trim().split(/^|\s+/).every(function(t){var e=t.indexOf(".");return e>=0&&(t=t.slice(0,e)),!t||"start"===t})}(e)?Xe:qe;return function(){var a=o(this,t),u=a.on;u!==r&&(i=(r=u).copy()).on(e,n),a.on=i}}(n,t,e))}function hr(){return this.on("end.remove",function(t){return function(){var e=this.parentNode;for(var n in this.__transition)if(+n!==t)return;e&&e.removeChild(this)}}(this._id))}function pr(t){var e=this._name,n=this._id;"function"!=typeof t&&(t=c(t));for(var r=this._groups,i=r.length,o=new Array(i),a=0;a<i;++a)for(var u,l,s=r[a],f=s.length,h=o[a]=new Array(f),p=0;p<f;++p)(u=s[p])&&(l=t.call(u,u.__data__,p,s))&&("__data__"in u&&(l.__data__=u.__data__),h[p]=l,Ce(h[p],e,n,p,h,Ve(u,n)));return new Mr(o,this._parents,e,n)}function dr(t){var e=this._name,n=this._id;"function"!=typeof t&&(t=p(t));for(var r=this._groups,i=r.length,o=[],a=[],u=0;u<i;++u)for(var l,s=r[u],c=s.length,f=0;f<c;++f)if(l=s[f]){for(var h,d=t.call(l,l.__data__,f,s),y=Ve(l,n),m=0,v=d.length;m<v;++m)(h=d[m])&&Ce(h,e,n,m,d,y);o.push(d),a.push(l)}return new Mr(o,a,e,n)}Object.defineProperty(In,"name",{value:"default",configurable:!0}),Object.defineProperty(Zn,"name",{value:"default",configurable:!0}),Object.defineProperty(tr,"name",{value:"default",configurable:!0}),Object.defineProperty(rr,"name",{value:"default",configurable:!0}),Object.defineProperty(ar,"name",{value:"default",configurable:!0}),Object.defineProperty(ur,"name",{value:"default",configurable:!0}),Object.defineProperty(lr,"name",{value:"default",configurable:!0}),Object.defineProperty(sr,"name",{value:"default",configurable:!0}),Object.defineProperty(cr,"name",{value:"default",configurable:!0}),Object.defineProperty(fr,"name",{value:"default",configurable:!0}),Object.defineProperty(hr,"name",{value:"default",configurable:!0}),Object.defineProperty(pr,"name",{value:"default",configurable:!0}),Object.defineProperty(dr,"name",{value:"default",configurable:!0}),Object.defineProperty(mr,"name",{value:"default",configurable:!0});var yr=ee.prototype.constructor;function mr(){return new yr(this._groups,this._parents)}function vr(t){return function(){this.style.removeProperty(t)}}function gr(t,e,n){var r="transform"==(t+="")?Ke:In;return null==e?this.styleTween(t,function(t,e){var n,r,i;return function(){var o=at(this,t),a=(this.style.removeProperty(t),at(this,t));return o===a?null:o===n&&a===r?i:i=e(n=o,r=a)}}(t,r)).on("end.style."+t,vr(t)):"function"==typeof e?this.styleTween(t,function(t,e,n){var r,i,o;return function(){var a=at(this,t),u=n(this),l=u+"";return null==u&&(this.style.removeProperty(t),l=u=at(this,t)),a===l?null:a===r&&l===i?o:(i=l,o=e(r=a,u))}}(t,r,Je(this,"style."+t,e))).each(function(t,e){var n,r,i,o,a="style."+e,u="end."+a;return function(){var l=qe(this,t),s=l.on,c=null==l.value[a]?o||(o=vr(e)):void 0;s===n&&i===c||(r=(n=s).copy()).on(u,i=c),l.on=r}}(this._id,t)):this.styleTween(t,function(t,e,n){var r,i,o=n+"";return function(){var a=at(this,t);return a===o?null:a===r?i:i=e(r=a,n)}}(t,r,e),n).on("end.style."+t,null)}function br(t,e,n){var r="style."+(t+="");if(arguments.length<2)return(r=this.tween(r))&&r._value;if(null==e)return this.tween(r,null);if("function"!=typeof e)throw new Error;return this.tween(r,function(t,e,n){var r,i;function o(){var o=e.apply(this,arguments);return o!==i&&(r=(i=o)&&function(t,e,n){return function(r){this.style.setProperty(t,e.call(this,r),n)}}(t,o,n)),r}return o._value=e,o}(t,e,n??""))}function wr(t){return this.tween("text","function"==typeof t?function(t){return function(){var e=t(this);this.textContent=e??""}}(Je(this,"text",t)):function(t){return function(){this.textContent=t}}(null==t?"":t+""))}function _r(t){var e="text";if(arguments.length<1)return(e=this.tween(e))&&e._value;if(null==t)return this.tween(e,null);if("function"!=typeof t)throw new Error;return this.tween(e,function(t){var e,n;function r(){var r=t.apply(this,arguments);return r!==n&&(e=(n=r)&&function(t){return function(e){this.textContent=t.call(this,e)}}(r)),e}return r._value=t,r}(t))}function xr(){for(var t=this._name,e=this._id,n=Or(),r=this._groups,i=r.length,o=0;o<i;++o)for(var a,u=r[o],l=u.length,s=0;s<l;++s)if(a=u[s]){var c=Ve(a,e);Ce(a,t,n,s,u,{time:c.time+c.delay+c.duration,delay:0,duration:c.duration,ease:c.ease})}return new Mr(r,this._parents,t,n)}function kr(){var t,e,n=this,r=n._id,i=n.size();return new Promise(function(o,a){var u={value:a},l={value:function(){0===--i&&o()}};n.each(function(){var n=qe(this,r),i=n.on;i!==t&&((e=(t=i).copy())._.cancel.push(u),e._.interrupt.push(u),e._.end.push(l)),n.on=e}),0===i&&o()})}Object.defineProperty(gr,"name",{value:"default",configurable:!0}),Object.defineProperty(br,"name",{value:"default",configurable:!0}),Object.defineProperty(wr,"name",{value:"default",configurable:!0}),Object.defineProperty(_r,"name",{value:"default",configurable:!0}),Object.defineProperty(xr,"name",{value:"default",configurable:!0}),Object.defineProperty(kr,"name",{value:"default",configurable:!0});var Pr=0;function Mr(t,e,n,r){this._groups=t,this._parents=e,this._name=n,this._id=r}function Or(){return++Pr}var jr=ee.prototype;Mr.prototype=function(t){return ee().transition(t)}.prototype={constructor:Mr,select:pr,selectAll:dr,selectChild:jr.selectChild,selectChildren:jr.selectChildren,filter:sr,merge:cr,selection:mr,transition:xr,call:jr.call,nodes:jr.nodes,node:jr.node,size:jr.size,empty:jr.empty,each:jr.each,on:fr,attr:Zn,attrTween:tr,style:gr,styleTween:br,text:wr,textTween:_r,remove:hr,tween:Ze,delay:rr,duration:ar,ease:ur,easeVarying:lr,end:kr,[Symbol.iterator]:jr[Symbol.iterator]},Object.defineProperty(Rr,"name",{value:"default",configurable:!0});var Ar,Nr,zr,Er,$r,Sr,Tr,Cr,Xr,qr,Vr,Yr={time:null,delay:0,duration:250,ease:function(t){return((t*=2)<=1?t*t*t:(t-=2)*t*t+2)/2}};function Hr(t,e){for(var n;!(n=t.__transition)||!(n=n[e]);)if(!(t=t.parentNode))throw new Error(`transition ${e} not found`);return n}function Rr(t){var e,n;t instanceof Mr?(e=t._id,t=t._name):(e=Or(),(n=Yr).time=ge(),t=null==t?null:t+"");for(var r=this._groups,i=r.length,o=0;o<i;++o)for(var a,u=r[o],l=u.length,s=0;s<l;++s)(a=u[s])&&Ce(a,t,e,s,u,n||Hr(a,e));return new Mr(r,this._parents,t,e)}ee.prototype.interrupt=He,ee.prototype.transition=Rr,Object.defineProperty(function(){return function(t){var e,n=ui,r=ai,i=li,o=!0,a=Ar("start","brush","end"),u=6;function l(e){var n=e.property("__brush",y).selectAll(".overlay").data([oi("overlay")]);n.enter().append("rect").attr("class","overlay").attr("pointer-events","all").attr("cursor",ti.overlay).merge(n).each(function(){var t=si(this).extent;Sr(this).attr("x",t[0][0]).attr("y",t[0][1]).attr("width",t[1][0]-t[0][0]).attr("height",t[1][1]-t[0][1])}),e.selectAll(".selection").data([oi("selection")]).enter().append("rect").attr("class","selection").attr("cursor",ti.selection).attr("fill","#777").attr("fill-opacity",.3).attr("stroke","#fff").attr("shape-rendering","crispEdges");var r=e.selectAll(".handle").data(t.handles,function(t){return t.type});r.exit().remove(),r.enter().append("rect").attr("class",function(t){return"handle handle--"+t.type}).attr("cursor",function(t){return ti[t.type]}),e.each(s).attr("fill","none").attr("pointer-events","all").on("mousedown.brush",h).filter(i).on("touchstart.brush",h).on("touchmove.brush",p).on("touchend.brush touchcancel.brush",d).style("touch-action","none").style("-webkit-tap-highlight-color","rgba(0,0,0,0)")}function s(){var t=Sr(this),e=si(this).selection;e?(t.selectAll(".selection").style("display",null).attr("x",e[0][0]).attr("y",e[0][1]).attr("width",e[1][0]-e[0][0]).attr("height",e[1][1]-e[0][1]),t.selectAll(".handle").style("display",null).attr("x",function(t){return"e"===t.type[t.type.length-1]?e[1][0]-u/2:e[0][0]-u/2}).attr("y",function(t){return"s"===t.type[0]?e[1][1]-u/2:e[0][1]-u/2}).attr("width",function(t){return"n"===t.type||"s"===t.type?e[1][0]-e[0][0]+u:u}).attr("height",function(t){return"e"===t.type||"w"===t.type?e[1][1]-e[0][1]+u:u})):t.selectAll(".selection,.handle").style("display","none").attr("x",null).attr("y",null).attr("width",null).attr("height",null)}function c(t,e,n){var r=t.__brush.emitter;return!r||n&&r.clean?new f(t,e,n):r}function f(t,e,n){this.that=t,this.args=e,this.state=t.__brush,this.active=0,this.clean=n}function h(n){if((!e||n.touches)&&r.apply(this,arguments)){var i,a,u,l,f,h,p,d,y,m,v,g=this,b=n.target.__data__.type,w="selection"===(o&&n.metaKey?b="overlay":b)?Br:o&&n.altKey?Fr:Ir,_=t===Jr?null:ri[b],x=t===Zr?null:ii[b],k=si(g),P=k.extent,M=k.selection,O=P[0][0],j=P[0][1],A=P[1][0],N=P[1][1],z=0,E=0,$=_&&x&&o&&n.shiftKey,S=Array.from(n.touches||[n],t=>{const e=t.identifier;return(t=$r(t,g)).point0=t.slice(),t.identifier=e,t});Tr(g);var T=c(g,arguments,!0).beforestart();if("overlay"===b){M&&(y=!0);const e=[S[0],S[1]||S[0]];k.selection=M=[[i=t===Jr?O:Gr(e[0][0],e[1][0]),u=t===Zr?j:Gr(e[0][1],e[1][1])],[f=t===Jr?A:Kr(e[0][0],e[1][0]),p=t===Zr?N:Kr(e[0][1],e[1][1])]],S.length>1&&Y(n)}else i=M[0][0],u=M[0][1],f=M[1][0],p=M[1][1];a=i,l=u,h=f,d=p;var C=Sr(g).attr("pointer-events","none"),X=C.selectAll(".overlay").attr("cursor",ti[b]);if(n.touches)T.moved=V,T.ended=H;else{var q=Sr(n.view).on("mousemove.brush",V,!0).on("mouseup.brush",H,!0);o&&q.on("keydown.brush",function(t){switch(t.keyCode){case 16:$=_&&x;break;case 18:w===Ir&&(_&&(f=h-z*_,i=a+z*_),x&&(p=d-E*x,u=l+E*x),w=Fr,Y(t));break;case 32:w!==Ir&&w!==Fr||(_<0?f=h-z:_>0&&(i=a-z),x<0?p=d-E:x>0&&(u=l-E),w=Dr,X.attr("cursor",ti.selection),Y(t));break;default:return}qr(t)},!0).on("keyup.brush",function(t){switch(t.keyCode){case 16:$&&(m=v=$=!1,Y(t));break;case 18:w===Fr&&(_<0?f=h:_>0&&(i=a),x<0?p=d:x>0&&(u=l),w=Ir,Y(t));break;case 32:w===Dr&&(t.altKey?(_&&(f=h-z*_,i=a+z*_),x&&(p=d-E*x,u=l+E*x),w=Fr):(_<0?f=h:_>0&&(i=a),x<0?p=d:x>0&&(u=l),w=Ir),X.attr("cursor",ti[b]),Y(t));break;default:return}qr(t)},!0),Nr(n.view)}s.call(g),T.start(n,w.name)}function V(t){for(const e of t.changedTouches||[t])for(const t of S)t.identifier===e.identifier&&(t.cur=$r(e,g));if($&&!m&&!v&&1===S.length){const t=S[0];Lr(t.cur[0]-t[0])>Lr(t.cur[1]-t[1])?v=!0:m=!0}for(const t of S)t.cur&&(t[0]=t.cur[0],t[1]=t.cur[1]);y=!0,qr(t),Y(t)}function Y(t){const e=S[0],n=e.point0;var r;switch(z=e[0]-n[0],E=e[1]-n[1],w){case Dr:case Br:_&&(z=Kr(O-i,Gr(A-f,z)),a=i+z,h=f+z),x&&(E=Kr(j-u,Gr(N-p,E)),l=u+E,d=p+E);break;case Ir:S[1]?(_&&(a=Kr(O,Gr(A,S[0][0])),h=Kr(O,Gr(A,S[1][0])),_=1),x&&(l=Kr(j,Gr(N,S[0][1])),d=Kr(j,Gr(N,S[1][1])),x=1)):(_<0?(z=Kr(O-i,Gr(A-i,z)),a=i+z,h=f):_>0&&(z=Kr(O-f,Gr(A-f,z)),a=i,h=f+z),x<0?(E=Kr(j-u,Gr(N-u,E)),l=u+E,d=p):x>0&&(E=Kr(j-p,Gr(N-p,E)),l=u,d=p+E));break;case Fr:_&&(a=Kr(O,Gr(A,i-z*_)),h=Kr(O,Gr(A,f+z*_))),x&&(l=Kr(j,Gr(N,u-E*x)),d=Kr(j,Gr(N,p+E*x)))}h<a&&(_*=-1,r=i,i=f,f=r,r=a,a=h,h=r,b in ei&&X.attr("cursor",ti[b=ei[b]])),d<l&&(x*=-1,r=u,u=p,p=r,r=l,l=d,d=r,b in ni&&X.attr("cursor",ti[b=ni[b]])),k.selection&&(M=k.selection),m&&(a=M[0][0],h=M[1][0]),v&&(l=M[0][1],d=M[1][1]),M[0][0]===a&&M[0][1]===l&&M[1][0]===h&&M[1][1]===d||(k.selection=[[a,l],[h,d]],s.call(g),T.brush(t,w.name))}function H(t){if(Vr(t),t.touches){if(t.touches.length)return;e&&clearTimeout(e),e=setTimeout(function(){e=null},500)}else zr(t.view,y),q.on("keydown.brush keyup.brush mousemove.brush mouseup.brush",null);C.attr("pointer-events","all"),X.attr("cursor",ti.overlay),k.selection&&(M=k.selection),function(t){return t[0][0]===t[1][0]||t[0][1]===t[1][1]}(M)&&(k.selection=null,s.call(g)),T.end(t,w.name)}}function p(t){c(this,arguments).moved(t)}function d(t){c(this,arguments).ended(t)}function y(){var e=this.__brush||{selection:null};return e.extent=Wr(n.apply(this,arguments)),e.dim=t,e}return l.move=function(e,n,r){e.tween?e.on("start.brush",function(t){c(this,arguments).beforestart().start(t)}).on("interrupt.brush end.brush",function(t){c(this,arguments).end(t)}).tween("brush",function(){var e=this,r=e.__brush,i=c(e,arguments),o=r.selection,a=t.input("function"==typeof n?n.apply(this,arguments):n,r.extent),u=Er(o,a);function l(t){r.selection=1===t&&null===a?null:u(t),s.call(e),i.brush()}return null!==o&&null!==a?l:l(1)}):e.each(function(){var e=this,i=arguments,o=e.__brush,a=t.input("function"==typeof n?n.apply(e,i):n,o.extent),u=c(e,i).beforestart();Tr(e),o.selection=null===a?null:a,s.call(e),u.start(r).brush(r).end(r)})},l.clear=function(t,e){l.move(t,null,e)},f.prototype={beforestart:function(){return 1===++this.active&&(this.state.emitter=this,this.starting=!0),this},start:function(t,e){return this.starting?(this.starting=!1,this.emit("start",t,e)):this.emit("brush",t),this},brush:function(t,e){return this.emit("brush",t,e),this},end:function(t,e){return 0===--this.active&&(delete this.state.emitter,this.emit("end",t,e)),this},emit:function(e,n,r){var i=Sr(this.that).datum();a.call(e,this.that,new Xr(e,{sourceEvent:n,target:l,selection:t.output(this.state.selection),mode:r,dispatch:a}),i)}},l.extent=function(t){return arguments.length?(n="function"==typeof t?t:Cr(Wr(t)),l):n},l.filter=function(t){return arguments.length?(r="function"==typeof t?t:Cr(!!t),l):r},l.touchable=function(t){return arguments.length?(i="function"==typeof t?t:Cr(!!t),l):i},l.handleSize=function(t){return arguments.length?(u=+t,l):u},l.keyModifiers=function(t){return arguments.length?(o=!!t,l):o},l.on=function(){var t=a.on.apply(a,arguments);return t===a?l:t},l}(Qr)},"name",{value:"default",configurable:!0});var Br={name:"drag"},Dr={name:"space"},Ir={name:"handle"},Fr={name:"center"};const{abs:Lr,max:Kr,min:Gr}=Math;function Ur(t){return[+t[0],+t[1]]}function Wr(t){return[Ur(t[0]),Ur(t[1])]}var Zr={name:"x",handles:["w","e"].map(oi),input:function(t,e){return null==t?null:[[+t[0],e[0][1]],[+t[1],e[1][1]]]},output:function(t){return t&&[t[0][0],t[1][0]]}},Jr={name:"y",handles:["n","s"].map(oi),input:function(t,e){return null==t?null:[[e[0][0],+t[0]],[e[1][0],+t[1]]]},output:function(t){return t&&[t[0][1],t[1][1]]}},Qr={name:"xy",handles:["n","w","e","s","nw","ne","sw","se"].map(oi),input:function(t){return null==t?null:Wr(t)},output:function(t){return t}},ti={overlay:"crosshair",selection:"move",n:"ns-resize",e:"ew-resize",s:"ns-resize",w:"ew-resize",nw:"nwse-resize",ne:"nesw-resize",se:"nwse-resize",sw:"nesw-resize"},ei={e:"w",w:"e",nw:"ne",ne:"nw",se:"sw",sw:"se"},ni={n:"s",s:"n",nw:"sw",ne:"se",se:"ne",sw:"nw"},ri={overlay:1,selection:1,n:null,e:1,s:null,w:-1,nw:-1,ne:1,se:1,sw:-1},ii={overlay:1,selection:1,n:-1,e:null,s:1,w:null,nw:-1,ne:-1,se:1,sw:1};function oi(t){return{type:t}}function ai(t){return!t.ctrlKey&&!t.button}function ui(){var t=this.ownerSVGElement||this;return t.hasAttribute("viewBox")?[[(t=t.viewBox.baseVal).x,t.y],[t.x+t.width,t.y+t.height]]:[[0,0],[t.width.baseVal.value,t.height.baseVal.value]]}function li(){return navigator.maxTouchPoints||"ontouchstart"in this}function si(t){for(;!t.__brush;)if(!(t=t.parentNode))return;return t.__brush}function ci(t){if(!t.ok)throw new Error(t.status+" "+t.statusText);if(204!==t.status&&205!==t.status)return t.json()}function fi(t,e){return fetch(t,e).then(ci)}Object.defineProperty(fi,"name",{value:"default",configurable:!0});const hi=Math.sqrt(50),pi=Math.sqrt(10),di=Math.sqrt(2);function yi(t,e,n){const r=(e-t)/Math.max(0,n),i=Math.floor(Math.log10(r)),o=r/Math.pow(10,i),a=o>=hi?10:o>=pi?5:o>=di?2:1;let u,l,s;return i<0?(s=Math.pow(10,-i)/a,u=Math.round(t*s),l=Math.round(e*s),u/s<t&&++u,l/s>e&&--l,s=-s):(s=Math.pow(10,i)*a,u=Math.round(t/s),l=Math.round(e/s),u*s<t&&++u,l*s>e&&--l),l<u&&.5<=n&&n<2?yi(t,e,2*n):[u,l,s]}function mi(t,e,n){return yi(t=+t,e=+e,n=+n)[2]}function vi(t,e){return null==t||null==e?NaN:t<e?-1:t>e?1:t>=e?0:NaN}function gi(t,e){return null==t||null==e?NaN:e<t?-1:e>t?1:e>=t?0:NaN}function bi(t){let e,n,r;function i(t,r,i=0,o=t.length){if(i<o){if(0!==e(r,r))return o;do{const e=i+o>>>1;n(t[e],r)<0?i=e+1:o=e}while(i<o)}return i}return 2!==t.length?(e=vi,n=(e,n)=>vi(t(e),n),r=(e,n)=>t(e)-n):(e=t===vi||t===gi?t:wi,n=t,r=t),{left:i,center:function(t,e,n=0,o=t.length){const a=i(t,e,n,o-1);return a>n&&r(t[a-1],e)>-r(t[a],e)?a-1:a},right:function(t,r,i=0,o=t.length){if(i<o){if(0!==e(r,r))return o;do{const e=i+o>>>1;n(t[e],r)<=0?i=e+1:o=e}while(i<o)}return i}}}function wi(){return 0}const _i=bi(vi),xi=_i.right,ki=(_i.left,bi(function(t){return null===t?NaN:+t}).center,xi);function Pi(t,e){var n,r=e?e.length:0,i=t?Math.min(r,t.length):0,o=new Array(i),a=new Array(r);for(n=0;n<i;++n)o[n]=Ai(t[n],e[n]);for(;n<r;++n)a[n]=e[n];return function(t){for(n=0;n<i;++n)a[n]=o[n](t);return a}}function Mi(t,e){var n=new Date;return t=+t,e=+e,function(r){return n.setTime(t*(1-r)+e*r),n}}function Oi(t,e){var n,r={},i={};for(n in null!==t&&"object"==typeof t||(t={}),null!==e&&"object"==typeof e||(e={}),e)n in t?r[n]=Ai(t[n],e[n]):i[n]=e[n];return function(t){for(n in r)i[n]=r[n](t);return i}}function ji(t,e){e||(e=[]);var n,r=t?Math.min(e.length,t.length):0,i=e.slice();return function(o){for(n=0;n<r;++n)i[n]=t[n]*(1-o)+e[n]*o;return i}}function Ai(t,e){var n,r,i=typeof e;return null==e||"boolean"===i?qn(e):("number"===i?Re:"string"===i?(n=gn(e))?(e=n,Yn):Dn:e instanceof gn?Yn:e instanceof Date?Mi:(r=e,!ArrayBuffer.isView(r)||r instanceof DataView?Array.isArray(e)?Pi:"function"!=typeof e.valueOf&&"function"!=typeof e.toString||isNaN(e)?Oi:Re:ji))(t,e)}function Ni(t,e){return t=+t,e=+e,function(n){return Math.round(t*(1-n)+e*n)}}function zi(t){return+t}Object.defineProperty(function(t,e){return((void 0)(e)?void 0:Pi)(t,e)},"name",{value:"default",configurable:!0}),Object.defineProperty(Mi,"name",{value:"default",configurable:!0}),Object.defineProperty(Oi,"name",{value:"default",configurable:!0}),Object.defineProperty(ji,"name",{value:"default",configurable:!0}),Object.defineProperty(Ai,"name",{value:"default",configurable:!0}),Object.defineProperty(Ni,"name",{value:"default",configurable:!0});var Ei=[0,1];function $i(t){return t}function Si(t,e){return(e-=t=+t)?function(n){return(n-t)/e}:(n=isNaN(e)?NaN:.5,function(){return n});var n}function Ti(t,e,n){var r=t[0],i=t[1],o=e[0],a=e[1];return i<r?(r=Si(i,r),o=n(a,o)):(r=Si(r,i),o=n(o,a)),function(t){return o(r(t))}}function Ci(t,e,n){var r=Math.min(t.length,e.length)-1,i=new Array(r),o=new Array(r),a=-1;for(t[r]<t[0]&&(t=t.slice().reverse(),e=e.slice().reverse());++a<r;)i[a]=Si(t[a],t[a+1]),o[a]=n(e[a],e[a+1]);return function(e){var n=ki(t,e,1,r)-1;return o[n](i[n](e))}}function Xi(t,e){switch(arguments.length){case 0:break;case 1:this.range(t);break;default:this.range(e).domain(t)}return this}var qi,Vi=/^(?:(.)?([<>=^]))?([+\-( ])?([$#])?(0)?(\d+)?(,)?(\.\d+)?(~)?([a-z%])?$/i;function Yi(t){if(!(e=Vi.exec(t)))throw new Error("invalid format: "+t);var e;return new Hi({fill:e[1],align:e[2],sign:e[3],symbol:e[4],zero:e[5],width:e[6],comma:e[7],precision:e[8]&&e[8].slice(1),trim:e[9],type:e[10]})}function Hi(t){this.fill=void 0===t.fill?" ":t.fill+"",this.align=void 0===t.align?">":t.align+"",this.sign=void 0===t.sign?"-":t.sign+"",this.symbol=void 0===t.symbol?"":t.symbol+"",this.zero=!!t.zero,this.width=void 0===t.width?void 0:+t.width,this.comma=!!t.comma,this.precision=void 0===t.precision?void 0:+t.precision,this.trim=!!t.trim,this.type=void 0===t.type?"":t.type+""}function Ri(t){return Math.abs(t=Math.round(t))>=1e21?t.toLocaleString("en").replace(/,/g,""):t.toString(10)}function Bi(t,e){if(!isFinite(t)||0===t)return null;var n=(t=e?t.toExponential(e-1):t.toExponential()).indexOf("e"),r=t.slice(0,n);return[r.length>1?r[0]+r.slice(2):r,+t.slice(n+1)]}function Di(t){return(t=Bi(Math.abs(t)))?t[1]:NaN}function Ii(t,e){return Math.max(0,3*Math.max(-8,Math.min(8,Math.floor(Di(e)/3)))-Di(Math.abs(t)))}function Fi(t,e){return function(n,r){for(var i=n.length,o=[],a=0,u=t[0],l=0;i>0&&u>0&&(l+u+1>r&&(u=Math.max(1,r-l)),o.push(n.substring(i-=u,i+u)),!((l+=u+1)>r));)u=t[a=(a+1)%t.length];return o.reverse().join(e)}}function Li(t){return function(e){return e.replace(/[0-9]/g,function(e){return t[+e]})}}function Ki(t){t:for(var e,n=t.length,r=1,i=-1;r<n;++r)switch(t[r]){case".":i=e=r;break;case"0":0===i&&(i=r),e=r;break;default:if(!+t[r])break t;i>0&&(i=0)}return i>0?t.slice(0,i)+t.slice(e+1):t}function Gi(t,e){var n=Bi(t,e);if(!n)return qi=void 0,t.toPrecision(e);var r=n[0],i=n[1],o=i-(qi=3*Math.max(-8,Math.min(8,Math.floor(i/3))))+1,a=r.length;return o===a?r:o>a?r+new Array(o-a+1).join("0"):o>0?r.slice(0,o)+"."+r.slice(o):"0."+new Array(1-o).join("0")+Bi(t,Math.max(0,e+o-1))[0]}function Ui(t,e){var n=Bi(t,e);if(!n)return t+"";var r=n[0],i=n[1];return i<0?"0."+new Array(-i).join("0")+r:r.length>i+1?r.slice(0,i+1)+"."+r.slice(i+1):r+new Array(i-r.length+2).join("0")}Yi.prototype=Hi.prototype,Hi.prototype.toString=function(){return this.fill+this.align+this.sign+this.symbol+(this.zero?"0":"")+(void 0===this.width?"":Math.max(1,0|this.width))+(this.comma?",":"")+(void 0===this.precision?"":"."+Math.max(0,0|this.precision))+(this.trim?"~":"")+this.type},Object.defineProperty(Ri,"name",{value:"default",configurable:!0}),Object.defineProperty(Di,"name",{value:"default",configurable:!0}),Object.defineProperty(Ii,"name",{value:"default",configurable:!0}),Object.defineProperty(Fi,"name",{value:"default",configurable:!0}),Object.defineProperty(Li,"name",{value:"default",configurable:!0}),Object.defineProperty(Ki,"name",{value:"default",configurable:!0}),Object.defineProperty(Gi,"name",{value:"default",configurable:!0}),Object.defineProperty(Ui,"name",{value:"default",configurable:!0});const Wi={"%":(t,e)=>(100*t).toFixed(e),b:t=>Math.round(t).toString(2),c:t=>t+"",d:Ri,e:(t,e)=>t.toExponential(e),f:(t,e)=>t.toFixed(e),g:(t,e)=>t.toPrecision(e),o:t=>Math.round(t).toString(8),p:(t,e)=>Ui(100*t,e),r:Ui,s:Gi,X:t=>Math.round(t).toString(16).toUpperCase(),x:t=>Math.round(t).toString(16)};function Zi(t){return t}Object.defineProperty(Zi,"name",{value:"default",configurable:!0}),Object.defineProperty(fo,"name",{value:"default",configurable:!0});var Ji,Qi,to,eo,no,ro,io,oo,ao,uo,lo,so=Array.prototype.map,co=["y","z","a","f","p","n","µ","m","","k","M","G","T","P","E","Z","Y"];function fo(t){var e=void 0===t.grouping||void 0===t.thousands?Zi:Fi(so.call(t.grouping,Number),t.thousands+""),n=void 0===t.currency?"":t.currency[0]+"",r=void 0===t.currency?"":t.currency[1]+"",i=void 0===t.decimal?".":t.decimal+"",o=void 0===t.numerals?Zi:Li(so.call(t.numerals,String)),a=void 0===t.percent?"%":t.percent+"",u=void 0===t.minus?"−":t.minus+"",l=void 0===t.nan?"NaN":t.nan+"";function s(t,s){var c=(t=Yi(t)).fill,f=t.align,h=t.sign,p=t.symbol,d=t.zero,y=t.width,m=t.comma,v=t.precision,g=t.trim,b=t.type;"n"===b?(m=!0,b="g"):Wi[b]||(void 0===v&&(v=12),g=!0,b="g"),(d||"0"===c&&"="===f)&&(d=!0,c="0",f="=");var w=(s&&void 0!==s.prefix?s.prefix:"")+("$"===p?n:"#"===p&&/[boxX]/.test(b)?"0"+b.toLowerCase():""),_=("$"===p?r:/[%p]/.test(b)?a:"")+(s&&void 0!==s.suffix?s.suffix:""),x=Wi[b],k=/[defgprs%]/.test(b);function P(t){var n,r,a,s=w,p=_;if("c"===b)p=x(t)+p,t="";else{var P=(t=+t)<0||1/t<0;if(t=isNaN(t)?l:x(Math.abs(t),v),g&&(t=Ki(t)),P&&0===+t&&"+"!==h&&(P=!1),s=(P?"("===h?h:u:"-"===h||"("===h?"":h)+s,p=("s"!==b||isNaN(t)||void 0===qi?"":co[8+qi/3])+p+(P&&"("===h?")":""),k)for(n=-1,r=t.length;++n<r;)if(48>(a=t.charCodeAt(n))||a>57){p=(46===a?i+t.slice(n+1):t.slice(n))+p,t=t.slice(0,n);break}}m&&!d&&(t=e(t,1/0));var M=s.length+t.length+p.length,O=M<y?new Array(y-M+1).join(c):"";switch(m&&d&&(t=e(O+t,O.length?y-p.length:1/0),O=""),f){case"<":t=s+t+p+O;break;case"=":t=s+O+t+p;break;case"^":t=O.slice(0,M=O.length>>1)+s+t+p+O.slice(M);break;default:t=O+s+t+p}return o(t)}return v=void 0===v?6:/[gprs]/.test(b)?Math.max(1,Math.min(21,v)):Math.max(0,Math.min(20,v)),P.toString=function(){return t+""},P}return{format:s,formatPrefix:function(t,e){var n=3*Math.max(-8,Math.min(8,Math.floor(Di(e)/3))),r=Math.pow(10,-n),i=s(((t=Yi(t)).type="f",t),{suffix:co[8+n/3]});return function(t){return i(r*t)}}}}function ho(t,e){return t=Math.abs(t),e=Math.abs(e)-t,Math.max(0,Di(e)-Di(t))+1}function po(t){return Math.max(0,-Di(Math.abs(t)))}function yo(t){var e=t.domain;return t.ticks=function(t){var n=e();return function(t,e,n){if(!((n=+n)>0))return[];if((t=+t)===(e=+e))return[t];const r=e<t,[i,o,a]=r?yi(e,t,n):yi(t,e,n);if(!(o>=i))return[];const u=o-i+1,l=new Array(u);if(r)if(a<0)for(let t=0;t<u;++t)l[t]=(o-t)/-a;else for(let t=0;t<u;++t)l[t]=(o-t)*a;else if(a<0)for(let t=0;t<u;++t)l[t]=(i+t)/-a;else for(let t=0;t<u;++t)l[t]=(i+t)*a;return l}(n[0],n[n.length-1],t??10)},t.tickFormat=function(t,n){var r=e();return function(t,e,n,r){var i,o=function(t,e,n){n=+n;const r=(e=+e)<(t=+t),i=r?mi(e,t,n):mi(t,e,n);return(r?-1:1)*(i<0?1/-i:i)}(t,e,n);switch((r=Yi(r??",f")).type){case"s":var a=Math.max(Math.abs(t),Math.abs(e));return null!=r.precision||isNaN(i=Ii(o,a))||(r.precision=i),to(r,a);case"":case"e":case"g":case"p":case"r":null!=r.precision||isNaN(i=ho(o,Math.max(Math.abs(t),Math.abs(e))))||(r.precision=i-("e"===r.type));break;case"f":case"%":null!=r.precision||isNaN(i=po(o))||(r.precision=i-2*("%"===r.type))}return Qi(r)}(r[0],r[r.length-1],t??10,n)},t.nice=function(n){null==n&&(n=10);var r,i,o=e(),a=0,u=o.length-1,l=o[a],s=o[u],c=10;for(s<l&&(i=l,l=s,s=i,i=a,a=u,u=i);c-- >0;){if((i=mi(l,s,n))===r)return o[a]=l,o[u]=s,e(o);if(i>0)l=Math.floor(l/i)*i,s=Math.ceil(s/i)*i;else{if(!(i<0))break;l=Math.ceil(l*i)/i,s=Math.floor(s*i)/i}r=i}return t},t}function mo(){var t=function(){var t,e,n,r,i,o,a=Ei,u=Ei,l=Ai,s=$i;function c(){var t,e,n,l=Math.min(a.length,u.length);return s!==$i&&(t=a[0],e=a[l-1],t>e&&(n=t,t=e,e=n),s=function(n){return Math.max(t,Math.min(e,n))}),r=l>2?Ci:Ti,i=o=null,f}function f(e){return null==e||isNaN(e=+e)?n:(i||(i=r(a.map(t),u,l)))(t(s(e)))}return f.invert=function(n){return s(e((o||(o=r(u,a.map(t),Re)))(n)))},f.domain=function(t){return arguments.length?(a=Array.from(t,zi),c()):a.slice()},f.range=function(t){return arguments.length?(u=Array.from(t),c()):u.slice()},f.rangeRound=function(t){return u=Array.from(t),l=Ni,c()},f.clamp=function(t){return arguments.length?(s=!!t||$i,c()):s!==$i},f.interpolate=function(t){return arguments.length?(l=t,c()):l},f.unknown=function(t){return arguments.length?(n=t,f):n},function(n,r){return t=n,e=r,c()}}()($i,$i);return t.copy=function(){return e=t,mo().domain(e.domain()).range(e.range()).interpolate(e.interpolate()).clamp(e.clamp()).unknown(e.unknown());var e},Xi.apply(t,arguments),yo(t)}function vo(t){return"string"==typeof t?new Qt([[document.querySelector(t)]],[document.documentElement]):new Qt([[t]],Jt)}function go(t,e,n){this.k=t,this.x=e,this.y=n}function bo(t){return!(t.ctrlKey&&"wheel"!==t.type||t.button)}function wo(){var t=this;return t instanceof SVGElement?(t=t.ownerSVGElement||t).hasAttribute("viewBox")?[[(t=t.viewBox.baseVal).x,t.y],[t.x+t.width,t.y+t.height]]:[[0,0],[t.width.baseVal.value,t.height.baseVal.value]]:[[0,0],[t.clientWidth,t.clientHeight]]}function _o(){return this.__zoom||oo}function xo(t){return-t.deltaY*(1===t.deltaMode?.05:t.deltaMode?1:.002)*(t.ctrlKey?10:1)}function ko(){return navigator.maxTouchPoints||"ontouchstart"in this}function Po(t,e,n){var r=t.invertX(e[0][0])-n[0][0],i=t.invertX(e[1][0])-n[1][0],o=t.invertY(e[0][1])-n[0][1],a=t.invertY(e[1][1])-n[1][1];return t.translate(i>r?(r+i)/2:Math.min(0,r)||Math.max(0,i),a>o?(o+a)/2:Math.min(0,o)||Math.max(0,a))}Ji=fo({thousands:",",grouping:[3],currency:["$",""]}),Qi=Ji.format,to=Ji.formatPrefix,Object.defineProperty(ho,"name",{value:"default",configurable:!0}),Object.defineProperty(po,"name",{value:"default",configurable:!0}),Object.defineProperty(vo,"name",{value:"default",configurable:!0}),go.prototype={constructor:go,scale:function(t){return 1===t?this:new go(this.k*t,this.x,this.y)},translate:function(t,e){return 0===t&0===e?this:new go(this.k,this.x+this.k*t,this.y+this.k*e)},apply:function(t){return[t[0]*this.k+this.x,t[1]*this.k+this.y]},applyX:function(t){return t*this.k+this.x},applyY:function(t){return t*this.k+this.y},invert:function(t){return[(t[0]-this.x)/this.k,(t[1]-this.y)/this.k]},invertX:function(t){return(t-this.x)/this.k},invertY:function(t){return(t-this.y)/this.k},rescaleX:function(t){return t.copy().domain(t.range().map(this.invertX,this).map(t.invert,t))},rescaleY:function(t){return t.copy().domain(t.range().map(this.invertY,this).map(t.invert,t))},toString:function(){return"translate("+this.x+","+this.y+") scale("+this.k+")"}},new go(1,0,0),go.prototype,Object.defineProperty(function(){var t,e,n,r=bo,i=wo,o=Po,a=xo,u=ko,l=[0,1/0],s=[[-1/0,-1/0],[1/0,1/0]],c=250,f=void 0,h=(void 0)("start","zoom","end"),p=0,d=10;function y(t){t.property("__zoom",_o).on("wheel.zoom",x,{passive:!1}).on("mousedown.zoom",k).on("dblclick.zoom",P).filter(u).on("touchstart.zoom",M).on("touchmove.zoom",O).on("touchend.zoom touchcancel.zoom",j).style("-webkit-tap-highlight-color","rgba(0,0,0,0)")}function m(t,e){return(e=Math.max(l[0],Math.min(l[1],e)))===t.k?t:new ao(e,t.x,t.y)}function v(t,e,n){var r=e[0]-n[0]*t.k,i=e[1]-n[1]*t.k;return r===t.x&&i===t.y?t:new ao(t.k,r,i)}function g(t){return[(+t[0][0]+ +t[1][0])/2,(+t[0][1]+ +t[1][1])/2]}function b(t,e,n,r){t.on("start.zoom",function(){w(this,arguments).event(r).start()}).on("interrupt.zoom end.zoom",function(){w(this,arguments).event(r).end()}).tween("zoom",function(){var t=this,o=arguments,a=w(t,o).event(r),u=i.apply(t,o),l=null==n?g(u):"function"==typeof n?n.apply(t,o):n,s=Math.max(u[1][0]-u[0][0],u[1][1]-u[0][1]),c=t.__zoom,h="function"==typeof e?e.apply(t,o):e,p=f(c.invert(l).concat(s/c.k),h.invert(l).concat(s/h.k));return function(t){if(1===t)t=h;else{var e=p(t),n=s/e[2];t=new ao(n,l[0]-e[0]*n,l[1]-e[1]*n)}a.zoom(null,t)}})}function w(t,e,n){return!n&&t.__zooming||new _(t,e)}function _(t,e){this.that=t,this.args=e,this.active=0,this.sourceEvent=null,this.extent=i.apply(t,e),this.taps=0}function x(t,...e){if(r.apply(this,arguments)){var n=w(this,e).event(t),i=this.__zoom,u=Math.max(l[0],Math.min(l[1],i.k*Math.pow(2,a.apply(this,arguments)))),c=eo(t);if(n.wheel)n.mouse[0][0]===c[0]&&n.mouse[0][1]===c[1]||(n.mouse[1]=i.invert(n.mouse[0]=c)),clearTimeout(n.wheel);else{if(i.k===u)return;n.mouse=[c,i.invert(c)],ro(this),n.start()}uo(t),n.wheel=setTimeout(function(){n.wheel=null,n.end()},150),n.zoom("mouse",o(v(m(i,u),n.mouse[0],n.mouse[1]),n.extent,s))}}function k(t,...e){if(!n&&r.apply(this,arguments)){var i=t.currentTarget,a=w(this,e,!0).event(t),u=no(t.view).on("mousemove.zoom",function(t){if(uo(t),!a.moved){var e=t.clientX-c,n=t.clientY-f;a.moved=e*e+n*n>p}a.event(t).zoom("mouse",o(v(a.that.__zoom,a.mouse[0]=eo(t,i),a.mouse[1]),a.extent,s))},!0).on("mouseup.zoom",function(t){u.on("mousemove.zoom mouseup.zoom",null),(void 0)(t.view,a.moved),uo(t),a.event(t).end()},!0),l=eo(t,i),c=t.clientX,f=t.clientY;(void 0)(t.view),lo(t),a.mouse=[l,this.__zoom.invert(l)],ro(this),a.start()}}function P(t,...e){if(r.apply(this,arguments)){var n=this.__zoom,a=eo(t.changedTouches?t.changedTouches[0]:t,this),u=n.invert(a),l=n.k*(t.shiftKey?.5:2),f=o(v(m(n,l),a,u),i.apply(this,e),s);uo(t),c>0?no(this).transition().duration(c).call(b,f,a,t):no(this).call(y.transform,f,a,t)}}function M(n,...i){if(r.apply(this,arguments)){var o,a,u,l,s=n.touches,c=s.length,f=w(this,i,n.changedTouches.length===c).event(n);for(lo(n),a=0;a<c;++a)u=s[a],l=[l=eo(u,this),this.__zoom.invert(l),u.identifier],f.touch0?f.touch1||f.touch0[2]===l[2]||(f.touch1=l,f.taps=0):(f.touch0=l,o=!0,f.taps=1+!!t);t&&(t=clearTimeout(t)),o&&(f.taps<2&&(e=l[0],t=setTimeout(function(){t=null},500)),ro(this),f.start())}}function O(t,...e){if(this.__zooming){var n,r,i,a,u=w(this,e).event(t),l=t.changedTouches,c=l.length;for(uo(t),n=0;n<c;++n)r=l[n],i=eo(r,this),u.touch0&&u.touch0[2]===r.identifier?u.touch0[0]=i:u.touch1&&u.touch1[2]===r.identifier&&(u.touch1[0]=i);if(r=u.that.__zoom,u.touch1){var f=u.touch0[0],h=u.touch0[1],p=u.touch1[0],d=u.touch1[1],y=(y=p[0]-f[0])*y+(y=p[1]-f[1])*y,g=(g=d[0]-h[0])*g+(g=d[1]-h[1])*g;r=m(r,Math.sqrt(y/g)),i=[(f[0]+p[0])/2,(f[1]+p[1])/2],a=[(h[0]+d[0])/2,(h[1]+d[1])/2]}else{if(!u.touch0)return;i=u.touch0[0],a=u.touch0[1]}u.zoom("touch",o(v(r,i,a),u.extent,s))}}function j(t,...r){if(this.__zooming){var i,o,a=w(this,r).event(t),u=t.changedTouches,l=u.length;for(lo(t),n&&clearTimeout(n),n=setTimeout(function(){n=null},500),i=0;i<l;++i)o=u[i],a.touch0&&a.touch0[2]===o.identifier?delete a.touch0:a.touch1&&a.touch1[2]===o.identifier&&delete a.touch1;if(a.touch1&&!a.touch0&&(a.touch0=a.touch1,delete a.touch1),a.touch0)a.touch0[1]=this.__zoom.invert(a.touch0[0]);else if(a.end(),2===a.taps&&(o=eo(o,this),Math.hypot(e[0]-o[0],e[1]-o[1])<d)){var s=no(this).on("dblclick.zoom");s&&s.apply(this,arguments)}}}return y.transform=function(t,e,n,r){var i=t.selection?t.selection():t;i.property("__zoom",_o),t!==i?b(t,e,n,r):i.interrupt().each(function(){w(this,arguments).event(r).start().zoom(null,"function"==typeof e?e.apply(this,arguments):e).end()})},y.scaleBy=function(t,e,n,r){y.scaleTo(t,function(){return this.__zoom.k*("function"==typeof e?e.apply(this,arguments):e)},n,r)},y.scaleTo=function(t,e,n,r){y.transform(t,function(){var t=i.apply(this,arguments),r=this.__zoom,a=null==n?g(t):"function"==typeof n?n.apply(this,arguments):n,u=r.invert(a),l="function"==typeof e?e.apply(this,arguments):e;return o(v(m(r,l),a,u),t,s)},n,r)},y.translateBy=function(t,e,n,r){y.transform(t,function(){return o(this.__zoom.translate("function"==typeof e?e.apply(this,arguments):e,"function"==typeof n?n.apply(this,arguments):n),i.apply(this,arguments),s)},null,r)},y.translateTo=function(t,e,n,r,a){y.transform(t,function(){var t=i.apply(this,arguments),a=this.__zoom,u=null==r?g(t):"function"==typeof r?r.apply(this,arguments):r;return o(oo.translate(u[0],u[1]).scale(a.k).translate("function"==typeof e?-e.apply(this,arguments):-e,"function"==typeof n?-n.apply(this,arguments):-n),t,s)},r,a)},_.prototype={event:function(t){return t&&(this.sourceEvent=t),this},start:function(){return 1===++this.active&&(this.that.__zooming=this,this.emit("start")),this},zoom:function(t,e){return this.mouse&&"mouse"!==t&&(this.mouse[1]=e.invert(this.mouse[0])),this.touch0&&"touch"!==t&&(this.touch0[1]=e.invert(this.touch0[0])),this.touch1&&"touch"!==t&&(this.touch1[1]=e.invert(this.touch1[0])),this.that.__zoom=e,this.emit("zoom"),this},end:function(){return 0===--this.active&&(delete this.that.__zooming,this.emit("end")),this},emit:function(t){var e=no(this.that).datum();h.call(t,this.that,new(void 0)(t,{sourceEvent:this.sourceEvent,target:y,type:t,transform:this.that.__zoom,dispatch:h}),e)}},y.wheelDelta=function(t){return arguments.length?(a="function"==typeof t?t:io(+t),y):a},y.filter=function(t){return arguments.length?(r="function"==typeof t?t:io(!!t),y):r},y.touchable=function(t){return arguments.length?(u="function"==typeof t?t:io(!!t),y):u},y.extent=function(t){return arguments.length?(i="function"==typeof t?t:io([[+t[0][0],+t[0][1]],[+t[1][0],+t[1][1]]]),y):i},y.scaleExtent=function(t){return arguments.length?(l[0]=+t[0],l[1]=+t[1],y):[l[0],l[1]]},y.translateExtent=function(t){return arguments.length?(s[0][0]=+t[0][0],s[1][0]=+t[1][0],s[0][1]=+t[0][1],s[1][1]=+t[1][1],y):[[s[0][0],s[0][1]],[s[1][0],s[1][1]]]},y.constrain=function(t){return arguments.length?(o=t,y):o},y.duration=function(t){return arguments.length?(c=+t,y):c},y.interpolate=function(t){return arguments.length?(f=t,y):f},y.on=function(){var t=h.on.apply(h,arguments);return t===h?y:t},y.clickDistance=function(t){return arguments.length?(p=(t=+t)*t,y):Math.sqrt(p)},y.tapDistance=function(t){return arguments.length?(d=+t,y):d},y},"name",{value:"default",configurable:!0});const Mo=1190,Oo=680,jo=vo("body").append("div").style("position","absolute").style("background","rgba(0, 0, 0, 0.8)").style("color","white").style("padding","8px 12px").style("border-radius","4px").style("font-size","12px").style("pointer-events","none").style("opacity",0).style("z-index",1e3),Ao=vo("#container").append("svg").attr("width",1280).attr("height",720).append("g").attr("transform","translate(60,10)");function No(t){return t.reduce((t,e)=>t+e,0)/t.length}let zo="Shygirl/Nymph_o/07 - Shygirl - Playboy _ Positions.ogg";fi("http://localhost:8080/api/playlist/"+encodeURIComponent(zo)+"?length=150").then(function(e){const n=e.tail.flatMap(t=>No(t.analysis.bliss)),r=e.tail.flatMap(t=>No(t.analysis.genre)),i=mo().domain(t(n)).nice().range([0,Mo]),o=mo().domain(t(r)).nice().range([Oo,0]);var a;Ao.append("g").attr("transform","translate(0,680)").call((a=i,l(3,a)).ticks(5)),Ao.append("g").call(function(t){return l(4,t)}(o).ticks(5));const u=e.head.title||e.head.path.split("/").pop()||zo;Ao.append("text").attr("x",595).attr("y",-5).attr("text-anchor","middle").style("font-size","18px").style("font-weight","bold").text(`Selected song: ${u}`),Ao.append("text").attr("transform","translate(595)").attr("x",-10).attr("y",710).style("text-anchor","middle").text("Bliss Similarity"),Ao.append("text").attr("transform","rotate(-90)").attr("y",-60).attr("x",-340).attr("dy","1em").style("text-anchor","middle").text("Genre Similarity");const s=e.tail.map(t=>({bliss:t.analysis.bliss,genre:t.analysis.genre,i:e.tail.indexOf(t)})),c=new Audio,f=t=>({mouseover:function(n){const r=e.tail[t.i];vo(this).style("opacity",1),jo.style("opacity",1).html(`\n                        <strong><div style="text-decoration: underline;">${r.title}</div></strong>\n                        <br/>\n                        <strong>Bliss distance:</strong> ${No(t.bliss).toFixed(3)}\n                        <br/>\n                        <strong>Genre distance:</strong> ${No(t.genre).toFixed(3)}\n                        <br/>\n                        <strong>Genres:</strong> ${r.genre}\n                        <br/>\n                        <strong>Popularity:</strong> ${void 0!==r.popularity?r.popularity:"N/A"}\n                    `).style("left",n.pageX+10+"px").style("top",n.pageY-28+"px"),c.src=r.href,c.currentTime=30,c.play().catch(t=>console.log("Audio playback failed:",t))},mouseout:function(){vo(this).style("opacity",.8),jo.style("opacity",0),c.pause()}});Ao.append("g").selectAll("image").data(s).enter().append("image").attr("x",t=>i(No(t.bliss))-20).attr("y",t=>o(No(t.genre))-20).attr("width",40).attr("height",40).attr("href",t=>{const n=e.tail[t.i].path;return"http://localhost:8080/api/albumart/"+encodeURIComponent(n)}).style("cursor","pointer").style("opacity",.8).on("error",function(){const t=vo(this).datum(),e=i(No(t.bliss)),n=o(No(t.genre));vo(this).remove();const r=f(t);Ao.append("circle").attr("cx",e).attr("cy",n).attr("r",20).style("fill","#69b3a2").style("opacity",.8).style("cursor","pointer").on("mouseover",r.mouseover).on("mouseout",r.mouseout)}).on("mouseover",function(t,e){f(e).mouseover.call(this,t)}).on("mouseout",function(t,e){f(e).mouseout.call(this,t)}),console.log("Graph rendered with",s.length,"points")}).catch(function(t){console.error("Error loading data:",t)})})();
\ No newline at end of file
+(()=>{"use strict";function t(t,e){let n,r;if(void 0===e)for(const e of t)null!=e&&(void 0===n?e>=e&&(n=r=e):(n>e&&(n=e),r<e&&(r=e)));else{let i=-1;for(let o of t)null!=(o=e(o,++i,t))&&(void 0===n?o>=o&&(n=r=o):(n>o&&(n=o),r<o&&(r=o)))}return[n,r]}function e(t){return t}Object.defineProperty(e,"name",{value:"default",configurable:!0});var n=1e-6;function r(t){return"translate("+t+",0)"}function i(t){return"translate(0,"+t+")"}function o(t){return e=>+t(e)}function a(t,e){return e=Math.max(0,t.bandwidth()-2*e)/2,t.round()&&(e=Math.round(e)),n=>+t(n)+e}function u(){return!this.__axis}function l(t,l){var s=[],c=null,f=null,h=6,p=6,d=3,y="undefined"!=typeof window&&window.devicePixelRatio>1?0:.5,m=1===t||4===t?-1:1,v=4===t||2===t?"x":"y",g=1===t||3===t?r:i;function b(r){var i=c??(l.ticks?l.ticks.apply(l,s):l.domain()),b=f??(l.tickFormat?l.tickFormat.apply(l,s):e),w=Math.max(h,0)+d,_=l.range(),x=+_[0]+y,k=+_[_.length-1]+y,P=(l.bandwidth?a:o)(l.copy(),y),M=r.selection?r.selection():r,O=M.selectAll(".domain").data([null]),j=M.selectAll(".tick").data(i,l).order(),A=j.exit(),N=j.enter().append("g").attr("class","tick"),z=j.select("line"),E=j.select("text");O=O.merge(O.enter().insert("path",".tick").attr("class","domain").attr("stroke","currentColor")),j=j.merge(N),z=z.merge(N.append("line").attr("stroke","currentColor").attr(v+"2",m*h)),E=E.merge(N.append("text").attr("fill","currentColor").attr(v,m*w).attr("dy",1===t?"0em":3===t?"0.71em":"0.32em")),r!==M&&(O=O.transition(r),j=j.transition(r),z=z.transition(r),E=E.transition(r),A=A.transition(r).attr("opacity",n).attr("transform",function(t){return isFinite(t=P(t))?g(t+y):this.getAttribute("transform")}),N.attr("opacity",n).attr("transform",function(t){var e=this.parentNode.__axis;return g((e&&isFinite(e=e(t))?e:P(t))+y)})),A.remove(),O.attr("d",4===t||2===t?p?"M"+m*p+","+x+"H"+y+"V"+k+"H"+m*p:"M"+y+","+x+"V"+k:p?"M"+x+","+m*p+"V"+y+"H"+k+"V"+m*p:"M"+x+","+y+"H"+k),j.attr("opacity",1).attr("transform",function(t){return g(P(t)+y)}),z.attr(v+"2",m*h),E.attr(v,m*w).text(b),M.filter(u).attr("fill","none").attr("font-size",10).attr("font-family","sans-serif").attr("text-anchor",2===t?"start":4===t?"end":"middle"),M.each(function(){this.__axis=P})}return b.scale=function(t){return arguments.length?(l=t,b):l},b.ticks=function(){return s=Array.from(arguments),b},b.tickArguments=function(t){return arguments.length?(s=null==t?[]:Array.from(t),b):s.slice()},b.tickValues=function(t){return arguments.length?(c=null==t?null:Array.from(t),b):c&&c.slice()},b.tickFormat=function(t){return arguments.length?(f=t,b):f},b.tickSize=function(t){return arguments.length?(h=p=+t,b):h},b.tickSizeInner=function(t){return arguments.length?(h=+t,b):h},b.tickSizeOuter=function(t){return arguments.length?(p=+t,b):p},b.tickPadding=function(t){return arguments.length?(d=+t,b):d},b.offset=function(t){return arguments.length?(y=+t,b):y},b}function s(){}function c(t){return null==t?s:function(){return this.querySelector(t)}}function f(t){"function"!=typeof t&&(t=c(t));for(var e=this._groups,n=e.length,r=new Array(n),i=0;i<n;++i)for(var o,a,u=e[i],l=u.length,s=r[i]=new Array(l),f=0;f<l;++f)(o=u[f])&&(a=t.call(o,o.__data__,f,u))&&("__data__"in o&&(a.__data__=o.__data__),s[f]=a);return new Qt(r,this._parents)}function h(){return[]}function p(t){return null==t?h:function(){return this.querySelectorAll(t)}}function d(t){t="function"==typeof t?function(t){return function(){return null==(e=t.apply(this,arguments))?[]:Array.isArray(e)?e:Array.from(e);var e}}(t):p(t);for(var e=this._groups,n=e.length,r=[],i=[],o=0;o<n;++o)for(var a,u=e[o],l=u.length,s=0;s<l;++s)(a=u[s])&&(r.push(t.call(a,a.__data__,s,u)),i.push(a));return new Qt(r,i)}function y(t){return function(){return this.matches(t)}}function m(t){return function(e){return e.matches(t)}}Object.defineProperty(c,"name",{value:"default",configurable:!0}),Object.defineProperty(f,"name",{value:"default",configurable:!0}),Object.defineProperty(p,"name",{value:"default",configurable:!0}),Object.defineProperty(d,"name",{value:"default",configurable:!0}),Object.defineProperty(y,"name",{value:"default",configurable:!0}),Object.defineProperty(b,"name",{value:"default",configurable:!0});var v=Array.prototype.find;function g(){return this.firstElementChild}function b(t){return this.select(null==t?g:function(t){return function(){return v.call(this.children,t)}}("function"==typeof t?t:m(t)))}Object.defineProperty(x,"name",{value:"default",configurable:!0});var w=Array.prototype.filter;function _(){return Array.from(this.children)}function x(t){return this.selectAll(null==t?_:function(t){return function(){return w.call(this.children,t)}}("function"==typeof t?t:m(t)))}function k(t){"function"!=typeof t&&(t=y(t));for(var e=this._groups,n=e.length,r=new Array(n),i=0;i<n;++i)for(var o,a=e[i],u=a.length,l=r[i]=[],s=0;s<u;++s)(o=a[s])&&t.call(o,o.__data__,s,a)&&l.push(o);return new Qt(r,this._parents)}function P(t){return new Array(t.length)}function M(){return new Qt(this._enter||this._groups.map(P),this._parents)}function O(t,e){this.ownerDocument=t.ownerDocument,this.namespaceURI=t.namespaceURI,this._next=null,this._parent=t,this.__data__=e}function j(t){return function(){return t}}function A(t,e,n,r,i,o){for(var a,u=0,l=e.length,s=o.length;u<s;++u)(a=e[u])?(a.__data__=o[u],r[u]=a):n[u]=new O(t,o[u]);for(;u<l;++u)(a=e[u])&&(i[u]=a)}function N(t,e,n,r,i,o,a){var u,l,s,c=new Map,f=e.length,h=o.length,p=new Array(f);for(u=0;u<f;++u)(l=e[u])&&(p[u]=s=a.call(l,l.__data__,u,e)+"",c.has(s)?i[u]=l:c.set(s,l));for(u=0;u<h;++u)s=a.call(t,o[u],u,o)+"",(l=c.get(s))?(r[u]=l,l.__data__=o[u],c.delete(s)):n[u]=new O(t,o[u]);for(u=0;u<f;++u)(l=e[u])&&c.get(p[u])===l&&(i[u]=l)}function z(t){return t.__data__}function E(t,e){if(!arguments.length)return Array.from(this,z);var n=e?N:A,r=this._parents,i=this._groups;"function"!=typeof t&&(t=j(t));for(var o=i.length,a=new Array(o),u=new Array(o),l=new Array(o),s=0;s<o;++s){var c=r[s],f=i[s],h=f.length,p=$(t.call(c,c&&c.__data__,s,r)),d=p.length,y=u[s]=new Array(d),m=a[s]=new Array(d);n(c,f,y,m,l[s]=new Array(h),p,e);for(var v,g,b=0,w=0;b<d;++b)if(v=y[b]){for(b>=w&&(w=b+1);!(g=m[w])&&++w<d;);v._next=g||null}}return(a=new Qt(a,r))._enter=u,a._exit=l,a}function $(t){return"object"==typeof t&&"length"in t?t:Array.from(t)}function S(){return new Qt(this._exit||this._groups.map(P),this._parents)}function T(t,e,n){var r=this.enter(),i=this,o=this.exit();return"function"==typeof t?(r=t(r))&&(r=r.selection()):r=r.append(t+""),null!=e&&(i=e(i))&&(i=i.selection()),null==n?o.remove():n(o),r&&i?r.merge(i).order():i}function C(t){for(var e=t.selection?t.selection():t,n=this._groups,r=e._groups,i=n.length,o=r.length,a=Math.min(i,o),u=new Array(i),l=0;l<a;++l)for(var s,c=n[l],f=r[l],h=c.length,p=u[l]=new Array(h),d=0;d<h;++d)(s=c[d]||f[d])&&(p[d]=s);for(;l<i;++l)u[l]=n[l];return new Qt(u,this._parents)}function X(){for(var t=this._groups,e=-1,n=t.length;++e<n;)for(var r,i=t[e],o=i.length-1,a=i[o];--o>=0;)(r=i[o])&&(a&&4^r.compareDocumentPosition(a)&&a.parentNode.insertBefore(r,a),a=r);return this}function q(t){function e(e,n){return e&&n?t(e.__data__,n.__data__):!e-!n}t||(t=V);for(var n=this._groups,r=n.length,i=new Array(r),o=0;o<r;++o){for(var a,u=n[o],l=u.length,s=i[o]=new Array(l),c=0;c<l;++c)(a=u[c])&&(s[c]=a);s.sort(e)}return new Qt(i,this._parents).order()}function V(t,e){return t<e?-1:t>e?1:t>=e?0:NaN}function Y(){var t=arguments[0];return arguments[0]=this,t.apply(null,arguments),this}function H(){return Array.from(this)}function R(){for(var t=this._groups,e=0,n=t.length;e<n;++e)for(var r=t[e],i=0,o=r.length;i<o;++i){var a=r[i];if(a)return a}return null}function B(){let t=0;for(const e of this)++t;return t}function D(){return!this.node()}function I(t){for(var e=this._groups,n=0,r=e.length;n<r;++n)for(var i,o=e[n],a=0,u=o.length;a<u;++a)(i=o[a])&&t.call(i,i.__data__,a,o);return this}Object.defineProperty(k,"name",{value:"default",configurable:!0}),Object.defineProperty(P,"name",{value:"default",configurable:!0}),Object.defineProperty(M,"name",{value:"default",configurable:!0}),O.prototype={constructor:O,appendChild:function(t){return this._parent.insertBefore(t,this._next)},insertBefore:function(t,e){return this._parent.insertBefore(t,e)},querySelector:function(t){return this._parent.querySelector(t)},querySelectorAll:function(t){return this._parent.querySelectorAll(t)}},Object.defineProperty(j,"name",{value:"default",configurable:!0}),Object.defineProperty(E,"name",{value:"default",configurable:!0}),Object.defineProperty(S,"name",{value:"default",configurable:!0}),Object.defineProperty(T,"name",{value:"default",configurable:!0}),Object.defineProperty(C,"name",{value:"default",configurable:!0}),Object.defineProperty(X,"name",{value:"default",configurable:!0}),Object.defineProperty(q,"name",{value:"default",configurable:!0}),Object.defineProperty(Y,"name",{value:"default",configurable:!0}),Object.defineProperty(H,"name",{value:"default",configurable:!0}),Object.defineProperty(R,"name",{value:"default",configurable:!0}),Object.defineProperty(B,"name",{value:"default",configurable:!0}),Object.defineProperty(D,"name",{value:"default",configurable:!0}),Object.defineProperty(I,"name",{value:"default",configurable:!0});var F="http://www.w3.org/1999/xhtml";const L={svg:"http://www.w3.org/2000/svg",xhtml:F,xlink:"http://www.w3.org/1999/xlink",xml:"http://www.w3.org/XML/1998/namespace",xmlns:"http://www.w3.org/2000/xmlns/"};function K(t){var e=t+="",n=e.indexOf(":");return n>=0&&"xmlns"!==(e=t.slice(0,n))&&(t=t.slice(n+1)),L.hasOwnProperty(e)?{space:L[e],local:t}:t}function G(t){return function(){this.removeAttribute(t)}}function U(t){return function(){this.removeAttributeNS(t.space,t.local)}}function W(t,e){return function(){this.setAttribute(t,e)}}function Z(t,e){return function(){this.setAttributeNS(t.space,t.local,e)}}function J(t,e){return function(){var n=e.apply(this,arguments);null==n?this.removeAttribute(t):this.setAttribute(t,n)}}function Q(t,e){return function(){var n=e.apply(this,arguments);null==n?this.removeAttributeNS(t.space,t.local):this.setAttributeNS(t.space,t.local,n)}}function tt(t,e){var n=K(t);if(arguments.length<2){var r=this.node();return n.local?r.getAttributeNS(n.space,n.local):r.getAttribute(n)}return this.each((null==e?n.local?U:G:"function"==typeof e?n.local?Q:J:n.local?Z:W)(n,e))}function et(t){return t.ownerDocument&&t.ownerDocument.defaultView||t.document&&t||t.defaultView}function nt(t){return function(){this.style.removeProperty(t)}}function rt(t,e,n){return function(){this.style.setProperty(t,e,n)}}function it(t,e,n){return function(){var r=e.apply(this,arguments);null==r?this.style.removeProperty(t):this.style.setProperty(t,r,n)}}function ot(t,e,n){return arguments.length>1?this.each((null==e?nt:"function"==typeof e?it:rt)(t,e,n??"")):at(this.node(),t)}function at(t,e){return t.style.getPropertyValue(e)||et(t).getComputedStyle(t,null).getPropertyValue(e)}function ut(t){return function(){delete this[t]}}function lt(t,e){return function(){this[t]=e}}function st(t,e){return function(){var n=e.apply(this,arguments);null==n?delete this[t]:this[t]=n}}function ct(t,e){return arguments.length>1?this.each((null==e?ut:"function"==typeof e?st:lt)(t,e)):this.node()[t]}function ft(t){return t.trim().split(/^|\s+/)}function ht(t){return t.classList||new pt(t)}function pt(t){this._node=t,this._names=ft(t.getAttribute("class")||"")}function dt(t,e){for(var n=ht(t),r=-1,i=e.length;++r<i;)n.add(e[r])}function yt(t,e){for(var n=ht(t),r=-1,i=e.length;++r<i;)n.remove(e[r])}function mt(t){return function(){dt(this,t)}}function vt(t){return function(){yt(this,t)}}function gt(t,e){return function(){(e.apply(this,arguments)?dt:yt)(this,t)}}function bt(t,e){var n=ft(t+"");if(arguments.length<2){for(var r=ht(this.node()),i=-1,o=n.length;++i<o;)if(!r.contains(n[i]))return!1;return!0}return this.each(("function"==typeof e?gt:e?mt:vt)(n,e))}function wt(){this.textContent=""}function _t(t){return function(){this.textContent=t}}function xt(t){return function(){var e=t.apply(this,arguments);this.textContent=e??""}}function kt(t){return arguments.length?this.each(null==t?wt:("function"==typeof t?xt:_t)(t)):this.node().textContent}function Pt(){this.innerHTML=""}function Mt(t){return function(){this.innerHTML=t}}function Ot(t){return function(){var e=t.apply(this,arguments);this.innerHTML=e??""}}function jt(t){return arguments.length?this.each(null==t?Pt:("function"==typeof t?Ot:Mt)(t)):this.node().innerHTML}function At(){this.nextSibling&&this.parentNode.appendChild(this)}function Nt(){return this.each(At)}function zt(){this.previousSibling&&this.parentNode.insertBefore(this,this.parentNode.firstChild)}function Et(){return this.each(zt)}function $t(t){return function(){var e=this.ownerDocument,n=this.namespaceURI;return n===F&&e.documentElement.namespaceURI===F?e.createElement(t):e.createElementNS(n,t)}}function St(t){return function(){return this.ownerDocument.createElementNS(t.space,t.local)}}function Tt(t){var e=K(t);return(e.local?St:$t)(e)}function Ct(t){var e="function"==typeof t?t:Tt(t);return this.select(function(){return this.appendChild(e.apply(this,arguments))})}function Xt(){return null}function qt(t,e){var n="function"==typeof t?t:Tt(t),r=null==e?Xt:"function"==typeof e?e:c(e);return this.select(function(){return this.insertBefore(n.apply(this,arguments),r.apply(this,arguments)||null)})}function Vt(){var t=this.parentNode;t&&t.removeChild(this)}function Yt(){return this.each(Vt)}function Ht(){var t=this.cloneNode(!1),e=this.parentNode;return e?e.insertBefore(t,this.nextSibling):t}function Rt(){var t=this.cloneNode(!0),e=this.parentNode;return e?e.insertBefore(t,this.nextSibling):t}function Bt(t){return this.select(t?Rt:Ht)}function Dt(t){return arguments.length?this.property("__data__",t):this.node().__data__}function It(t){return function(){var e=this.__on;if(e){for(var n,r=0,i=-1,o=e.length;r<o;++r)n=e[r],t.type&&n.type!==t.type||n.name!==t.name?e[++i]=n:this.removeEventListener(n.type,n.listener,n.options);++i?e.length=i:delete this.__on}}}function Ft(t,e,n){return function(){var r,i=this.__on,o=function(t){return function(e){t.call(this,e,this.__data__)}}(e);if(i)for(var a=0,u=i.length;a<u;++a)if((r=i[a]).type===t.type&&r.name===t.name)return this.removeEventListener(r.type,r.listener,r.options),this.addEventListener(r.type,r.listener=o,r.options=n),void(r.value=e);this.addEventListener(t.type,o,n),r={type:t.type,name:t.name,value:e,listener:o,options:n},i?i.push(r):this.__on=[r]}}function Lt(t,e,n){var r,i,o=function(t){return t.trim().split(/^|\s+/).map(function(t){var e="",n=t.indexOf(".");return n>=0&&(e=t.slice(n+1),t=t.slice(0,n)),{type:t,name:e}})}(t+""),a=o.length;if(!(arguments.length<2)){for(u=e?Ft:It,r=0;r<a;++r)this.each(u(o[r],e,n));return this}var u=this.node().__on;if(u)for(var l,s=0,c=u.length;s<c;++s)for(r=0,l=u[s];r<a;++r)if((i=o[r]).type===l.type&&i.name===l.name)return l.value}function Kt(t,e,n){var r=et(t),i=r.CustomEvent;"function"==typeof i?i=new i(e,n):(i=r.document.createEvent("Event"),n?(i.initEvent(e,n.bubbles,n.cancelable),i.detail=n.detail):i.initEvent(e,!1,!1)),t.dispatchEvent(i)}function Gt(t,e){return function(){return Kt(this,t,e)}}function Ut(t,e){return function(){return Kt(this,t,e.apply(this,arguments))}}function Wt(t,e){return this.each(("function"==typeof e?Ut:Gt)(t,e))}function*Zt(){for(var t=this._groups,e=0,n=t.length;e<n;++e)for(var r,i=t[e],o=0,a=i.length;o<a;++o)(r=i[o])&&(yield r)}Object.defineProperty(K,"name",{value:"default",configurable:!0}),Object.defineProperty(tt,"name",{value:"default",configurable:!0}),Object.defineProperty(et,"name",{value:"default",configurable:!0}),Object.defineProperty(ot,"name",{value:"default",configurable:!0}),Object.defineProperty(ct,"name",{value:"default",configurable:!0}),Object.defineProperty(bt,"name",{value:"default",configurable:!0}),pt.prototype={add:function(t){this._names.indexOf(t)<0&&(this._names.push(t),this._node.setAttribute("class",this._names.join(" ")))},remove:function(t){var e=this._names.indexOf(t);e>=0&&(this._names.splice(e,1),this._node.setAttribute("class",this._names.join(" ")))},contains:function(t){return this._names.indexOf(t)>=0}},Object.defineProperty(kt,"name",{value:"default",configurable:!0}),Object.defineProperty(jt,"name",{value:"default",configurable:!0}),Object.defineProperty(Nt,"name",{value:"default",configurable:!0}),Object.defineProperty(Et,"name",{value:"default",configurable:!0}),Object.defineProperty(Tt,"name",{value:"default",configurable:!0}),Object.defineProperty(Ct,"name",{value:"default",configurable:!0}),Object.defineProperty(qt,"name",{value:"default",configurable:!0}),Object.defineProperty(Yt,"name",{value:"default",configurable:!0}),Object.defineProperty(Bt,"name",{value:"default",configurable:!0}),Object.defineProperty(Dt,"name",{value:"default",configurable:!0}),Object.defineProperty(Lt,"name",{value:"default",configurable:!0}),Object.defineProperty(Wt,"name",{value:"default",configurable:!0}),Object.defineProperty(Zt,"name",{value:"default",configurable:!0});var Jt=[null];function Qt(t,e){this._groups=t,this._parents=e}function te(){return new Qt([[document.documentElement]],Jt)}Qt.prototype=te.prototype={constructor:Qt,select:f,selectAll:d,selectChild:b,selectChildren:x,filter:k,data:E,enter:M,exit:S,join:T,merge:C,selection:function(){return this},order:X,sort:q,call:Y,nodes:H,node:R,size:B,empty:D,each:I,attr:tt,style:ot,property:ct,classed:bt,text:kt,html:jt,raise:Nt,lower:Et,append:Ct,insert:qt,remove:Yt,clone:Bt,datum:Dt,on:Lt,dispatch:Wt,[Symbol.iterator]:Zt};const ee=te;var ne={value:()=>{}};function re(){for(var t,e=0,n=arguments.length,r={};e<n;++e){if(!(t=arguments[e]+"")||t in r||/[\s.]/.test(t))throw new Error("illegal type: "+t);r[t]=[]}return new ie(r)}function ie(t){this._=t}function oe(t,e){for(var n,r=0,i=t.length;r<i;++r)if((n=t[r]).name===e)return n.value}function ae(t,e,n){for(var r=0,i=t.length;r<i;++r)if(t[r].name===e){t[r]=ne,t=t.slice(0,r).concat(t.slice(r+1));break}return null!=n&&t.push({name:e,value:n}),t}ie.prototype=re.prototype={constructor:ie,on:function(t,e){var n,r,i=this._,o=(r=i,(t+"").trim().split(/^|\s+/).map(function(t){var e="",n=t.indexOf(".");if(n>=0&&(e=t.slice(n+1),t=t.slice(0,n)),t&&!r.hasOwnProperty(t))throw new Error("unknown type: "+t);return{type:t,name:e}})),a=-1,u=o.length;if(!(arguments.length<2)){if(null!=e&&"function"!=typeof e)throw new Error("invalid callback: "+e);for(;++a<u;)if(n=(t=o[a]).type)i[n]=ae(i[n],t.name,e);else if(null==e)for(n in i)i[n]=ae(i[n],t.name,null);return this}for(;++a<u;)if((n=(t=o[a]).type)&&(n=oe(i[n],t.name)))return n},copy:function(){var t={},e=this._;for(var n in e)t[n]=e[n].slice();return new ie(t)},call:function(t,e){if((n=arguments.length-2)>0)for(var n,r,i=new Array(n),o=0;o<n;++o)i[o]=arguments[o+2];if(!this._.hasOwnProperty(t))throw new Error("unknown type: "+t);for(o=0,n=(r=this._[t]).length;o<n;++o)r[o].value.apply(e,i)},apply:function(t,e,n){if(!this._.hasOwnProperty(t))throw new Error("unknown type: "+t);for(var r=this._[t],i=0,o=r.length;i<o;++i)r[i].value.apply(e,n)}};const ue=re;var le,se,ce=0,fe=0,he=0,pe=0,de=0,ye=0,me="object"==typeof performance&&performance.now?performance:Date,ve="object"==typeof window&&window.requestAnimationFrame?window.requestAnimationFrame.bind(window):function(t){setTimeout(t,17)};function ge(){return de||(ve(be),de=me.now()+ye)}function be(){de=0}function we(){this._call=this._time=this._next=null}function _e(t,e,n){var r=new we;return r.restart(t,e,n),r}function xe(){de=(pe=me.now())+ye,ce=fe=0;try{!function(){ge(),++ce;for(var t,e=le;e;)(t=de-e._time)>=0&&e._call.call(void 0,t),e=e._next;--ce}()}finally{ce=0,function(){for(var t,e,n=le,r=1/0;n;)n._call?(r>n._time&&(r=n._time),t=n,n=n._next):(e=n._next,n._next=null,n=t?t._next=e:le=e);se=t,Pe(r)}(),de=0}}function ke(){var t=me.now(),e=t-pe;e>1e3&&(ye-=e,pe=t)}function Pe(t){ce||(fe&&(fe=clearTimeout(fe)),t-de>24?(t<1/0&&(fe=setTimeout(xe,t-me.now()-ye)),he&&(he=clearInterval(he))):(he||(pe=me.now(),he=setInterval(ke,1e3)),ce=1,ve(xe)))}function Me(t,e,n){var r=new we;return e=null==e?0:+e,r.restart(n=>{r.stop(),t(n+e)},e,n),r}we.prototype=_e.prototype={constructor:we,restart:function(t,e,n){if("function"!=typeof t)throw new TypeError("callback is not a function");n=(null==n?ge():+n)+(null==e?0:+e),this._next||se===this||(se?se._next=this:le=this,se=this),this._call=t,this._time=n,Pe()},stop:function(){this._call&&(this._call=null,this._time=1/0,Pe())}},Object.defineProperty(Me,"name",{value:"default",configurable:!0}),Object.defineProperty(Ce,"name",{value:"default",configurable:!0});var Oe=ue("start","end","cancel","interrupt"),je=[],Ae=0,Ne=1,ze=2,Ee=3,$e=4,Se=5,Te=6;function Ce(t,e,n,r,i,o){var a=t.__transition;if(a){if(n in a)return}else t.__transition={};!function(t,e,n){var r,i=t.__transition;function o(l){var s,c,f,h;if(n.state!==Ne)return u();for(s in i)if((h=i[s]).name===n.name){if(h.state===Ee)return Me(o);h.state===$e?(h.state=Te,h.timer.stop(),h.on.call("interrupt",t,t.__data__,h.index,h.group),delete i[s]):+s<e&&(h.state=Te,h.timer.stop(),h.on.call("cancel",t,t.__data__,h.index,h.group),delete i[s])}if(Me(function(){n.state===Ee&&(n.state=$e,n.timer.restart(a,n.delay,n.time),a(l))}),n.state=ze,n.on.call("start",t,t.__data__,n.index,n.group),n.state===ze){for(n.state=Ee,r=new Array(f=n.tween.length),s=0,c=-1;s<f;++s)(h=n.tween[s].value.call(t,t.__data__,n.index,n.group))&&(r[++c]=h);r.length=c+1}}function a(e){for(var i=e<n.duration?n.ease.call(null,e/n.duration):(n.timer.restart(u),n.state=Se,1),o=-1,a=r.length;++o<a;)r[o].call(t,i);n.state===Se&&(n.on.call("end",t,t.__data__,n.index,n.group),u())}function u(){for(var r in n.state=Te,n.timer.stop(),delete i[e],i)return;delete t.__transition}i[e]=n,n.timer=_e(function(t){n.state=Ne,n.timer.restart(o,n.delay,n.time),n.delay<=t&&o(t-n.delay)},0,n.time)}(t,n,{name:e,index:r,group:i,on:Oe,tween:je,time:o.time,delay:o.delay,duration:o.duration,ease:o.ease,timer:null,state:Ae})}function Xe(t,e){var n=Ve(t,e);if(n.state>Ae)throw new Error("too late; already scheduled");return n}function qe(t,e){var n=Ve(t,e);if(n.state>Ee)throw new Error("too late; already running");return n}function Ve(t,e){var n=t.__transition;if(!n||!(n=n[e]))throw new Error("transition not found");return n}function Ye(t,e){var n,r,i,o=t.__transition,a=!0;if(o){for(i in e=null==e?null:e+"",o)(n=o[i]).name===e?(r=n.state>ze&&n.state<Se,n.state=Te,n.timer.stop(),n.on.call(r?"interrupt":"cancel",t,t.__data__,n.index,n.group),delete o[i]):a=!1;a&&delete t.__transition}}function He(t){return this.each(function(){Ye(this,t)})}function Re(t,e){return t=+t,e=+e,function(n){return t*(1-n)+e*n}}Object.defineProperty(Ye,"name",{value:"default",configurable:!0}),Object.defineProperty(He,"name",{value:"default",configurable:!0}),Object.defineProperty(Re,"name",{value:"default",configurable:!0}),Object.defineProperty(Fe,"name",{value:"default",configurable:!0});var Be,De=180/Math.PI,Ie={translateX:0,translateY:0,rotate:0,skewX:0,scaleX:1,scaleY:1};function Fe(t,e,n,r,i,o){var a,u,l;return(a=Math.sqrt(t*t+e*e))&&(t/=a,e/=a),(l=t*n+e*r)&&(n-=t*l,r-=e*l),(u=Math.sqrt(n*n+r*r))&&(n/=u,r/=u,l/=u),t*r<e*n&&(t=-t,e=-e,l=-l,a=-a),{translateX:i,translateY:o,rotate:Math.atan2(e,t)*De,skewX:Math.atan(l)*De,scaleX:a,scaleY:u}}function Le(t,e,n,r){function i(t){return t.length?t.pop()+" ":""}return function(o,a){var u=[],l=[];return o=t(o),a=t(a),function(t,r,i,o,a,u){if(t!==i||r!==o){var l=a.push("translate(",null,e,null,n);u.push({i:l-4,x:Re(t,i)},{i:l-2,x:Re(r,o)})}else(i||o)&&a.push("translate("+i+e+o+n)}(o.translateX,o.translateY,a.translateX,a.translateY,u,l),function(t,e,n,o){t!==e?(t-e>180?e+=360:e-t>180&&(t+=360),o.push({i:n.push(i(n)+"rotate(",null,r)-2,x:Re(t,e)})):e&&n.push(i(n)+"rotate("+e+r)}(o.rotate,a.rotate,u,l),function(t,e,n,o){t!==e?o.push({i:n.push(i(n)+"skewX(",null,r)-2,x:Re(t,e)}):e&&n.push(i(n)+"skewX("+e+r)}(o.skewX,a.skewX,u,l),function(t,e,n,r,o,a){if(t!==n||e!==r){var u=o.push(i(o)+"scale(",null,",",null,")");a.push({i:u-4,x:Re(t,n)},{i:u-2,x:Re(e,r)})}else 1===n&&1===r||o.push(i(o)+"scale("+n+","+r+")")}(o.scaleX,o.scaleY,a.scaleX,a.scaleY,u,l),o=a=null,function(t){for(var e,n=-1,r=l.length;++n<r;)u[(e=l[n]).i]=e.x(t);return u.join("")}}}var Ke=Le(function(t){const e=new("function"==typeof DOMMatrix?DOMMatrix:WebKitCSSMatrix)(t+"");return e.isIdentity?Ie:Fe(e.a,e.b,e.c,e.d,e.e,e.f)},"px, ","px)","deg)"),Ge=Le(function(t){return null==t?Ie:(Be||(Be=document.createElementNS("http://www.w3.org/2000/svg","g")),Be.setAttribute("transform",t),(t=Be.transform.baseVal.consolidate())?Fe((t=t.matrix).a,t.b,t.c,t.d,t.e,t.f):Ie)},", ",")",")");function Ue(t,e){var n,r;return function(){var i=qe(this,t),o=i.tween;if(o!==n)for(var a=0,u=(r=n=o).length;a<u;++a)if(r[a].name===e){(r=r.slice()).splice(a,1);break}i.tween=r}}function We(t,e,n){var r,i;if("function"!=typeof n)throw new Error;return function(){var o=qe(this,t),a=o.tween;if(a!==r){i=(r=a).slice();for(var u={name:e,value:n},l=0,s=i.length;l<s;++l)if(i[l].name===e){i[l]=u;break}l===s&&i.push(u)}o.tween=i}}function Ze(t,e){var n=this._id;if(t+="",arguments.length<2){for(var r,i=Ve(this.node(),n).tween,o=0,a=i.length;o<a;++o)if((r=i[o]).name===t)return r.value;return null}return this.each((null==e?Ue:We)(n,t,e))}function Je(t,e,n){var r=t._id;return t.each(function(){var t=qe(this,r);(t.value||(t.value={}))[e]=n.apply(this,arguments)}),function(t){return Ve(t,r).value[e]}}function Qe(t,e,n){t.prototype=e.prototype=n,n.constructor=t}function tn(t,e){var n=Object.create(t.prototype);for(var r in e)n[r]=e[r];return n}function en(){}Object.defineProperty(Ze,"name",{value:"default",configurable:!0}),Object.defineProperty(Qe,"name",{value:"default",configurable:!0});var nn=.7,rn=1/nn,on="\\s*([+-]?\\d+)\\s*",an="\\s*([+-]?(?:\\d*\\.)?\\d+(?:[eE][+-]?\\d+)?)\\s*",un="\\s*([+-]?(?:\\d*\\.)?\\d+(?:[eE][+-]?\\d+)?)%\\s*",ln=/^#([0-9a-f]{3,8})$/,sn=new RegExp(`^rgb\\(${on},${on},${on}\\)$`),cn=new RegExp(`^rgb\\(${un},${un},${un}\\)$`),fn=new RegExp(`^rgba\\(${on},${on},${on},${an}\\)$`),hn=new RegExp(`^rgba\\(${un},${un},${un},${an}\\)$`),pn=new RegExp(`^hsl\\(${an},${un},${un}\\)$`),dn=new RegExp(`^hsla\\(${an},${un},${un},${an}\\)$`),yn={aliceblue:15792383,antiquewhite:16444375,aqua:65535,aquamarine:8388564,azure:15794175,beige:16119260,bisque:16770244,black:0,blanchedalmond:16772045,blue:255,blueviolet:9055202,brown:10824234,burlywood:14596231,cadetblue:6266528,chartreuse:8388352,chocolate:13789470,coral:16744272,cornflowerblue:6591981,cornsilk:16775388,crimson:14423100,cyan:65535,darkblue:139,darkcyan:35723,darkgoldenrod:12092939,darkgray:11119017,darkgreen:25600,darkgrey:11119017,darkkhaki:12433259,darkmagenta:9109643,darkolivegreen:5597999,darkorange:16747520,darkorchid:10040012,darkred:9109504,darksalmon:15308410,darkseagreen:9419919,darkslateblue:4734347,darkslategray:3100495,darkslategrey:3100495,darkturquoise:52945,darkviolet:9699539,deeppink:16716947,deepskyblue:49151,dimgray:6908265,dimgrey:6908265,dodgerblue:2003199,firebrick:11674146,floralwhite:16775920,forestgreen:2263842,fuchsia:16711935,gainsboro:14474460,ghostwhite:16316671,gold:16766720,goldenrod:14329120,gray:8421504,green:32768,greenyellow:11403055,grey:8421504,honeydew:15794160,hotpink:16738740,indianred:13458524,indigo:4915330,ivory:16777200,khaki:15787660,lavender:15132410,lavenderblush:16773365,lawngreen:8190976,lemonchiffon:16775885,lightblue:11393254,lightcoral:15761536,lightcyan:14745599,lightgoldenrodyellow:16448210,lightgray:13882323,lightgreen:9498256,lightgrey:13882323,lightpink:16758465,lightsalmon:16752762,lightseagreen:2142890,lightskyblue:8900346,lightslategray:7833753,lightslategrey:7833753,lightsteelblue:11584734,lightyellow:16777184,lime:65280,limegreen:3329330,linen:16445670,magenta:16711935,maroon:8388608,mediumaquamarine:6737322,mediumblue:205,mediumorchid:12211667,mediumpurple:9662683,mediumseagreen:3978097,mediumslateblue:8087790,mediumspringgreen:64154,mediumturquoise:4772300,mediumvioletred:13047173,midnightblue:1644912,mintcream:16121850,mistyrose:16770273,moccasin:16770229,navajowhite:16768685,navy:128,oldlace:16643558,olive:8421376,olivedrab:7048739,orange:16753920,orangered:16729344,orchid:14315734,palegoldenrod:15657130,palegreen:10025880,paleturquoise:11529966,palevioletred:14381203,papayawhip:16773077,peachpuff:16767673,peru:13468991,pink:16761035,plum:14524637,powderblue:11591910,purple:8388736,rebeccapurple:6697881,red:16711680,rosybrown:12357519,royalblue:4286945,saddlebrown:9127187,salmon:16416882,sandybrown:16032864,seagreen:3050327,seashell:16774638,sienna:10506797,silver:12632256,skyblue:8900331,slateblue:6970061,slategray:7372944,slategrey:7372944,snow:16775930,springgreen:65407,steelblue:4620980,tan:13808780,teal:32896,thistle:14204888,tomato:16737095,turquoise:4251856,violet:15631086,wheat:16113331,white:16777215,whitesmoke:16119285,yellow:16776960,yellowgreen:10145074};function mn(){return this.rgb().formatHex()}function vn(){return this.rgb().formatRgb()}function gn(t){var e,n;return t=(t+"").trim().toLowerCase(),(e=ln.exec(t))?(n=e[1].length,e=parseInt(e[1],16),6===n?bn(e):3===n?new xn(e>>8&15|e>>4&240,e>>4&15|240&e,(15&e)<<4|15&e,1):8===n?wn(e>>24&255,e>>16&255,e>>8&255,(255&e)/255):4===n?wn(e>>12&15|e>>8&240,e>>8&15|e>>4&240,e>>4&15|240&e,((15&e)<<4|15&e)/255):null):(e=sn.exec(t))?new xn(e[1],e[2],e[3],1):(e=cn.exec(t))?new xn(255*e[1]/100,255*e[2]/100,255*e[3]/100,1):(e=fn.exec(t))?wn(e[1],e[2],e[3],e[4]):(e=hn.exec(t))?wn(255*e[1]/100,255*e[2]/100,255*e[3]/100,e[4]):(e=pn.exec(t))?An(e[1],e[2]/100,e[3]/100,1):(e=dn.exec(t))?An(e[1],e[2]/100,e[3]/100,e[4]):yn.hasOwnProperty(t)?bn(yn[t]):"transparent"===t?new xn(NaN,NaN,NaN,0):null}function bn(t){return new xn(t>>16&255,t>>8&255,255&t,1)}function wn(t,e,n,r){return r<=0&&(t=e=n=NaN),new xn(t,e,n,r)}function _n(t,e,n,r){return 1===arguments.length?((i=t)instanceof en||(i=gn(i)),i?new xn((i=i.rgb()).r,i.g,i.b,i.opacity):new xn):new xn(t,e,n,r??1);var i}function xn(t,e,n,r){this.r=+t,this.g=+e,this.b=+n,this.opacity=+r}function kn(){return`#${jn(this.r)}${jn(this.g)}${jn(this.b)}`}function Pn(){const t=Mn(this.opacity);return`${1===t?"rgb(":"rgba("}${On(this.r)}, ${On(this.g)}, ${On(this.b)}${1===t?")":`, ${t})`}`}function Mn(t){return isNaN(t)?1:Math.max(0,Math.min(1,t))}function On(t){return Math.max(0,Math.min(255,Math.round(t)||0))}function jn(t){return((t=On(t))<16?"0":"")+t.toString(16)}function An(t,e,n,r){return r<=0?t=e=n=NaN:n<=0||n>=1?t=e=NaN:e<=0&&(t=NaN),new zn(t,e,n,r)}function Nn(t){if(t instanceof zn)return new zn(t.h,t.s,t.l,t.opacity);if(t instanceof en||(t=gn(t)),!t)return new zn;if(t instanceof zn)return t;var e=(t=t.rgb()).r/255,n=t.g/255,r=t.b/255,i=Math.min(e,n,r),o=Math.max(e,n,r),a=NaN,u=o-i,l=(o+i)/2;return u?(a=e===o?(n-r)/u+6*(n<r):n===o?(r-e)/u+2:(e-n)/u+4,u/=l<.5?o+i:2-o-i,a*=60):u=l>0&&l<1?0:a,new zn(a,u,l,t.opacity)}function zn(t,e,n,r){this.h=+t,this.s=+e,this.l=+n,this.opacity=+r}function En(t){return(t=(t||0)%360)<0?t+360:t}function $n(t){return Math.max(0,Math.min(1,t||0))}function Sn(t,e,n){return 255*(t<60?e+(n-e)*t/60:t<180?n:t<240?e+(n-e)*(240-t)/60:e)}function Tn(t,e,n,r,i){var o=t*t,a=o*t;return((1-3*t+3*o-a)*e+(4-6*o+3*a)*n+(1+3*t+3*o-3*a)*r+a*i)/6}function Cn(t){var e=t.length-1;return function(n){var r=n<=0?n=0:n>=1?(n=1,e-1):Math.floor(n*e),i=t[r],o=t[r+1],a=r>0?t[r-1]:2*i-o,u=r<e-1?t[r+2]:2*o-i;return Tn((n-r/e)*e,a,i,o,u)}}function Xn(t){var e=t.length;return function(n){var r=Math.floor(((n%=1)<0?++n:n)*e),i=t[(r+e-1)%e],o=t[r%e],a=t[(r+1)%e],u=t[(r+2)%e];return Tn((n-r/e)*e,i,o,a,u)}}Qe(en,gn,{copy(t){return Object.assign(new this.constructor,this,t)},displayable(){return this.rgb().displayable()},hex:mn,formatHex:mn,formatHex8:function(){return this.rgb().formatHex8()},formatHsl:function(){return Nn(this).formatHsl()},formatRgb:vn,toString:vn}),Qe(xn,_n,tn(en,{brighter(t){return t=null==t?rn:Math.pow(rn,t),new xn(this.r*t,this.g*t,this.b*t,this.opacity)},darker(t){return t=null==t?nn:Math.pow(nn,t),new xn(this.r*t,this.g*t,this.b*t,this.opacity)},rgb(){return this},clamp(){return new xn(On(this.r),On(this.g),On(this.b),Mn(this.opacity))},displayable(){return-.5<=this.r&&this.r<255.5&&-.5<=this.g&&this.g<255.5&&-.5<=this.b&&this.b<255.5&&0<=this.opacity&&this.opacity<=1},hex:kn,formatHex:kn,formatHex8:function(){return`#${jn(this.r)}${jn(this.g)}${jn(this.b)}${jn(255*(isNaN(this.opacity)?1:this.opacity))}`},formatRgb:Pn,toString:Pn})),Qe(zn,function(t,e,n,r){return 1===arguments.length?Nn(t):new zn(t,e,n,r??1)},tn(en,{brighter(t){return t=null==t?rn:Math.pow(rn,t),new zn(this.h,this.s,this.l*t,this.opacity)},darker(t){return t=null==t?nn:Math.pow(nn,t),new zn(this.h,this.s,this.l*t,this.opacity)},rgb(){var t=this.h%360+360*(this.h<0),e=isNaN(t)||isNaN(this.s)?0:this.s,n=this.l,r=n+(n<.5?n:1-n)*e,i=2*n-r;return new xn(Sn(t>=240?t-240:t+120,i,r),Sn(t,i,r),Sn(t<120?t+240:t-120,i,r),this.opacity)},clamp(){return new zn(En(this.h),$n(this.s),$n(this.l),Mn(this.opacity))},displayable(){return(0<=this.s&&this.s<=1||isNaN(this.s))&&0<=this.l&&this.l<=1&&0<=this.opacity&&this.opacity<=1},formatHsl(){const t=Mn(this.opacity);return`${1===t?"hsl(":"hsla("}${En(this.h)}, ${100*$n(this.s)}%, ${100*$n(this.l)}%${1===t?")":`, ${t})`}`}})),Object.defineProperty(Cn,"name",{value:"default",configurable:!0}),Object.defineProperty(Xn,"name",{value:"default",configurable:!0});const qn=t=>()=>t;function Vn(t,e){var n=e-t;return n?function(t,e){return function(n){return t+n*e}}(t,n):qn(isNaN(t)?e:t)}(Object.getOwnPropertyDescriptor(qn,"name")||{}).writable||Object.defineProperty(qn,"name",{value:"default",configurable:!0});const Yn=function t(e){var n=function(t){return 1===(t=+t)?Vn:function(e,n){return n-e?function(t,e,n){return t=Math.pow(t,n),e=Math.pow(e,n)-t,n=1/n,function(r){return Math.pow(t+r*e,n)}}(e,n,t):qn(isNaN(e)?n:e)}}(e);function r(t,e){var r=n((t=_n(t)).r,(e=_n(e)).r),i=n(t.g,e.g),o=n(t.b,e.b),a=Vn(t.opacity,e.opacity);return function(e){return t.r=r(e),t.g=i(e),t.b=o(e),t.opacity=a(e),t+""}}return r.gamma=t,r}(1);function Hn(t){return function(e){var n,r,i=e.length,o=new Array(i),a=new Array(i),u=new Array(i);for(n=0;n<i;++n)r=_n(e[n]),o[n]=r.r||0,a[n]=r.g||0,u[n]=r.b||0;return o=t(o),a=t(a),u=t(u),r.opacity=1,function(t){return r.r=o(t),r.g=a(t),r.b=u(t),r+""}}}Hn(Cn),Hn(Xn),Object.defineProperty(Dn,"name",{value:"default",configurable:!0});var Rn=/[-+]?(?:\d+\.?\d*|\.?\d+)(?:[eE][-+]?\d+)?/g,Bn=new RegExp(Rn.source,"g");function Dn(t,e){var n,r,i,o=Rn.lastIndex=Bn.lastIndex=0,a=-1,u=[],l=[];for(t+="",e+="";(n=Rn.exec(t))&&(r=Bn.exec(e));)(i=r.index)>o&&(i=e.slice(o,i),u[a]?u[a]+=i:u[++a]=i),(n=n[0])===(r=r[0])?u[a]?u[a]+=r:u[++a]=r:(u[++a]=null,l.push({i:a,x:Re(n,r)})),o=Bn.lastIndex;return o<e.length&&(i=e.slice(o),u[a]?u[a]+=i:u[++a]=i),u.length<2?l[0]?function(t){return function(e){return t(e)+""}}(l[0].x):function(t){return function(){return t}}(e):(e=l.length,function(t){for(var n,r=0;r<e;++r)u[(n=l[r]).i]=n.x(t);return u.join("")})}function In(t,e){var n;return("number"==typeof e?Re:e instanceof gn?Yn:(n=gn(e))?(e=n,Yn):Dn)(t,e)}function Fn(t){return function(){this.removeAttribute(t)}}function Ln(t){return function(){this.removeAttributeNS(t.space,t.local)}}function Kn(t,e,n){var r,i,o=n+"";return function(){var a=this.getAttribute(t);return a===o?null:a===r?i:i=e(r=a,n)}}function Gn(t,e,n){var r,i,o=n+"";return function(){var a=this.getAttributeNS(t.space,t.local);return a===o?null:a===r?i:i=e(r=a,n)}}function Un(t,e,n){var r,i,o;return function(){var a,u,l=n(this);if(null!=l)return(a=this.getAttribute(t))===(u=l+"")?null:a===r&&u===i?o:(i=u,o=e(r=a,l));this.removeAttribute(t)}}function Wn(t,e,n){var r,i,o;return function(){var a,u,l=n(this);if(null!=l)return(a=this.getAttributeNS(t.space,t.local))===(u=l+"")?null:a===r&&u===i?o:(i=u,o=e(r=a,l));this.removeAttributeNS(t.space,t.local)}}function Zn(t,e){var n=K(t),r="transform"===n?Ge:In;return this.attrTween(t,"function"==typeof e?(n.local?Wn:Un)(n,r,Je(this,"attr."+t,e)):null==e?(n.local?Ln:Fn)(n):(n.local?Gn:Kn)(n,r,e))}function Jn(t,e){var n,r;function i(){var i=e.apply(this,arguments);return i!==r&&(n=(r=i)&&function(t,e){return function(n){this.setAttributeNS(t.space,t.local,e.call(this,n))}}(t,i)),n}return i._value=e,i}function Qn(t,e){var n,r;function i(){var i=e.apply(this,arguments);return i!==r&&(n=(r=i)&&function(t,e){return function(n){this.setAttribute(t,e.call(this,n))}}(t,i)),n}return i._value=e,i}function tr(t,e){var n="attr."+t;if(arguments.length<2)return(n=this.tween(n))&&n._value;if(null==e)return this.tween(n,null);if("function"!=typeof e)throw new Error;var r=K(t);return this.tween(n,(r.local?Jn:Qn)(r,e))}function er(t,e){return function(){Xe(this,t).delay=+e.apply(this,arguments)}}function nr(t,e){return e=+e,function(){Xe(this,t).delay=e}}function rr(t){var e=this._id;return arguments.length?this.each(("function"==typeof t?er:nr)(e,t)):Ve(this.node(),e).delay}function ir(t,e){return function(){qe(this,t).duration=+e.apply(this,arguments)}}function or(t,e){return e=+e,function(){qe(this,t).duration=e}}function ar(t){var e=this._id;return arguments.length?this.each(("function"==typeof t?ir:or)(e,t)):Ve(this.node(),e).duration}function ur(t){var e=this._id;return arguments.length?this.each(function(t,e){if("function"!=typeof e)throw new Error;return function(){qe(this,t).ease=e}}(e,t)):Ve(this.node(),e).ease}function lr(t){if("function"!=typeof t)throw new Error;return this.each(function(t,e){return function(){var n=e.apply(this,arguments);if("function"!=typeof n)throw new Error;qe(this,t).ease=n}}(this._id,t))}function sr(t){"function"!=typeof t&&(t=y(t));for(var e=this._groups,n=e.length,r=new Array(n),i=0;i<n;++i)for(var o,a=e[i],u=a.length,l=r[i]=[],s=0;s<u;++s)(o=a[s])&&t.call(o,o.__data__,s,a)&&l.push(o);return new Mr(r,this._parents,this._name,this._id)}function cr(t){if(t._id!==this._id)throw new Error;for(var e=this._groups,n=t._groups,r=e.length,i=n.length,o=Math.min(r,i),a=new Array(r),u=0;u<o;++u)for(var l,s=e[u],c=n[u],f=s.length,h=a[u]=new Array(f),p=0;p<f;++p)(l=s[p]||c[p])&&(h[p]=l);for(;u<r;++u)a[u]=e[u];return new Mr(a,this._parents,this._name,this._id)}function fr(t,e){var n=this._id;return arguments.length<2?Ve(this.node(),n).on.on(t):this.each(function(t,e,n){var r,i,o=function(t){return(t+"").trim().split(/^|\s+/).every(function(t){var e=t.indexOf(".");return e>=0&&(t=t.slice(0,e)),!t||"start"===t})}(e)?Xe:qe;return function(){var a=o(this,t),u=a.on;u!==r&&(i=(r=u).copy()).on(e,n),a.on=i}}(n,t,e))}function hr(){return this.on("end.remove",function(t){return function(){var e=this.parentNode;for(var n in this.__transition)if(+n!==t)return;e&&e.removeChild(this)}}(this._id))}function pr(t){var e=this._name,n=this._id;"function"!=typeof t&&(t=c(t));for(var r=this._groups,i=r.length,o=new Array(i),a=0;a<i;++a)for(var u,l,s=r[a],f=s.length,h=o[a]=new Array(f),p=0;p<f;++p)(u=s[p])&&(l=t.call(u,u.__data__,p,s))&&("__data__"in u&&(l.__data__=u.__data__),h[p]=l,Ce(h[p],e,n,p,h,Ve(u,n)));return new Mr(o,this._parents,e,n)}function dr(t){var e=this._name,n=this._id;"function"!=typeof t&&(t=p(t));for(var r=this._groups,i=r.length,o=[],a=[],u=0;u<i;++u)for(var l,s=r[u],c=s.length,f=0;f<c;++f)if(l=s[f]){for(var h,d=t.call(l,l.__data__,f,s),y=Ve(l,n),m=0,v=d.length;m<v;++m)(h=d[m])&&Ce(h,e,n,m,d,y);o.push(d),a.push(l)}return new Mr(o,a,e,n)}Object.defineProperty(In,"name",{value:"default",configurable:!0}),Object.defineProperty(Zn,"name",{value:"default",configurable:!0}),Object.defineProperty(tr,"name",{value:"default",configurable:!0}),Object.defineProperty(rr,"name",{value:"default",configurable:!0}),Object.defineProperty(ar,"name",{value:"default",configurable:!0}),Object.defineProperty(ur,"name",{value:"default",configurable:!0}),Object.defineProperty(lr,"name",{value:"default",configurable:!0}),Object.defineProperty(sr,"name",{value:"default",configurable:!0}),Object.defineProperty(cr,"name",{value:"default",configurable:!0}),Object.defineProperty(fr,"name",{value:"default",configurable:!0}),Object.defineProperty(hr,"name",{value:"default",configurable:!0}),Object.defineProperty(pr,"name",{value:"default",configurable:!0}),Object.defineProperty(dr,"name",{value:"default",configurable:!0}),Object.defineProperty(mr,"name",{value:"default",configurable:!0});var yr=ee.prototype.constructor;function mr(){return new yr(this._groups,this._parents)}function vr(t){return function(){this.style.removeProperty(t)}}function gr(t,e,n){var r="transform"==(t+="")?Ke:In;return null==e?this.styleTween(t,function(t,e){var n,r,i;return function(){var o=at(this,t),a=(this.style.removeProperty(t),at(this,t));return o===a?null:o===n&&a===r?i:i=e(n=o,r=a)}}(t,r)).on("end.style."+t,vr(t)):"function"==typeof e?this.styleTween(t,function(t,e,n){var r,i,o;return function(){var a=at(this,t),u=n(this),l=u+"";return null==u&&(this.style.removeProperty(t),l=u=at(this,t)),a===l?null:a===r&&l===i?o:(i=l,o=e(r=a,u))}}(t,r,Je(this,"style."+t,e))).each(function(t,e){var n,r,i,o,a="style."+e,u="end."+a;return function(){var l=qe(this,t),s=l.on,c=null==l.value[a]?o||(o=vr(e)):void 0;s===n&&i===c||(r=(n=s).copy()).on(u,i=c),l.on=r}}(this._id,t)):this.styleTween(t,function(t,e,n){var r,i,o=n+"";return function(){var a=at(this,t);return a===o?null:a===r?i:i=e(r=a,n)}}(t,r,e),n).on("end.style."+t,null)}function br(t,e,n){var r="style."+(t+="");if(arguments.length<2)return(r=this.tween(r))&&r._value;if(null==e)return this.tween(r,null);if("function"!=typeof e)throw new Error;return this.tween(r,function(t,e,n){var r,i;function o(){var o=e.apply(this,arguments);return o!==i&&(r=(i=o)&&function(t,e,n){return function(r){this.style.setProperty(t,e.call(this,r),n)}}(t,o,n)),r}return o._value=e,o}(t,e,n??""))}function wr(t){return this.tween("text","function"==typeof t?function(t){return function(){var e=t(this);this.textContent=e??""}}(Je(this,"text",t)):function(t){return function(){this.textContent=t}}(null==t?"":t+""))}function _r(t){var e="text";if(arguments.length<1)return(e=this.tween(e))&&e._value;if(null==t)return this.tween(e,null);if("function"!=typeof t)throw new Error;return this.tween(e,function(t){var e,n;function r(){var r=t.apply(this,arguments);return r!==n&&(e=(n=r)&&function(t){return function(e){this.textContent=t.call(this,e)}}(r)),e}return r._value=t,r}(t))}function xr(){for(var t=this._name,e=this._id,n=Or(),r=this._groups,i=r.length,o=0;o<i;++o)for(var a,u=r[o],l=u.length,s=0;s<l;++s)if(a=u[s]){var c=Ve(a,e);Ce(a,t,n,s,u,{time:c.time+c.delay+c.duration,delay:0,duration:c.duration,ease:c.ease})}return new Mr(r,this._parents,t,n)}function kr(){var t,e,n=this,r=n._id,i=n.size();return new Promise(function(o,a){var u={value:a},l={value:function(){0===--i&&o()}};n.each(function(){var n=qe(this,r),i=n.on;i!==t&&((e=(t=i).copy())._.cancel.push(u),e._.interrupt.push(u),e._.end.push(l)),n.on=e}),0===i&&o()})}Object.defineProperty(gr,"name",{value:"default",configurable:!0}),Object.defineProperty(br,"name",{value:"default",configurable:!0}),Object.defineProperty(wr,"name",{value:"default",configurable:!0}),Object.defineProperty(_r,"name",{value:"default",configurable:!0}),Object.defineProperty(xr,"name",{value:"default",configurable:!0}),Object.defineProperty(kr,"name",{value:"default",configurable:!0});var Pr=0;function Mr(t,e,n,r){this._groups=t,this._parents=e,this._name=n,this._id=r}function Or(){return++Pr}var jr=ee.prototype;Mr.prototype=function(t){return ee().transition(t)}.prototype={constructor:Mr,select:pr,selectAll:dr,selectChild:jr.selectChild,selectChildren:jr.selectChildren,filter:sr,merge:cr,selection:mr,transition:xr,call:jr.call,nodes:jr.nodes,node:jr.node,size:jr.size,empty:jr.empty,each:jr.each,on:fr,attr:Zn,attrTween:tr,style:gr,styleTween:br,text:wr,textTween:_r,remove:hr,tween:Ze,delay:rr,duration:ar,ease:ur,easeVarying:lr,end:kr,[Symbol.iterator]:jr[Symbol.iterator]},Object.defineProperty(Rr,"name",{value:"default",configurable:!0});var Ar,Nr,zr,Er,$r,Sr,Tr,Cr,Xr,qr,Vr,Yr={time:null,delay:0,duration:250,ease:function(t){return((t*=2)<=1?t*t*t:(t-=2)*t*t+2)/2}};function Hr(t,e){for(var n;!(n=t.__transition)||!(n=n[e]);)if(!(t=t.parentNode))throw new Error(`transition ${e} not found`);return n}function Rr(t){var e,n;t instanceof Mr?(e=t._id,t=t._name):(e=Or(),(n=Yr).time=ge(),t=null==t?null:t+"");for(var r=this._groups,i=r.length,o=0;o<i;++o)for(var a,u=r[o],l=u.length,s=0;s<l;++s)(a=u[s])&&Ce(a,t,e,s,u,n||Hr(a,e));return new Mr(r,this._parents,t,e)}ee.prototype.interrupt=He,ee.prototype.transition=Rr,Object.defineProperty(function(){return function(t){var e,n=ui,r=ai,i=li,o=!0,a=Ar("start","brush","end"),u=6;function l(e){var n=e.property("__brush",y).selectAll(".overlay").data([oi("overlay")]);n.enter().append("rect").attr("class","overlay").attr("pointer-events","all").attr("cursor",ti.overlay).merge(n).each(function(){var t=si(this).extent;Sr(this).attr("x",t[0][0]).attr("y",t[0][1]).attr("width",t[1][0]-t[0][0]).attr("height",t[1][1]-t[0][1])}),e.selectAll(".selection").data([oi("selection")]).enter().append("rect").attr("class","selection").attr("cursor",ti.selection).attr("fill","#777").attr("fill-opacity",.3).attr("stroke","#fff").attr("shape-rendering","crispEdges");var r=e.selectAll(".handle").data(t.handles,function(t){return t.type});r.exit().remove(),r.enter().append("rect").attr("class",function(t){return"handle handle--"+t.type}).attr("cursor",function(t){return ti[t.type]}),e.each(s).attr("fill","none").attr("pointer-events","all").on("mousedown.brush",h).filter(i).on("touchstart.brush",h).on("touchmove.brush",p).on("touchend.brush touchcancel.brush",d).style("touch-action","none").style("-webkit-tap-highlight-color","rgba(0,0,0,0)")}function s(){var t=Sr(this),e=si(this).selection;e?(t.selectAll(".selection").style("display",null).attr("x",e[0][0]).attr("y",e[0][1]).attr("width",e[1][0]-e[0][0]).attr("height",e[1][1]-e[0][1]),t.selectAll(".handle").style("display",null).attr("x",function(t){return"e"===t.type[t.type.length-1]?e[1][0]-u/2:e[0][0]-u/2}).attr("y",function(t){return"s"===t.type[0]?e[1][1]-u/2:e[0][1]-u/2}).attr("width",function(t){return"n"===t.type||"s"===t.type?e[1][0]-e[0][0]+u:u}).attr("height",function(t){return"e"===t.type||"w"===t.type?e[1][1]-e[0][1]+u:u})):t.selectAll(".selection,.handle").style("display","none").attr("x",null).attr("y",null).attr("width",null).attr("height",null)}function c(t,e,n){var r=t.__brush.emitter;return!r||n&&r.clean?new f(t,e,n):r}function f(t,e,n){this.that=t,this.args=e,this.state=t.__brush,this.active=0,this.clean=n}function h(n){if((!e||n.touches)&&r.apply(this,arguments)){var i,a,u,l,f,h,p,d,y,m,v,g=this,b=n.target.__data__.type,w="selection"===(o&&n.metaKey?b="overlay":b)?Br:o&&n.altKey?Fr:Ir,_=t===Jr?null:ri[b],x=t===Zr?null:ii[b],k=si(g),P=k.extent,M=k.selection,O=P[0][0],j=P[0][1],A=P[1][0],N=P[1][1],z=0,E=0,$=_&&x&&o&&n.shiftKey,S=Array.from(n.touches||[n],t=>{const e=t.identifier;return(t=$r(t,g)).point0=t.slice(),t.identifier=e,t});Tr(g);var T=c(g,arguments,!0).beforestart();if("overlay"===b){M&&(y=!0);const e=[S[0],S[1]||S[0]];k.selection=M=[[i=t===Jr?O:Gr(e[0][0],e[1][0]),u=t===Zr?j:Gr(e[0][1],e[1][1])],[f=t===Jr?A:Kr(e[0][0],e[1][0]),p=t===Zr?N:Kr(e[0][1],e[1][1])]],S.length>1&&Y(n)}else i=M[0][0],u=M[0][1],f=M[1][0],p=M[1][1];a=i,l=u,h=f,d=p;var C=Sr(g).attr("pointer-events","none"),X=C.selectAll(".overlay").attr("cursor",ti[b]);if(n.touches)T.moved=V,T.ended=H;else{var q=Sr(n.view).on("mousemove.brush",V,!0).on("mouseup.brush",H,!0);o&&q.on("keydown.brush",function(t){switch(t.keyCode){case 16:$=_&&x;break;case 18:w===Ir&&(_&&(f=h-z*_,i=a+z*_),x&&(p=d-E*x,u=l+E*x),w=Fr,Y(t));break;case 32:w!==Ir&&w!==Fr||(_<0?f=h-z:_>0&&(i=a-z),x<0?p=d-E:x>0&&(u=l-E),w=Dr,X.attr("cursor",ti.selection),Y(t));break;default:return}qr(t)},!0).on("keyup.brush",function(t){switch(t.keyCode){case 16:$&&(m=v=$=!1,Y(t));break;case 18:w===Fr&&(_<0?f=h:_>0&&(i=a),x<0?p=d:x>0&&(u=l),w=Ir,Y(t));break;case 32:w===Dr&&(t.altKey?(_&&(f=h-z*_,i=a+z*_),x&&(p=d-E*x,u=l+E*x),w=Fr):(_<0?f=h:_>0&&(i=a),x<0?p=d:x>0&&(u=l),w=Ir),X.attr("cursor",ti[b]),Y(t));break;default:return}qr(t)},!0),Nr(n.view)}s.call(g),T.start(n,w.name)}function V(t){for(const e of t.changedTouches||[t])for(const t of S)t.identifier===e.identifier&&(t.cur=$r(e,g));if($&&!m&&!v&&1===S.length){const t=S[0];Lr(t.cur[0]-t[0])>Lr(t.cur[1]-t[1])?v=!0:m=!0}for(const t of S)t.cur&&(t[0]=t.cur[0],t[1]=t.cur[1]);y=!0,qr(t),Y(t)}function Y(t){const e=S[0],n=e.point0;var r;switch(z=e[0]-n[0],E=e[1]-n[1],w){case Dr:case Br:_&&(z=Kr(O-i,Gr(A-f,z)),a=i+z,h=f+z),x&&(E=Kr(j-u,Gr(N-p,E)),l=u+E,d=p+E);break;case Ir:S[1]?(_&&(a=Kr(O,Gr(A,S[0][0])),h=Kr(O,Gr(A,S[1][0])),_=1),x&&(l=Kr(j,Gr(N,S[0][1])),d=Kr(j,Gr(N,S[1][1])),x=1)):(_<0?(z=Kr(O-i,Gr(A-i,z)),a=i+z,h=f):_>0&&(z=Kr(O-f,Gr(A-f,z)),a=i,h=f+z),x<0?(E=Kr(j-u,Gr(N-u,E)),l=u+E,d=p):x>0&&(E=Kr(j-p,Gr(N-p,E)),l=u,d=p+E));break;case Fr:_&&(a=Kr(O,Gr(A,i-z*_)),h=Kr(O,Gr(A,f+z*_))),x&&(l=Kr(j,Gr(N,u-E*x)),d=Kr(j,Gr(N,p+E*x)))}h<a&&(_*=-1,r=i,i=f,f=r,r=a,a=h,h=r,b in ei&&X.attr("cursor",ti[b=ei[b]])),d<l&&(x*=-1,r=u,u=p,p=r,r=l,l=d,d=r,b in ni&&X.attr("cursor",ti[b=ni[b]])),k.selection&&(M=k.selection),m&&(a=M[0][0],h=M[1][0]),v&&(l=M[0][1],d=M[1][1]),M[0][0]===a&&M[0][1]===l&&M[1][0]===h&&M[1][1]===d||(k.selection=[[a,l],[h,d]],s.call(g),T.brush(t,w.name))}function H(t){if(Vr(t),t.touches){if(t.touches.length)return;e&&clearTimeout(e),e=setTimeout(function(){e=null},500)}else zr(t.view,y),q.on("keydown.brush keyup.brush mousemove.brush mouseup.brush",null);C.attr("pointer-events","all"),X.attr("cursor",ti.overlay),k.selection&&(M=k.selection),function(t){return t[0][0]===t[1][0]||t[0][1]===t[1][1]}(M)&&(k.selection=null,s.call(g)),T.end(t,w.name)}}function p(t){c(this,arguments).moved(t)}function d(t){c(this,arguments).ended(t)}function y(){var e=this.__brush||{selection:null};return e.extent=Wr(n.apply(this,arguments)),e.dim=t,e}return l.move=function(e,n,r){e.tween?e.on("start.brush",function(t){c(this,arguments).beforestart().start(t)}).on("interrupt.brush end.brush",function(t){c(this,arguments).end(t)}).tween("brush",function(){var e=this,r=e.__brush,i=c(e,arguments),o=r.selection,a=t.input("function"==typeof n?n.apply(this,arguments):n,r.extent),u=Er(o,a);function l(t){r.selection=1===t&&null===a?null:u(t),s.call(e),i.brush()}return null!==o&&null!==a?l:l(1)}):e.each(function(){var e=this,i=arguments,o=e.__brush,a=t.input("function"==typeof n?n.apply(e,i):n,o.extent),u=c(e,i).beforestart();Tr(e),o.selection=null===a?null:a,s.call(e),u.start(r).brush(r).end(r)})},l.clear=function(t,e){l.move(t,null,e)},f.prototype={beforestart:function(){return 1===++this.active&&(this.state.emitter=this,this.starting=!0),this},start:function(t,e){return this.starting?(this.starting=!1,this.emit("start",t,e)):this.emit("brush",t),this},brush:function(t,e){return this.emit("brush",t,e),this},end:function(t,e){return 0===--this.active&&(delete this.state.emitter,this.emit("end",t,e)),this},emit:function(e,n,r){var i=Sr(this.that).datum();a.call(e,this.that,new Xr(e,{sourceEvent:n,target:l,selection:t.output(this.state.selection),mode:r,dispatch:a}),i)}},l.extent=function(t){return arguments.length?(n="function"==typeof t?t:Cr(Wr(t)),l):n},l.filter=function(t){return arguments.length?(r="function"==typeof t?t:Cr(!!t),l):r},l.touchable=function(t){return arguments.length?(i="function"==typeof t?t:Cr(!!t),l):i},l.handleSize=function(t){return arguments.length?(u=+t,l):u},l.keyModifiers=function(t){return arguments.length?(o=!!t,l):o},l.on=function(){var t=a.on.apply(a,arguments);return t===a?l:t},l}(Qr)},"name",{value:"default",configurable:!0});var Br={name:"drag"},Dr={name:"space"},Ir={name:"handle"},Fr={name:"center"};const{abs:Lr,max:Kr,min:Gr}=Math;function Ur(t){return[+t[0],+t[1]]}function Wr(t){return[Ur(t[0]),Ur(t[1])]}var Zr={name:"x",handles:["w","e"].map(oi),input:function(t,e){return null==t?null:[[+t[0],e[0][1]],[+t[1],e[1][1]]]},output:function(t){return t&&[t[0][0],t[1][0]]}},Jr={name:"y",handles:["n","s"].map(oi),input:function(t,e){return null==t?null:[[e[0][0],+t[0]],[e[1][0],+t[1]]]},output:function(t){return t&&[t[0][1],t[1][1]]}},Qr={name:"xy",handles:["n","w","e","s","nw","ne","sw","se"].map(oi),input:function(t){return null==t?null:Wr(t)},output:function(t){return t}},ti={overlay:"crosshair",selection:"move",n:"ns-resize",e:"ew-resize",s:"ns-resize",w:"ew-resize",nw:"nwse-resize",ne:"nesw-resize",se:"nwse-resize",sw:"nesw-resize"},ei={e:"w",w:"e",nw:"ne",ne:"nw",se:"sw",sw:"se"},ni={n:"s",s:"n",nw:"sw",ne:"se",se:"ne",sw:"nw"},ri={overlay:1,selection:1,n:null,e:1,s:null,w:-1,nw:-1,ne:1,se:1,sw:-1},ii={overlay:1,selection:1,n:-1,e:null,s:1,w:null,nw:-1,ne:-1,se:1,sw:1};function oi(t){return{type:t}}function ai(t){return!t.ctrlKey&&!t.button}function ui(){var t=this.ownerSVGElement||this;return t.hasAttribute("viewBox")?[[(t=t.viewBox.baseVal).x,t.y],[t.x+t.width,t.y+t.height]]:[[0,0],[t.width.baseVal.value,t.height.baseVal.value]]}function li(){return navigator.maxTouchPoints||"ontouchstart"in this}function si(t){for(;!t.__brush;)if(!(t=t.parentNode))return;return t.__brush}function ci(t){if(!t.ok)throw new Error(t.status+" "+t.statusText);if(204!==t.status&&205!==t.status)return t.json()}function fi(t,e){return fetch(t,e).then(ci)}Object.defineProperty(fi,"name",{value:"default",configurable:!0});const hi=Math.sqrt(50),pi=Math.sqrt(10),di=Math.sqrt(2);function yi(t,e,n){const r=(e-t)/Math.max(0,n),i=Math.floor(Math.log10(r)),o=r/Math.pow(10,i),a=o>=hi?10:o>=pi?5:o>=di?2:1;let u,l,s;return i<0?(s=Math.pow(10,-i)/a,u=Math.round(t*s),l=Math.round(e*s),u/s<t&&++u,l/s>e&&--l,s=-s):(s=Math.pow(10,i)*a,u=Math.round(t/s),l=Math.round(e/s),u*s<t&&++u,l*s>e&&--l),l<u&&.5<=n&&n<2?yi(t,e,2*n):[u,l,s]}function mi(t,e,n){return yi(t=+t,e=+e,n=+n)[2]}function vi(t,e){return null==t||null==e?NaN:t<e?-1:t>e?1:t>=e?0:NaN}function gi(t,e){return null==t||null==e?NaN:e<t?-1:e>t?1:e>=t?0:NaN}function bi(t){let e,n,r;function i(t,r,i=0,o=t.length){if(i<o){if(0!==e(r,r))return o;do{const e=i+o>>>1;n(t[e],r)<0?i=e+1:o=e}while(i<o)}return i}return 2!==t.length?(e=vi,n=(e,n)=>vi(t(e),n),r=(e,n)=>t(e)-n):(e=t===vi||t===gi?t:wi,n=t,r=t),{left:i,center:function(t,e,n=0,o=t.length){const a=i(t,e,n,o-1);return a>n&&r(t[a-1],e)>-r(t[a],e)?a-1:a},right:function(t,r,i=0,o=t.length){if(i<o){if(0!==e(r,r))return o;do{const e=i+o>>>1;n(t[e],r)<=0?i=e+1:o=e}while(i<o)}return i}}}function wi(){return 0}const _i=bi(vi),xi=_i.right,ki=(_i.left,bi(function(t){return null===t?NaN:+t}).center,xi);function Pi(t,e){var n,r=e?e.length:0,i=t?Math.min(r,t.length):0,o=new Array(i),a=new Array(r);for(n=0;n<i;++n)o[n]=Ai(t[n],e[n]);for(;n<r;++n)a[n]=e[n];return function(t){for(n=0;n<i;++n)a[n]=o[n](t);return a}}function Mi(t,e){var n=new Date;return t=+t,e=+e,function(r){return n.setTime(t*(1-r)+e*r),n}}function Oi(t,e){var n,r={},i={};for(n in null!==t&&"object"==typeof t||(t={}),null!==e&&"object"==typeof e||(e={}),e)n in t?r[n]=Ai(t[n],e[n]):i[n]=e[n];return function(t){for(n in r)i[n]=r[n](t);return i}}function ji(t,e){e||(e=[]);var n,r=t?Math.min(e.length,t.length):0,i=e.slice();return function(o){for(n=0;n<r;++n)i[n]=t[n]*(1-o)+e[n]*o;return i}}function Ai(t,e){var n,r,i=typeof e;return null==e||"boolean"===i?qn(e):("number"===i?Re:"string"===i?(n=gn(e))?(e=n,Yn):Dn:e instanceof gn?Yn:e instanceof Date?Mi:(r=e,!ArrayBuffer.isView(r)||r instanceof DataView?Array.isArray(e)?Pi:"function"!=typeof e.valueOf&&"function"!=typeof e.toString||isNaN(e)?Oi:Re:ji))(t,e)}function Ni(t,e){return t=+t,e=+e,function(n){return Math.round(t*(1-n)+e*n)}}function zi(t){return+t}Object.defineProperty(function(t,e){return((void 0)(e)?void 0:Pi)(t,e)},"name",{value:"default",configurable:!0}),Object.defineProperty(Mi,"name",{value:"default",configurable:!0}),Object.defineProperty(Oi,"name",{value:"default",configurable:!0}),Object.defineProperty(ji,"name",{value:"default",configurable:!0}),Object.defineProperty(Ai,"name",{value:"default",configurable:!0}),Object.defineProperty(Ni,"name",{value:"default",configurable:!0});var Ei=[0,1];function $i(t){return t}function Si(t,e){return(e-=t=+t)?function(n){return(n-t)/e}:(n=isNaN(e)?NaN:.5,function(){return n});var n}function Ti(t,e,n){var r=t[0],i=t[1],o=e[0],a=e[1];return i<r?(r=Si(i,r),o=n(a,o)):(r=Si(r,i),o=n(o,a)),function(t){return o(r(t))}}function Ci(t,e,n){var r=Math.min(t.length,e.length)-1,i=new Array(r),o=new Array(r),a=-1;for(t[r]<t[0]&&(t=t.slice().reverse(),e=e.slice().reverse());++a<r;)i[a]=Si(t[a],t[a+1]),o[a]=n(e[a],e[a+1]);return function(e){var n=ki(t,e,1,r)-1;return o[n](i[n](e))}}function Xi(t,e){switch(arguments.length){case 0:break;case 1:this.range(t);break;default:this.range(e).domain(t)}return this}var qi,Vi=/^(?:(.)?([<>=^]))?([+\-( ])?([$#])?(0)?(\d+)?(,)?(\.\d+)?(~)?([a-z%])?$/i;function Yi(t){if(!(e=Vi.exec(t)))throw new Error("invalid format: "+t);var e;return new Hi({fill:e[1],align:e[2],sign:e[3],symbol:e[4],zero:e[5],width:e[6],comma:e[7],precision:e[8]&&e[8].slice(1),trim:e[9],type:e[10]})}function Hi(t){this.fill=void 0===t.fill?" ":t.fill+"",this.align=void 0===t.align?">":t.align+"",this.sign=void 0===t.sign?"-":t.sign+"",this.symbol=void 0===t.symbol?"":t.symbol+"",this.zero=!!t.zero,this.width=void 0===t.width?void 0:+t.width,this.comma=!!t.comma,this.precision=void 0===t.precision?void 0:+t.precision,this.trim=!!t.trim,this.type=void 0===t.type?"":t.type+""}function Ri(t){return Math.abs(t=Math.round(t))>=1e21?t.toLocaleString("en").replace(/,/g,""):t.toString(10)}function Bi(t,e){if(!isFinite(t)||0===t)return null;var n=(t=e?t.toExponential(e-1):t.toExponential()).indexOf("e"),r=t.slice(0,n);return[r.length>1?r[0]+r.slice(2):r,+t.slice(n+1)]}function Di(t){return(t=Bi(Math.abs(t)))?t[1]:NaN}function Ii(t,e){return Math.max(0,3*Math.max(-8,Math.min(8,Math.floor(Di(e)/3)))-Di(Math.abs(t)))}function Fi(t,e){return function(n,r){for(var i=n.length,o=[],a=0,u=t[0],l=0;i>0&&u>0&&(l+u+1>r&&(u=Math.max(1,r-l)),o.push(n.substring(i-=u,i+u)),!((l+=u+1)>r));)u=t[a=(a+1)%t.length];return o.reverse().join(e)}}function Li(t){return function(e){return e.replace(/[0-9]/g,function(e){return t[+e]})}}function Ki(t){t:for(var e,n=t.length,r=1,i=-1;r<n;++r)switch(t[r]){case".":i=e=r;break;case"0":0===i&&(i=r),e=r;break;default:if(!+t[r])break t;i>0&&(i=0)}return i>0?t.slice(0,i)+t.slice(e+1):t}function Gi(t,e){var n=Bi(t,e);if(!n)return qi=void 0,t.toPrecision(e);var r=n[0],i=n[1],o=i-(qi=3*Math.max(-8,Math.min(8,Math.floor(i/3))))+1,a=r.length;return o===a?r:o>a?r+new Array(o-a+1).join("0"):o>0?r.slice(0,o)+"."+r.slice(o):"0."+new Array(1-o).join("0")+Bi(t,Math.max(0,e+o-1))[0]}function Ui(t,e){var n=Bi(t,e);if(!n)return t+"";var r=n[0],i=n[1];return i<0?"0."+new Array(-i).join("0")+r:r.length>i+1?r.slice(0,i+1)+"."+r.slice(i+1):r+new Array(i-r.length+2).join("0")}Yi.prototype=Hi.prototype,Hi.prototype.toString=function(){return this.fill+this.align+this.sign+this.symbol+(this.zero?"0":"")+(void 0===this.width?"":Math.max(1,0|this.width))+(this.comma?",":"")+(void 0===this.precision?"":"."+Math.max(0,0|this.precision))+(this.trim?"~":"")+this.type},Object.defineProperty(Ri,"name",{value:"default",configurable:!0}),Object.defineProperty(Di,"name",{value:"default",configurable:!0}),Object.defineProperty(Ii,"name",{value:"default",configurable:!0}),Object.defineProperty(Fi,"name",{value:"default",configurable:!0}),Object.defineProperty(Li,"name",{value:"default",configurable:!0}),Object.defineProperty(Ki,"name",{value:"default",configurable:!0}),Object.defineProperty(Gi,"name",{value:"default",configurable:!0}),Object.defineProperty(Ui,"name",{value:"default",configurable:!0});const Wi={"%":(t,e)=>(100*t).toFixed(e),b:t=>Math.round(t).toString(2),c:t=>t+"",d:Ri,e:(t,e)=>t.toExponential(e),f:(t,e)=>t.toFixed(e),g:(t,e)=>t.toPrecision(e),o:t=>Math.round(t).toString(8),p:(t,e)=>Ui(100*t,e),r:Ui,s:Gi,X:t=>Math.round(t).toString(16).toUpperCase(),x:t=>Math.round(t).toString(16)};function Zi(t){return t}Object.defineProperty(Zi,"name",{value:"default",configurable:!0}),Object.defineProperty(fo,"name",{value:"default",configurable:!0});var Ji,Qi,to,eo,no,ro,io,oo,ao,uo,lo,so=Array.prototype.map,co=["y","z","a","f","p","n","µ","m","","k","M","G","T","P","E","Z","Y"];function fo(t){var e=void 0===t.grouping||void 0===t.thousands?Zi:Fi(so.call(t.grouping,Number),t.thousands+""),n=void 0===t.currency?"":t.currency[0]+"",r=void 0===t.currency?"":t.currency[1]+"",i=void 0===t.decimal?".":t.decimal+"",o=void 0===t.numerals?Zi:Li(so.call(t.numerals,String)),a=void 0===t.percent?"%":t.percent+"",u=void 0===t.minus?"−":t.minus+"",l=void 0===t.nan?"NaN":t.nan+"";function s(t,s){var c=(t=Yi(t)).fill,f=t.align,h=t.sign,p=t.symbol,d=t.zero,y=t.width,m=t.comma,v=t.precision,g=t.trim,b=t.type;"n"===b?(m=!0,b="g"):Wi[b]||(void 0===v&&(v=12),g=!0,b="g"),(d||"0"===c&&"="===f)&&(d=!0,c="0",f="=");var w=(s&&void 0!==s.prefix?s.prefix:"")+("$"===p?n:"#"===p&&/[boxX]/.test(b)?"0"+b.toLowerCase():""),_=("$"===p?r:/[%p]/.test(b)?a:"")+(s&&void 0!==s.suffix?s.suffix:""),x=Wi[b],k=/[defgprs%]/.test(b);function P(t){var n,r,a,s=w,p=_;if("c"===b)p=x(t)+p,t="";else{var P=(t=+t)<0||1/t<0;if(t=isNaN(t)?l:x(Math.abs(t),v),g&&(t=Ki(t)),P&&0===+t&&"+"!==h&&(P=!1),s=(P?"("===h?h:u:"-"===h||"("===h?"":h)+s,p=("s"!==b||isNaN(t)||void 0===qi?"":co[8+qi/3])+p+(P&&"("===h?")":""),k)for(n=-1,r=t.length;++n<r;)if(48>(a=t.charCodeAt(n))||a>57){p=(46===a?i+t.slice(n+1):t.slice(n))+p,t=t.slice(0,n);break}}m&&!d&&(t=e(t,1/0));var M=s.length+t.length+p.length,O=M<y?new Array(y-M+1).join(c):"";switch(m&&d&&(t=e(O+t,O.length?y-p.length:1/0),O=""),f){case"<":t=s+t+p+O;break;case"=":t=s+O+t+p;break;case"^":t=O.slice(0,M=O.length>>1)+s+t+p+O.slice(M);break;default:t=O+s+t+p}return o(t)}return v=void 0===v?6:/[gprs]/.test(b)?Math.max(1,Math.min(21,v)):Math.max(0,Math.min(20,v)),P.toString=function(){return t+""},P}return{format:s,formatPrefix:function(t,e){var n=3*Math.max(-8,Math.min(8,Math.floor(Di(e)/3))),r=Math.pow(10,-n),i=s(((t=Yi(t)).type="f",t),{suffix:co[8+n/3]});return function(t){return i(r*t)}}}}function ho(t,e){return t=Math.abs(t),e=Math.abs(e)-t,Math.max(0,Di(e)-Di(t))+1}function po(t){return Math.max(0,-Di(Math.abs(t)))}function yo(t){var e=t.domain;return t.ticks=function(t){var n=e();return function(t,e,n){if(!((n=+n)>0))return[];if((t=+t)===(e=+e))return[t];const r=e<t,[i,o,a]=r?yi(e,t,n):yi(t,e,n);if(!(o>=i))return[];const u=o-i+1,l=new Array(u);if(r)if(a<0)for(let t=0;t<u;++t)l[t]=(o-t)/-a;else for(let t=0;t<u;++t)l[t]=(o-t)*a;else if(a<0)for(let t=0;t<u;++t)l[t]=(i+t)/-a;else for(let t=0;t<u;++t)l[t]=(i+t)*a;return l}(n[0],n[n.length-1],t??10)},t.tickFormat=function(t,n){var r=e();return function(t,e,n,r){var i,o=function(t,e,n){n=+n;const r=(e=+e)<(t=+t),i=r?mi(e,t,n):mi(t,e,n);return(r?-1:1)*(i<0?1/-i:i)}(t,e,n);switch((r=Yi(r??",f")).type){case"s":var a=Math.max(Math.abs(t),Math.abs(e));return null!=r.precision||isNaN(i=Ii(o,a))||(r.precision=i),to(r,a);case"":case"e":case"g":case"p":case"r":null!=r.precision||isNaN(i=ho(o,Math.max(Math.abs(t),Math.abs(e))))||(r.precision=i-("e"===r.type));break;case"f":case"%":null!=r.precision||isNaN(i=po(o))||(r.precision=i-2*("%"===r.type))}return Qi(r)}(r[0],r[r.length-1],t??10,n)},t.nice=function(n){null==n&&(n=10);var r,i,o=e(),a=0,u=o.length-1,l=o[a],s=o[u],c=10;for(s<l&&(i=l,l=s,s=i,i=a,a=u,u=i);c-- >0;){if((i=mi(l,s,n))===r)return o[a]=l,o[u]=s,e(o);if(i>0)l=Math.floor(l/i)*i,s=Math.ceil(s/i)*i;else{if(!(i<0))break;l=Math.ceil(l*i)/i,s=Math.floor(s*i)/i}r=i}return t},t}function mo(){var t=function(){var t,e,n,r,i,o,a=Ei,u=Ei,l=Ai,s=$i;function c(){var t,e,n,l=Math.min(a.length,u.length);return s!==$i&&(t=a[0],e=a[l-1],t>e&&(n=t,t=e,e=n),s=function(n){return Math.max(t,Math.min(e,n))}),r=l>2?Ci:Ti,i=o=null,f}function f(e){return null==e||isNaN(e=+e)?n:(i||(i=r(a.map(t),u,l)))(t(s(e)))}return f.invert=function(n){return s(e((o||(o=r(u,a.map(t),Re)))(n)))},f.domain=function(t){return arguments.length?(a=Array.from(t,zi),c()):a.slice()},f.range=function(t){return arguments.length?(u=Array.from(t),c()):u.slice()},f.rangeRound=function(t){return u=Array.from(t),l=Ni,c()},f.clamp=function(t){return arguments.length?(s=!!t||$i,c()):s!==$i},f.interpolate=function(t){return arguments.length?(l=t,c()):l},f.unknown=function(t){return arguments.length?(n=t,f):n},function(n,r){return t=n,e=r,c()}}()($i,$i);return t.copy=function(){return e=t,mo().domain(e.domain()).range(e.range()).interpolate(e.interpolate()).clamp(e.clamp()).unknown(e.unknown());var e},Xi.apply(t,arguments),yo(t)}function vo(t){return"string"==typeof t?new Qt([[document.querySelector(t)]],[document.documentElement]):new Qt([[t]],Jt)}function go(t,e,n){this.k=t,this.x=e,this.y=n}function bo(t){return!(t.ctrlKey&&"wheel"!==t.type||t.button)}function wo(){var t=this;return t instanceof SVGElement?(t=t.ownerSVGElement||t).hasAttribute("viewBox")?[[(t=t.viewBox.baseVal).x,t.y],[t.x+t.width,t.y+t.height]]:[[0,0],[t.width.baseVal.value,t.height.baseVal.value]]:[[0,0],[t.clientWidth,t.clientHeight]]}function _o(){return this.__zoom||oo}function xo(t){return-t.deltaY*(1===t.deltaMode?.05:t.deltaMode?1:.002)*(t.ctrlKey?10:1)}function ko(){return navigator.maxTouchPoints||"ontouchstart"in this}function Po(t,e,n){var r=t.invertX(e[0][0])-n[0][0],i=t.invertX(e[1][0])-n[1][0],o=t.invertY(e[0][1])-n[0][1],a=t.invertY(e[1][1])-n[1][1];return t.translate(i>r?(r+i)/2:Math.min(0,r)||Math.max(0,i),a>o?(o+a)/2:Math.min(0,o)||Math.max(0,a))}Ji=fo({thousands:",",grouping:[3],currency:["$",""]}),Qi=Ji.format,to=Ji.formatPrefix,Object.defineProperty(ho,"name",{value:"default",configurable:!0}),Object.defineProperty(po,"name",{value:"default",configurable:!0}),Object.defineProperty(vo,"name",{value:"default",configurable:!0}),go.prototype={constructor:go,scale:function(t){return 1===t?this:new go(this.k*t,this.x,this.y)},translate:function(t,e){return 0===t&0===e?this:new go(this.k,this.x+this.k*t,this.y+this.k*e)},apply:function(t){return[t[0]*this.k+this.x,t[1]*this.k+this.y]},applyX:function(t){return t*this.k+this.x},applyY:function(t){return t*this.k+this.y},invert:function(t){return[(t[0]-this.x)/this.k,(t[1]-this.y)/this.k]},invertX:function(t){return(t-this.x)/this.k},invertY:function(t){return(t-this.y)/this.k},rescaleX:function(t){return t.copy().domain(t.range().map(this.invertX,this).map(t.invert,t))},rescaleY:function(t){return t.copy().domain(t.range().map(this.invertY,this).map(t.invert,t))},toString:function(){return"translate("+this.x+","+this.y+") scale("+this.k+")"}},new go(1,0,0),go.prototype,Object.defineProperty(function(){var t,e,n,r=bo,i=wo,o=Po,a=xo,u=ko,l=[0,1/0],s=[[-1/0,-1/0],[1/0,1/0]],c=250,f=void 0,h=(void 0)("start","zoom","end"),p=0,d=10;function y(t){t.property("__zoom",_o).on("wheel.zoom",x,{passive:!1}).on("mousedown.zoom",k).on("dblclick.zoom",P).filter(u).on("touchstart.zoom",M).on("touchmove.zoom",O).on("touchend.zoom touchcancel.zoom",j).style("-webkit-tap-highlight-color","rgba(0,0,0,0)")}function m(t,e){return(e=Math.max(l[0],Math.min(l[1],e)))===t.k?t:new ao(e,t.x,t.y)}function v(t,e,n){var r=e[0]-n[0]*t.k,i=e[1]-n[1]*t.k;return r===t.x&&i===t.y?t:new ao(t.k,r,i)}function g(t){return[(+t[0][0]+ +t[1][0])/2,(+t[0][1]+ +t[1][1])/2]}function b(t,e,n,r){t.on("start.zoom",function(){w(this,arguments).event(r).start()}).on("interrupt.zoom end.zoom",function(){w(this,arguments).event(r).end()}).tween("zoom",function(){var t=this,o=arguments,a=w(t,o).event(r),u=i.apply(t,o),l=null==n?g(u):"function"==typeof n?n.apply(t,o):n,s=Math.max(u[1][0]-u[0][0],u[1][1]-u[0][1]),c=t.__zoom,h="function"==typeof e?e.apply(t,o):e,p=f(c.invert(l).concat(s/c.k),h.invert(l).concat(s/h.k));return function(t){if(1===t)t=h;else{var e=p(t),n=s/e[2];t=new ao(n,l[0]-e[0]*n,l[1]-e[1]*n)}a.zoom(null,t)}})}function w(t,e,n){return!n&&t.__zooming||new _(t,e)}function _(t,e){this.that=t,this.args=e,this.active=0,this.sourceEvent=null,this.extent=i.apply(t,e),this.taps=0}function x(t,...e){if(r.apply(this,arguments)){var n=w(this,e).event(t),i=this.__zoom,u=Math.max(l[0],Math.min(l[1],i.k*Math.pow(2,a.apply(this,arguments)))),c=eo(t);if(n.wheel)n.mouse[0][0]===c[0]&&n.mouse[0][1]===c[1]||(n.mouse[1]=i.invert(n.mouse[0]=c)),clearTimeout(n.wheel);else{if(i.k===u)return;n.mouse=[c,i.invert(c)],ro(this),n.start()}uo(t),n.wheel=setTimeout(function(){n.wheel=null,n.end()},150),n.zoom("mouse",o(v(m(i,u),n.mouse[0],n.mouse[1]),n.extent,s))}}function k(t,...e){if(!n&&r.apply(this,arguments)){var i=t.currentTarget,a=w(this,e,!0).event(t),u=no(t.view).on("mousemove.zoom",function(t){if(uo(t),!a.moved){var e=t.clientX-c,n=t.clientY-f;a.moved=e*e+n*n>p}a.event(t).zoom("mouse",o(v(a.that.__zoom,a.mouse[0]=eo(t,i),a.mouse[1]),a.extent,s))},!0).on("mouseup.zoom",function(t){u.on("mousemove.zoom mouseup.zoom",null),(void 0)(t.view,a.moved),uo(t),a.event(t).end()},!0),l=eo(t,i),c=t.clientX,f=t.clientY;(void 0)(t.view),lo(t),a.mouse=[l,this.__zoom.invert(l)],ro(this),a.start()}}function P(t,...e){if(r.apply(this,arguments)){var n=this.__zoom,a=eo(t.changedTouches?t.changedTouches[0]:t,this),u=n.invert(a),l=n.k*(t.shiftKey?.5:2),f=o(v(m(n,l),a,u),i.apply(this,e),s);uo(t),c>0?no(this).transition().duration(c).call(b,f,a,t):no(this).call(y.transform,f,a,t)}}function M(n,...i){if(r.apply(this,arguments)){var o,a,u,l,s=n.touches,c=s.length,f=w(this,i,n.changedTouches.length===c).event(n);for(lo(n),a=0;a<c;++a)u=s[a],l=[l=eo(u,this),this.__zoom.invert(l),u.identifier],f.touch0?f.touch1||f.touch0[2]===l[2]||(f.touch1=l,f.taps=0):(f.touch0=l,o=!0,f.taps=1+!!t);t&&(t=clearTimeout(t)),o&&(f.taps<2&&(e=l[0],t=setTimeout(function(){t=null},500)),ro(this),f.start())}}function O(t,...e){if(this.__zooming){var n,r,i,a,u=w(this,e).event(t),l=t.changedTouches,c=l.length;for(uo(t),n=0;n<c;++n)r=l[n],i=eo(r,this),u.touch0&&u.touch0[2]===r.identifier?u.touch0[0]=i:u.touch1&&u.touch1[2]===r.identifier&&(u.touch1[0]=i);if(r=u.that.__zoom,u.touch1){var f=u.touch0[0],h=u.touch0[1],p=u.touch1[0],d=u.touch1[1],y=(y=p[0]-f[0])*y+(y=p[1]-f[1])*y,g=(g=d[0]-h[0])*g+(g=d[1]-h[1])*g;r=m(r,Math.sqrt(y/g)),i=[(f[0]+p[0])/2,(f[1]+p[1])/2],a=[(h[0]+d[0])/2,(h[1]+d[1])/2]}else{if(!u.touch0)return;i=u.touch0[0],a=u.touch0[1]}u.zoom("touch",o(v(r,i,a),u.extent,s))}}function j(t,...r){if(this.__zooming){var i,o,a=w(this,r).event(t),u=t.changedTouches,l=u.length;for(lo(t),n&&clearTimeout(n),n=setTimeout(function(){n=null},500),i=0;i<l;++i)o=u[i],a.touch0&&a.touch0[2]===o.identifier?delete a.touch0:a.touch1&&a.touch1[2]===o.identifier&&delete a.touch1;if(a.touch1&&!a.touch0&&(a.touch0=a.touch1,delete a.touch1),a.touch0)a.touch0[1]=this.__zoom.invert(a.touch0[0]);else if(a.end(),2===a.taps&&(o=eo(o,this),Math.hypot(e[0]-o[0],e[1]-o[1])<d)){var s=no(this).on("dblclick.zoom");s&&s.apply(this,arguments)}}}return y.transform=function(t,e,n,r){var i=t.selection?t.selection():t;i.property("__zoom",_o),t!==i?b(t,e,n,r):i.interrupt().each(function(){w(this,arguments).event(r).start().zoom(null,"function"==typeof e?e.apply(this,arguments):e).end()})},y.scaleBy=function(t,e,n,r){y.scaleTo(t,function(){return this.__zoom.k*("function"==typeof e?e.apply(this,arguments):e)},n,r)},y.scaleTo=function(t,e,n,r){y.transform(t,function(){var t=i.apply(this,arguments),r=this.__zoom,a=null==n?g(t):"function"==typeof n?n.apply(this,arguments):n,u=r.invert(a),l="function"==typeof e?e.apply(this,arguments):e;return o(v(m(r,l),a,u),t,s)},n,r)},y.translateBy=function(t,e,n,r){y.transform(t,function(){return o(this.__zoom.translate("function"==typeof e?e.apply(this,arguments):e,"function"==typeof n?n.apply(this,arguments):n),i.apply(this,arguments),s)},null,r)},y.translateTo=function(t,e,n,r,a){y.transform(t,function(){var t=i.apply(this,arguments),a=this.__zoom,u=null==r?g(t):"function"==typeof r?r.apply(this,arguments):r;return o(oo.translate(u[0],u[1]).scale(a.k).translate("function"==typeof e?-e.apply(this,arguments):-e,"function"==typeof n?-n.apply(this,arguments):-n),t,s)},r,a)},_.prototype={event:function(t){return t&&(this.sourceEvent=t),this},start:function(){return 1===++this.active&&(this.that.__zooming=this,this.emit("start")),this},zoom:function(t,e){return this.mouse&&"mouse"!==t&&(this.mouse[1]=e.invert(this.mouse[0])),this.touch0&&"touch"!==t&&(this.touch0[1]=e.invert(this.touch0[0])),this.touch1&&"touch"!==t&&(this.touch1[1]=e.invert(this.touch1[0])),this.that.__zoom=e,this.emit("zoom"),this},end:function(){return 0===--this.active&&(delete this.that.__zooming,this.emit("end")),this},emit:function(t){var e=no(this.that).datum();h.call(t,this.that,new(void 0)(t,{sourceEvent:this.sourceEvent,target:y,type:t,transform:this.that.__zoom,dispatch:h}),e)}},y.wheelDelta=function(t){return arguments.length?(a="function"==typeof t?t:io(+t),y):a},y.filter=function(t){return arguments.length?(r="function"==typeof t?t:io(!!t),y):r},y.touchable=function(t){return arguments.length?(u="function"==typeof t?t:io(!!t),y):u},y.extent=function(t){return arguments.length?(i="function"==typeof t?t:io([[+t[0][0],+t[0][1]],[+t[1][0],+t[1][1]]]),y):i},y.scaleExtent=function(t){return arguments.length?(l[0]=+t[0],l[1]=+t[1],y):[l[0],l[1]]},y.translateExtent=function(t){return arguments.length?(s[0][0]=+t[0][0],s[1][0]=+t[1][0],s[0][1]=+t[0][1],s[1][1]=+t[1][1],y):[[s[0][0],s[0][1]],[s[1][0],s[1][1]]]},y.constrain=function(t){return arguments.length?(o=t,y):o},y.duration=function(t){return arguments.length?(c=+t,y):c},y.interpolate=function(t){return arguments.length?(f=t,y):f},y.on=function(){var t=h.on.apply(h,arguments);return t===h?y:t},y.clickDistance=function(t){return arguments.length?(p=(t=+t)*t,y):Math.sqrt(p)},y.tapDistance=function(t){return arguments.length?(d=+t,y):d},y},"name",{value:"default",configurable:!0});{const d3={select:vo,json:fi,scaleLinear:mo,extent:t,axisBottom:e=>l(3,e),axisLeft:e=>l(4,e)};
+const renderLibraryMap=(()=>{
+
+const margin = { top: 30, right: 30, bottom: 30, left: 30 },
+    width = 1280 - margin.left - margin.right,
+    height = 720 - margin.top - margin.bottom;
+
+const colors = {
+    song: "rgba(105, 179, 162, 0.35)",
+    history: "#f4a261",
+    upcoming: "#457b9d",
+    pin: "#e63946",
+};
+
+// the whole library is drawn on a canvas (there can be tens of thousands of songs), with the
+// highlighted songs on an svg on top so they stay crisp and easy to restyle
+function renderLibraryMap(container, apiBase, tooltip, onSelect) {
+    const root = d3.select(container)
+        .style("position", "relative")
+        .style("width", (width + margin.left + margin.right) + "px")
+        .style("height", (height + margin.top + margin.bottom) + "px");
+
+    const canvas = root.append("canvas")
+        .attr("width", width + margin.left + margin.right)
+        .attr("height", height + margin.top + margin.bottom)
+        .style("position", "absolute")
+        .style("cursor", "crosshair");
+    const context = canvas.node().getContext("2d");
+
+    const overlay = root.append("svg")
+        .attr("width", width + margin.left + margin.right)
+        .attr("height", height + margin.top + margin.bottom)
+        .style("position", "absolute")
+        .style("pointer-events", "none")
+        .append("g")
+        .attr("transform", "translate(" + margin.left + "," + margin.top + ")");
+
+    d3.json(apiBase + "/embedding", { credentials: "same-origin" })
+        .then(function (embedding) {
+            const songs = embedding.songs;
+            const x = d3.scaleLinear()
+                .domain(d3.extent(songs, d => d.x)).nice()
+                .range([0, width]);
+            const y = d3.scaleLinear()
+                .domain(d3.extent(songs, d => d.y)).nice()
+                .range([height, 0]);
+
+            context.translate(margin.left, margin.top);
+            context.fillStyle = colors.song;
+            for (const song of songs) {
+                context.fillRect(x(song.x) - 1, y(song.y) - 1, 2, 2);
+            }
+
+            const variance = embedding.explained_variance.map(v => (v * 100).toFixed(1) + "%");
+            overlay.append("text")
+                .attr("x", width / 2)
+                .attr("y", -margin.top / 2)
+                .attr("text-anchor", "middle")
+                .style("font-size", "18px")
+                .style("font-weight", "bold")
+                .text(`Library map (PCA, ${variance[0]} + ${variance[1]} of variance)`);
+
+            const byPath = new Map(songs.map(song => [song.path, song]));
+            // songs are bucketed into a grid of screen cells, so finding the one under the mouse
+            // only looks at the cells around it instead of the whole library
+            const cellSize = 20;
+            const columns = Math.ceil(width / cellSize) + 1;
+            const rows = Math.ceil(height / cellSize) + 1;
+            const grid = new Map();
+            const points = songs.map(function (song, i) {
+                const point = [x(song.x), y(song.y)];
+                const key = Math.floor(point[1] / cellSize) * columns + Math.floor(point[0] / cellSize);
+                if (!grid.has(key)) {
+                    grid.set(key, []);
+                }
+                grid.get(key).push(i);
+                return point;
+            });
+            const nearest = function (event) {
+                const rect = canvas.node().getBoundingClientRect();
+                const mx = event.clientX - rect.left - margin.left;
+                const my = event.clientY - rect.top - margin.top;
+                const cx = Math.floor(mx / cellSize), cy = Math.floor(my / cellSize);
+                let best, bestDistance = Infinity;
+                // search rings of cells outwards, until no song in the next ring could be closer
+                for (let ring = 0; ring <= columns + rows; ring++) {
+                    if (best !== undefined && ((ring - 1) * cellSize) ** 2 > bestDistance) {
+                        break;
+                    }
+                    for (let gy = cy - ring; gy <= cy + ring; gy++) {
+                        for (let gx = cx - ring; gx <= cx + ring; gx++) {
+                            const onRing = Math.max(Math.abs(gx - cx), Math.abs(gy - cy)) === ring;
+                            if (!onRing || gx < 0 || gy < 0 || gx >= columns || gy >= rows) {
+                                continue;
+                            }
+                            for (const i of grid.get(gy * columns + gx) || []) {
+                                const [px, py] = points[i];
+                                const distance = (px - mx) ** 2 + (py - my) ** 2;
+                                if (distance < bestDistance) {
+                                    best = songs[i];
+                                    bestDistance = distance;
+                                }
+                            }
+                        }
+                    }
+                }
+                return best;
+            };
+
+            canvas
+                .on("mousemove", function (event) {
+                    const song = nearest(event);
+                    if (song === undefined) {
+                        return;
+                    }
+                    tooltip
+                        .style("opacity", 1)
+                        .text(song.path)
+                        .style("left", (event.pageX + 10) + "px")
+                        .style("top", (event.pageY - 28) + "px");
+                })
+                .on("mouseout", () => tooltip.style("opacity", 0))
+                .on("click", function (event) {
+                    const song = nearest(event);
+                    if (song !== undefined) {
+                        onSelect(song.path);
+                    }
+                });
+
+            const highlight = function (paths, color, radius) {
+                overlay.selectAll(null)
+                    .data(paths.map(path => byPath.get(path)).filter(song => song !== undefined))
+                    .enter()
+                    .append("circle")
+                    .attr("class", "highlight")
+                    .attr("cx", d => x(d.x))
+                    .attr("cy", d => y(d.y))
+                    .attr("r", radius)
+                    .style("fill", color)
+                    .style("stroke", "white");
+            };
+
+            // only available when worf is queueing in the same process (`--serve`)
+            const refreshHighlights = function () {
+                const fetchDaemon = endpoint => d3.json(apiBase + "/daemon/" + endpoint, { credentials: "same-origin" })
+                    .catch(() => null);
+                Promise.all([fetchDaemon("pin"), fetchDaemon("history"), fetchDaemon("upcoming")])
+                    .then(function ([pin, history, upcoming]) {
+                        overlay.selectAll(".highlight").remove();
+                        highlight((upcoming || []).map(song => song.path), colors.upcoming, 3);
+                        highlight((history || []).map(song => song.path), colors.history, 4);
+                        highlight(pin ? [pin.path] : [], colors.pin, 7);
+                    });
+            };
+            refreshHighlights();
+
+            const updateStatus = overlay.append("text")
+                .attr("x", width)
+                .attr("y", height + margin.bottom * 0.8)
+                .attr("text-anchor", "end")
+                .style("font-size", "12px");
+
+            let events, stopped = false, retryDelay = 1000;
+            const onEvent = function (message) {
+                const event = JSON.parse(message.data);
+                if (event.type === "pin_changed" || event.type === "song_queued") {
+                    refreshHighlights();
+                } else if (event.type === "update_started") {
+                    updateStatus.text("Updating library...");
+                } else if (event.type === "update_progress") {
+                    updateStatus.text(`Updating library: ${event.analyzed} of ${event.total} new songs analyzed`);
+                } else if (event.type === "update_failed") {
+                    updateStatus.text("Library update failed: " + event.error);
+                } else if (event.type === "library_refreshed") {
+                    // the projection was recomputed after an update
+                    stopped = true;
+                    events.close();
+                    root.selectAll("*").remove();
+                    renderLibraryMap(container, apiBase, tooltip, onSelect);
+                }
+            };
+            // browsers reconnect on their own after a dropped connection, but give up when the
+            // stream is refused (e.g. while the server restarts, or for a standalone server, which
+            // has none), so keep trying with backoff
+            const connect = function () {
+                if (stopped) {
+                    return;
+                }
+                events = new EventSource(apiBase + "/events", { withCredentials: true });
+                events.onopen = () => retryDelay = 1000;
+                events.onmessage = onEvent;
+                events.onerror = function () {
+                    if (events.readyState === EventSource.CLOSED) {
+                        setTimeout(connect, retryDelay);
+                        retryDelay = Math.min(retryDelay * 2, 60000);
+                    }
+                };
+            };
+            connect();
+
+            console.log("Library map rendered with", songs.length, "songs");
+        })
+        .catch(function (error) {
+            console.error("Error loading library map:", error);
+        });
+}
+
+return renderLibraryMap;})();
+{
+
+const margin = { top: 10, right: 30, bottom: 30, left: 60 },
+    width = 1280 - margin.left - margin.right,
+    height = 720 - margin.top - margin.bottom;
+
+// will get reused
+const tooltip = d3.select("body")
+    .append("div")
+    .style("position", "absolute")
+    .style("background", "rgba(0, 0, 0, 0.8)")
+    .style("color", "white")
+    .style("padding", "8px 12px")
+    .style("border-radius", "4px")
+    .style("font-size", "12px")
+    .style("pointer-events", "none")
+    .style("opacity", 0)
+    .style("z-index", 1000);
+
+const svg = d3.select("#container")
+    .append("svg")
+    .attr("width", width + margin.left + margin.right)
+    .attr("height", height + margin.top + margin.bottom)
+    .append("g")
+    .attr("transform", "translate(" + margin.left + "," + margin.top + ")");
+
+// served by worf itself, so the API lives on the same origin
+const apiBase = "/api";
+
+const searchInput = d3.select("#song-search");
+const searchResults = d3.select("#song-results");
+const modeSelect = d3.select("#playlist-mode");
+let searchTimeout;
+
+// debounced so every keystroke doesn't hit the server
+searchInput.on("input", function () {
+    clearTimeout(searchTimeout);
+    const query = this.value.trim();
+    if (!query) {
+        searchResults.selectAll("*").remove();
+        return;
+    }
+    searchTimeout = setTimeout(() => searchSongs(query), 250);
+});
+
+function searchSongs(query) {
+    const params = new URLSearchParams({ q: query, sort: "artist", page_size: 20 });
+    d3.json(apiBase + "/all/search?" + params, { credentials: "same-origin" })
+        .then(function (data) {
+            searchResults.selectAll("*").remove();
+            searchResults.selectAll("li")
+                .data(data.songs)
+                .enter()
+                .append("li")
+                .style("cursor", "pointer")
+                .text(d => d.info.title ? `${d.info.artist || "Unknown artist"} - ${d.info.title}` : d.path)
+                .on("click", (_, d) => {
+                    searchResults.selectAll("*").remove();
+                    searchInput.property("value", "");
+                    location.hash = encodeURIComponent(d.path);
+                });
+        })
+        .catch(function (error) {
+            console.error("Error searching songs:", error);
+        });
+}
+
+// the selected song lives in the URL hash, so it survives reloads and can be linked to
+function loadSelected() {
+    if (location.hash.length > 1) {
+        loadPlaylist(decodeURIComponent(location.hash.slice(1)));
+    }
+}
+window.addEventListener("hashchange", loadSelected);
+modeSelect.on("change", loadSelected);
+loadSelected();
+
+renderLibraryMap("#library-map", apiBase, tooltip, path => location.hash = encodeURIComponent(path));
+
+function loadPlaylist(songPath) {
+    svg.selectAll("*").remove();
+
+    const params = new URLSearchParams({ length: 150, mode: modeSelect.property("value") });
+    d3.json(apiBase + "/playlist/" + encodeURIComponent(songPath) + "?" + params, { credentials: "same-origin" })
+        .then(function (data) {
+
+            const blissValues = data.tail.map(d => d.bliss_distance);
+            const genreValues = data.tail.map(d => d.genre_similarity ?? 0);
+
+            // bliss on the x
+            const x = d3.scaleLinear()
+                .domain(d3.extent(blissValues)).nice()
+                .range([0, width]);
+
+            // genre on the y
+            const y = d3.scaleLinear()
+                .domain(d3.extent(genreValues)).nice()
+                .range([height, 0]);
+
+            svg.append("g")
+                .attr("transform", "translate(0," + height + ")")
+                .call(d3.axisBottom(x).ticks(5));
+
+            svg.append("g")
+                .call(d3.axisLeft(y).ticks(5));
+
+            const selectedSongTitle = data.head.title || data.head.path.split("/").pop() || songPath;
+            svg.append("text")
+                .attr("x", width / 2)
+                .attr("y", -margin.top / 2)
+                .attr("text-anchor", "middle")
+                .style("font-size", "18px")
+                .style("font-weight", "bold")
+                .text(`Selected song: ${selectedSongTitle}`);
+
+            // axis labels
+            svg.append("text")
+                .attr("transform", "translate(" + (width / 2) + ")")
+                .attr("x", -10)
+                .attr("y", height + 30)
+                .style("text-anchor", "middle")
+                .text("Bliss Distance");
+
+            svg.append("text")
+                .attr("transform", "rotate(-90)")
+                .attr("y", 0 - margin.left)
+                .attr("x", 0 - (height / 2))
+                .attr("dy", "1em")
+                .style("text-anchor", "middle")
+                .text("Genre Similarity");
+
+            const points = data.tail.map((d, i) => ({ bliss: d.bliss_distance, genre: d.genre_similarity ?? 0, i }));
+            const audio = new Audio();
+            const imageSize = 40;
+
+            const createHoverHandlers = (dataPoint) => ({
+                mouseover: function (event) {
+                    const item = data.tail[dataPoint.i];
+                    d3.select(this).style("opacity", 1);
+                    tooltip
+                        .style("opacity", 1)
+                        .html(`
+                            <strong><div style="text-decoration: underline;">${item.title}</div></strong>
+                            <br/>
+                            <strong>Rank:</strong> ${item.rank}
+                            <br/>
+                            <strong>Bliss distance:</strong> ${item.bliss_distance.toFixed(3)}
+                            <br/>
+                            <strong>Genre similarity:</strong> ${item.genre_similarity !== null ? item.genre_similarity.toFixed(1) : 'N/A'}
+                            <br/>
+                            <strong>Genres:</strong> ${item.genre}
+                            <br/>
+                            <strong>Popularity:</strong> ${item.popularity !== undefined ? item.popularity : 'N/A'}
+                        `)
+                        .style("left", (event.pageX + 10) + "px")
+                        .style("top", (event.pageY - 28) + "px");
+                    audio.src = item.href;
+                    audio.currentTime = 30;
+                    audio.play().catch(err => console.log("Audio playback failed:", err));
+                },
+                mouseout: function () {
+                    d3.select(this).style("opacity", 0.8);
+                    tooltip.style("opacity", 0);
+                    audio.pause();
+                }
+            });
+
+            const imageSelection = svg.append('g')
+                .selectAll("image")
+                .data(points)
+                .enter()
+                .append("image")
+                .attr("x", d => x(d.bliss) - imageSize / 2)
+                .attr("y", d => y(d.genre) - imageSize / 2)
+                .attr("width", imageSize)
+                .attr("height", imageSize)
+                .attr("href", d => {
+                    const songPath = data.tail[d.i].path;
+                    // twice the drawn size, for high DPI screens
+                    return apiBase + "/albumart/" + encodeURIComponent(songPath) + "?size=" + imageSize * 2;
+                })
+                .style("cursor", "pointer")
+                .style("opacity", 0.8)
+                .on("error", function () {
+                    const d = d3.select(this).datum();
+                    const cx = x(d.bliss);
+                    const cy = y(d.genre);
+
+                    // image failed to load => use a circle instead
+                    d3.select(this).remove();
+
+                    const handlers = createHoverHandlers(d);
+                    svg.append("circle")
+                        .attr("cx", cx)
+                        .attr("cy", cy)
+                        .attr("r", imageSize / 2)
+                        .style("fill", "#69b3a2")
+                        .style("opacity", 0.8)
+                        .style("cursor", "pointer")
+                        .on("mouseover", handlers.mouseover)
+                        .on("mouseout", handlers.mouseout);
+                })
+                .on("mouseover", function (event, d) {
+                    const handlers = createHoverHandlers(d);
+                    handlers.mouseover.call(this, event);
+                })
+                .on("mouseout", function (event, d) {
+                    const handlers = createHoverHandlers(d);
+                    handlers.mouseout.call(this, event);
+                });
+
+            console.log("Graph rendered with", points.length, "points");
+        })
+        .catch(function (error) {
+            console.error("Error loading data:", error);
+        });
+}
+
+}}})();
@@ -1,4 +1,5 @@
 import * as d3 from "d3";
+import { renderLibraryMap } from "./libraryMap.js";
 
 const margin = { top: 10, right: 30, bottom: 30, left: 60 },
     width = 1280 - margin.left - margin.right,
@@ -24,140 +25,194 @@ const svg = d3.select("#container")
     .append("g")
     .attr("transform", "translate(" + margin.left + "," + margin.top + ")");
 
-function collapseArray(arr) {
-    let total = arr.reduce((sum, val) => sum + val, 0);
-    return total / arr.length;
-}
-
-let songPath = "Shygirl/Nymph_o/07 - Shygirl - Playboy _ Positions.ogg";
-
-d3.json("http://localhost:8080/api/playlist/" + encodeURIComponent(songPath) + "?length=150")
-    .then(function (data) {
-
-        const blissValues = data.tail.flatMap(d => collapseArray(d.analysis.bliss));
-        const genreValues = data.tail.flatMap(d => collapseArray(d.analysis.genre));
-
-        // bliss on the x
-        const x = d3.scaleLinear()
-            .domain(d3.extent(blissValues)).nice()
-            .range([0, width]);
-
-        // genre on the y
-        const y = d3.scaleLinear()
-            .domain(d3.extent(genreValues)).nice()
-            .range([height, 0]);
-
-        svg.append("g")
-            .attr("transform", "translate(0," + height + ")")
-            .call(d3.axisBottom(x).ticks(5));
-
-        svg.append("g")
-            .call(d3.axisLeft(y).ticks(5));
-
-        const selectedSongTitle = data.head.title || data.head.path.split("/").pop() || songPath;
-        svg.append("text")
-            .attr("x", width / 2)
-            .attr("y", -margin.top / 2)
-            .attr("text-anchor", "middle")
-            .style("font-size", "18px")
-            .style("font-weight", "bold")
-            .text(`Selected song: ${selectedSongTitle}`);
-
-        // axis labels
-        svg.append("text")
-            .attr("transform", "translate(" + (width / 2) + ")")
-            .attr("x", -10)
-            .attr("y", height + 30)
-            .style("text-anchor", "middle")
-            .text("Bliss Similarity");
-
-        svg.append("text")
-            .attr("transform", "rotate(-90)")
-            .attr("y", 0 - margin.left)
-            .attr("x", 0 - (height / 2))
-            .attr("dy", "1em")
-            .style("text-anchor", "middle")
-            .text("Genre Similarity");
-
-        const points = data.tail.map(d => ({ bliss: d.analysis.bliss, genre: d.analysis.genre, i: data.tail.indexOf(d) }));
-        const audio = new Audio();
-        const imageSize = 40;
-
-        const createHoverHandlers = (dataPoint) => ({
-            mouseover: function (event) {
-                const item = data.tail[dataPoint.i];
-                d3.select(this).style("opacity", 1);
-                tooltip
-                    .style("opacity", 1)
-                    .html(`
-                        <strong><div style="text-decoration: underline;">${item.title}</div></strong>
-                        <br/>
-                        <strong>Bliss distance:</strong> ${collapseArray(dataPoint.bliss).toFixed(3)}
-                        <br/>
-                        <strong>Genre distance:</strong> ${collapseArray(dataPoint.genre).toFixed(3)}
-                        <br/>
-                        <strong>Genres:</strong> ${item.genre}
-                        <br/>
-                        <strong>Popularity:</strong> ${item.popularity !== undefined ? item.popularity : 'N/A'}
-                    `)
-                    .style("left", (event.pageX + 10) + "px")
-                    .style("top", (event.pageY - 28) + "px");
-                audio.src = item.href;
-                audio.currentTime = 30;
-                audio.play().catch(err => console.log("Audio playback failed:", err));
-            },
-            mouseout: function () {
-                d3.select(this).style("opacity", 0.8);
-                tooltip.style("opacity", 0);
-                audio.pause();
-            }
+// served by worf itself, so the API lives on the same origin
+const apiBase = "/api";
+
+const searchInput = d3.select("#song-search");
+const searchResults = d3.select("#song-results");
+const modeSelect = d3.select("#playlist-mode");
+let searchTimeout;
+
+// debounced so every keystroke doesn't hit the server
+searchInput.on("input", function () {
+    clearTimeout(searchTimeout);
+    const query = this.value.trim();
+    if (!query) {
+        searchResults.selectAll("*").remove();
+        return;
+    }
+    searchTimeout = setTimeout(() => searchSongs(query), 250);
+});
+
+function searchSongs(query) {
+    const params = new URLSearchParams({ q: query, sort: "artist", page_size: 20 });
+    d3.json(apiBase + "/all/search?" + params, { credentials: "same-origin" })
+        .then(function (data) {
+            searchResults.selectAll("*").remove();
+            searchResults.selectAll("li")
+                .data(data.songs)
+                .enter()
+                .append("li")
+                .style("cursor", "pointer")
+                .text(d => d.info.title ? `${d.info.artist || "Unknown artist"} - ${d.info.title}` : d.path)
+                .on("click", (_, d) => {
+                    searchResults.selectAll("*").remove();
+                    searchInput.property("value", "");
+                    location.hash = encodeURIComponent(d.path);
+                });
+        })
+        .catch(function (error) {
+            console.error("Error searching songs:", error);
         });
+}
 
-        const imageSelection = svg.append('g')
-            .selectAll("image")
-            .data(points)
-            .enter()
-            .append("image")
-            .attr("x", d => x(collapseArray(d.bliss)) - imageSize / 2)
-            .attr("y", d => y(collapseArray(d.genre)) - imageSize / 2)
-            .attr("width", imageSize)
-            .attr("height", imageSize)
-            .attr("href", d => {
-                const songPath = data.tail[d.i].path;
-                return "http://localhost:8080/api/albumart/" + encodeURIComponent(songPath);
-            })
-            .style("cursor", "pointer")
-            .style("opacity", 0.8)
-            .on("error", function () {
-                const d = d3.select(this).datum();
-                const cx = x(collapseArray(d.bliss));
-                const cy = y(collapseArray(d.genre));
-
-                // image failed to load => use a circle instead
-                d3.select(this).remove();
-
-                const handlers = createHoverHandlers(d);
-                svg.append("circle")
-                    .attr("cx", cx)
-                    .attr("cy", cy)
-                    .attr("r", imageSize / 2)
-                    .style("fill", "#69b3a2")
-                    .style("opacity", 0.8)
-                    .style("cursor", "pointer")
-                    .on("mouseover", handlers.mouseover)
-                    .on("mouseout", handlers.mouseout);
-            })
-            .on("mouseover", function (event, d) {
-                const handlers = createHoverHandlers(d);
-                handlers.mouseover.call(this, event);
-            })
-            .on("mouseout", function (event, d) {
-                const handlers = createHoverHandlers(d);
-                handlers.mouseout.call(this, event);
+// the selected song lives in the URL hash, so it survives reloads and can be linked to
+function loadSelected() {
+    if (location.hash.length > 1) {
+        loadPlaylist(decodeURIComponent(location.hash.slice(1)));
+    }
+}
+window.addEventListener("hashchange", loadSelected);
+modeSelect.on("change", loadSelected);
+loadSelected();
+
+renderLibraryMap("#library-map", apiBase, tooltip, path => location.hash = encodeURIComponent(path));
+
+function loadPlaylist(songPath) {
+    svg.selectAll("*").remove();
+
+    const params = new URLSearchParams({ length: 150, mode: modeSelect.property("value") });
+    d3.json(apiBase + "/playlist/" + encodeURIComponent(songPath) + "?" + params, { credentials: "same-origin" })
+        .then(function (data) {
+
+            const blissValues = data.tail.map(d => d.bliss_distance);
+            const genreValues = data.tail.map(d => d.genre_similarity ?? 0);
+
+            // bliss on the x
+            const x = d3.scaleLinear()
+                .domain(d3.extent(blissValues)).nice()
+                .range([0, width]);
+
+            // genre on the y
+            const y = d3.scaleLinear()
+                .domain(d3.extent(genreValues)).nice()
+                .range([height, 0]);
+
+            svg.append("g")
+                .attr("transform", "translate(0," + height + ")")
+                .call(d3.axisBottom(x).ticks(5));
+
+            svg.append("g")
+                .call(d3.axisLeft(y).ticks(5));
+
+            const selectedSongTitle = data.head.title || data.head.path.split("/").pop() || songPath;
+            svg.append("text")
+                .attr("x", width / 2)
+                .attr("y", -margin.top / 2)
+                .attr("text-anchor", "middle")
+                .style("font-size", "18px")
+                .style("font-weight", "bold")
+                .text(`Selected song: ${selectedSongTitle}`);
+
+            // axis labels
+            svg.append("text")
+                .attr("transform", "translate(" + (width / 2) + ")")
+                .attr("x", -10)
+                .attr("y", height + 30)
+                .style("text-anchor", "middle")
+                .text("Bliss Distance");
+
+            svg.append("text")
+                .attr("transform", "rotate(-90)")
+                .attr("y", 0 - margin.left)
+                .attr("x", 0 - (height / 2))
+                .attr("dy", "1em")
+                .style("text-anchor", "middle")
+                .text("Genre Similarity");
+
+            const points = data.tail.map((d, i) => ({ bliss: d.bliss_distance, genre: d.genre_similarity ?? 0, i }));
+            const audio = new Audio();
+            const imageSize = 40;
+
+            const createHoverHandlers = (dataPoint) => ({
+                mouseover: function (event) {
+                    const item = data.tail[dataPoint.i];
+                    d3.select(this).style("opacity", 1);
+                    tooltip
+                        .style("opacity", 1)
+                        .html(`
+                            <strong><div style="text-decoration: underline;">${item.title}</div></strong>
+                            <br/>
+                            <strong>Rank:</strong> ${item.rank}
+                            <br/>
+                            <strong>Bliss distance:</strong> ${item.bliss_distance.toFixed(3)}
+                            <br/>
+                            <strong>Genre similarity:</strong> ${item.genre_similarity !== null ? item.genre_similarity.toFixed(1) : 'N/A'}
+                            <br/>
+                            <strong>Genres:</strong> ${item.genre}
+                            <br/>
+                            <strong>Popularity:</strong> ${item.popularity !== undefined ? item.popularity : 'N/A'}
+                        `)
+                        .style("left", (event.pageX + 10) + "px")
+                        .style("top", (event.pageY - 28) + "px");
+                    audio.src = item.href;
+                    audio.currentTime = 30;
+                    audio.play().catch(err => console.log("Audio playback failed:", err));
+                },
+                mouseout: function () {
+                    d3.select(this).style("opacity", 0.8);
+                    tooltip.style("opacity", 0);
+                    audio.pause();
+                }
             });
 
-        console.log("Graph rendered with", points.length, "points");
-    })
-    .catch(function (error) {
-        console.error("Error loading data:", error);
-    });
+            const imageSelection = svg.append('g')
+                .selectAll("image")
+                .data(points)
+                .enter()
+                .append("image")
+                .attr("x", d => x(d.bliss) - imageSize / 2)
+                .attr("y", d => y(d.genre) - imageSize / 2)
+                .attr("width", imageSize)
+                .attr("height", imageSize)
+                .attr("href", d => {
+                    const songPath = data.tail[d.i].path;
+                    // twice the drawn size, for high DPI screens
+                    return apiBase + "/albumart/" + encodeURIComponent(songPath) + "?size=" + imageSize * 2;
+                })
+                .style("cursor", "pointer")
+                .style("opacity", 0.8)
+                .on("error", function () {
+                    const d = d3.select(this).datum();
+                    const cx = x(d.bliss);
+                    const cy = y(d.genre);
+
+                    // image failed to load => use a circle instead
+                    d3.select(this).remove();
+
+                    const handlers = createHoverHandlers(d);
+                    svg.append("circle")
+                        .attr("cx", cx)
+                        .attr("cy", cy)
+                        .attr("r", imageSize / 2)
+                        .style("fill", "#69b3a2")
+                        .style("opacity", 0.8)
+                        .style("cursor", "pointer")
+                        .on("mouseover", handlers.mouseover)
+                        .on("mouseout", handlers.mouseout);
+                })
+                .on("mouseover", function (event, d) {
+                    const handlers = createHoverHandlers(d);
+                    handlers.mouseover.call(this, event);
+                })
+                .on("mouseout", function (event, d) {
+                    const handlers = createHoverHandlers(d);
+                    handlers.mouseout.call(this, event);
+                });
+
+            console.log("Graph rendered with", points.length, "points");
+        })
+        .catch(function (error) {
+            console.error("Error loading data:", error);
+        });
+}
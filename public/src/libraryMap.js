@@ -0,0 +1,205 @@
+import * as d3 from "d3";
+
+const margin = { top: 30, right: 30, bottom: 30, left: 30 },
+    width = 1280 - margin.left - margin.right,
+    height = 720 - margin.top - margin.bottom;
+
+const colors = {
+    song: "rgba(105, 179, 162, 0.35)",
+    history: "#f4a261",
+    upcoming: "#457b9d",
+    pin: "#e63946",
+};
+
+// the whole library is drawn on a canvas (there can be tens of thousands of songs), with the
+// highlighted songs on an svg on top so they stay crisp and easy to restyle
+export function renderLibraryMap(container, apiBase, tooltip, onSelect) {
+    const root = d3.select(container)
+        .style("position", "relative")
+        .style("width", (width + margin.left + margin.right) + "px")
+        .style("height", (height + margin.top + margin.bottom) + "px");
+
+    const canvas = root.append("canvas")
+        .attr("width", width + margin.left + margin.right)
+        .attr("height", height + margin.top + margin.bottom)
+        .style("position", "absolute")
+        .style("cursor", "crosshair");
+    const context = canvas.node().getContext("2d");
+
+    const overlay = root.append("svg")
+        .attr("width", width + margin.left + margin.right)
+        .attr("height", height + margin.top + margin.bottom)
+        .style("position", "absolute")
+        .style("pointer-events", "none")
+        .append("g")
+        .attr("transform", "translate(" + margin.left + "," + margin.top + ")");
+
+    d3.json(apiBase + "/embedding", { credentials: "same-origin" })
+        .then(function (embedding) {
+            const songs = embedding.songs;
+            const x = d3.scaleLinear()
+                .domain(d3.extent(songs, d => d.x)).nice()
+                .range([0, width]);
+            const y = d3.scaleLinear()
+                .domain(d3.extent(songs, d => d.y)).nice()
+                .range([height, 0]);
+
+            context.translate(margin.left, margin.top);
+            context.fillStyle = colors.song;
+            for (const song of songs) {
+                context.fillRect(x(song.x) - 1, y(song.y) - 1, 2, 2);
+            }
+
+            const variance = embedding.explained_variance.map(v => (v * 100).toFixed(1) + "%");
+            overlay.append("text")
+                .attr("x", width / 2)
+                .attr("y", -margin.top / 2)
+                .attr("text-anchor", "middle")
+                .style("font-size", "18px")
+                .style("font-weight", "bold")
+                .text(`Library map (PCA, ${variance[0]} + ${variance[1]} of variance)`);
+
+            const byPath = new Map(songs.map(song => [song.path, song]));
+            // songs are bucketed into a grid of screen cells, so finding the one under the mouse
+            // only looks at the cells around it instead of the whole library
+            const cellSize = 20;
+            const columns = Math.ceil(width / cellSize) + 1;
+            const rows = Math.ceil(height / cellSize) + 1;
+            const grid = new Map();
+            const points = songs.map(function (song, i) {
+                const point = [x(song.x), y(song.y)];
+                const key = Math.floor(point[1] / cellSize) * columns + Math.floor(point[0] / cellSize);
+                if (!grid.has(key)) {
+                    grid.set(key, []);
+                }
+                grid.get(key).push(i);
+                return point;
+            });
+            const nearest = function (event) {
+                const rect = canvas.node().getBoundingClientRect();
+                const mx = event.clientX - rect.left - margin.left;
+                const my = event.clientY - rect.top - margin.top;
+                const cx = Math.floor(mx / cellSize), cy = Math.floor(my / cellSize);
+                let best, bestDistance = Infinity;
+                // search rings of cells outwards, until no song in the next ring could be closer
+                for (let ring = 0; ring <= columns + rows; ring++) {
+                    if (best !== undefined && ((ring - 1) * cellSize) ** 2 > bestDistance) {
+                        break;
+                    }
+                    for (let gy = cy - ring; gy <= cy + ring; gy++) {
+                        for (let gx = cx - ring; gx <= cx + ring; gx++) {
+                            const onRing = Math.max(Math.abs(gx - cx), Math.abs(gy - cy)) === ring;
+                            if (!onRing || gx < 0 || gy < 0 || gx >= columns || gy >= rows) {
+                                continue;
+                            }
+                            for (const i of grid.get(gy * columns + gx) || []) {
+                                const [px, py] = points[i];
+                                const distance = (px - mx) ** 2 + (py - my) ** 2;
+                                if (distance < bestDistance) {
+                                    best = songs[i];
+                                    bestDistance = distance;
+                                }
+                            }
+                        }
+                    }
+                }
+                return best;
+            };
+
+            canvas
+                .on("mousemove", function (event) {
+                    const song = nearest(event);
+                    if (song === undefined) {
+                        return;
+                    }
+                    tooltip
+                        .style("opacity", 1)
+                        .text(song.path)
+                        .style("left", (event.pageX + 10) + "px")
+                        .style("top", (event.pageY - 28) + "px");
+                })
+                .on("mouseout", () => tooltip.style("opacity", 0))
+                .on("click", function (event) {
+                    const song = nearest(event);
+                    if (song !== undefined) {
+                        onSelect(song.path);
+                    }
+                });
+
+            const highlight = function (paths, color, radius) {
+                overlay.selectAll(null)
+                    .data(paths.map(path => byPath.get(path)).filter(song => song !== undefined))
+                    .enter()
+                    .append("circle")
+                    .attr("class", "highlight")
+                    .attr("cx", d => x(d.x))
+                    .attr("cy", d => y(d.y))
+                    .attr("r", radius)
+                    .style("fill", color)
+                    .style("stroke", "white");
+            };
+
+            // only available when worf is queueing in the same process (`--serve`)
+            const refreshHighlights = function () {
+                const fetchDaemon = endpoint => d3.json(apiBase + "/daemon/" + endpoint, { credentials: "same-origin" })
+                    .catch(() => null);
+                Promise.all([fetchDaemon("pin"), fetchDaemon("history"), fetchDaemon("upcoming")])
+                    .then(function ([pin, history, upcoming]) {
+                        overlay.selectAll(".highlight").remove();
+                        highlight((upcoming || []).map(song => song.path), colors.upcoming, 3);
+                        highlight((history || []).map(song => song.path), colors.history, 4);
+                        highlight(pin ? [pin.path] : [], colors.pin, 7);
+                    });
+            };
+            refreshHighlights();
+
+            const updateStatus = overlay.append("text")
+                .attr("x", width)
+                .attr("y", height + margin.bottom * 0.8)
+                .attr("text-anchor", "end")
+                .style("font-size", "12px");
+
+            let events, stopped = false, retryDelay = 1000;
+            const onEvent = function (message) {
+                const event = JSON.parse(message.data);
+                if (event.type === "pin_changed" || event.type === "song_queued") {
+                    refreshHighlights();
+                } else if (event.type === "update_started") {
+                    updateStatus.text("Updating library...");
+                } else if (event.type === "update_progress") {
+                    updateStatus.text(`Updating library: ${event.analyzed} of ${event.total} new songs analyzed`);
+                } else if (event.type === "update_failed") {
+                    updateStatus.text("Library update failed: " + event.error);
+                } else if (event.type === "library_refreshed") {
+                    // the projection was recomputed after an update
+                    stopped = true;
+                    events.close();
+                    root.selectAll("*").remove();
+                    renderLibraryMap(container, apiBase, tooltip, onSelect);
+                }
+            };
+            // browsers reconnect on their own after a dropped connection, but give up when the
+            // stream is refused (e.g. while the server restarts, or for a standalone server, which
+            // has none), so keep trying with backoff
+            const connect = function () {
+                if (stopped) {
+                    return;
+                }
+                events = new EventSource(apiBase + "/events", { withCredentials: true });
+                events.onopen = () => retryDelay = 1000;
+                events.onmessage = onEvent;
+                events.onerror = function () {
+                    if (events.readyState === EventSource.CLOSED) {
+                        setTimeout(connect, retryDelay);
+                        retryDelay = Math.min(retryDelay * 2, 60000);
+                    }
+                };
+            };
+            connect();
+
+            console.log("Library map rendered with", songs.length, "songs");
+        })
+        .catch(function (error) {
+            console.error("Error loading library map:", error);
+        });
+}
@@ -0,0 +1,246 @@
+//! State shared between the queueing loop and the server, so that the server can report what the
+//! daemon is doing when both run in the same process.
+
+use crate::mpd_library::BlissSong;
+use itertools::Itertools;
+use rocket::tokio::sync::broadcast;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// How many of the upcoming generated songs to keep track of.
+pub const UPCOMING_LEN: usize = 50;
+/// How many queued songs to remember.
+pub const HISTORY_LEN: usize = 100;
+/// How many events a slow subscriber can fall behind before it starts missing them.
+const EVENT_CAPACITY: usize = 64;
+
+/// Which sort the daemon uses to generate recommendations.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueueMode {
+    Bliss,
+    Genres,
+}
+
+/// A song as reported by the daemon, with its path relative to the MPD base path.
+#[derive(Serialize, Clone, Debug)]
+pub struct DaemonSong {
+    pub path: PathBuf,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+}
+
+impl DaemonSong {
+    /// `path` is relative to the MPD base path, see
+    /// [MPDLibrary::relative_path](crate::mpd_library::MPDLibrary::relative_path).
+    pub fn from_bliss(song: &BlissSong, path: &Path) -> Self {
+        let bliss_song = &song.bliss_song;
+        Self {
+            path: path.to_path_buf(),
+            artist: bliss_song.artist.clone(),
+            title: bliss_song.title.clone(),
+            album: bliss_song.album.clone(),
+            genre: bliss_song.genre.clone(),
+        }
+    }
+}
+
+/// Something the daemon did, published to subscribers of the event stream.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DaemonEvent {
+    PinChanged {
+        pin: DaemonSong,
+    },
+    SongQueued {
+        song: DaemonSong,
+        bliss_distance: f32,
+        genre_similarity: Option<f32>,
+        popularity: i32,
+    },
+    ModeChanged {
+        mode: QueueMode,
+    },
+    UpdateStarted,
+    /// Sent after each batch of new songs is analyzed
+    UpdateProgress {
+        analyzed: usize,
+        total: usize,
+    },
+    UpdateFinished,
+    UpdateFailed {
+        error: String,
+    },
+    MpdDisconnected {
+        error: String,
+    },
+    MpdReconnected,
+    /// The server has picked up the latest library update, e.g. the library map was recomputed
+    LibraryRefreshed,
+}
+
+/// A snapshot of everything the daemon is doing.
+#[derive(Serialize)]
+pub struct DaemonStatus {
+    pub mode: QueueMode,
+    pub pin: Option<DaemonSong>,
+    pub requested_pin: Option<PathBuf>,
+    pub upcoming: Vec<DaemonSong>,
+    pub history: Vec<DaemonSong>,
+    pub excluded: Vec<PathBuf>,
+    pub update_pending: bool,
+}
+
+/// What the queueing loop is currently doing. Written by the daemon, read by the server. Control
+/// requests from the server (new pins, exclusions, updates) are picked up by the daemon once MPD
+/// wakes it up, which the server makes sure happens right away.
+pub struct DaemonState {
+    mode: Mutex<QueueMode>,
+    pin: Mutex<Option<DaemonSong>>,
+    requested_pin: Mutex<Option<PathBuf>>,
+    upcoming: Mutex<Vec<DaemonSong>>,
+    history: Mutex<VecDeque<DaemonSong>>,
+    excluded: Mutex<HashSet<PathBuf>>,
+    update_on_next_loop: Arc<AtomicBool>,
+    events: broadcast::Sender<DaemonEvent>,
+}
+
+impl DaemonState {
+    pub fn new(mode: QueueMode) -> Self {
+        Self {
+            mode: Mutex::new(mode),
+            pin: Mutex::new(None),
+            requested_pin: Mutex::new(None),
+            upcoming: Mutex::new(vec![]),
+            history: Mutex::new(VecDeque::with_capacity(HISTORY_LEN)),
+            excluded: Mutex::new(HashSet::new()),
+            update_on_next_loop: Arc::new(AtomicBool::new(false)),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+        }
+    }
+
+    /// Send an event to everyone listening. Nobody listening is fine.
+    pub fn publish(&self, event: DaemonEvent) {
+        let _ = self.events.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DaemonEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn status(&self) -> DaemonStatus {
+        DaemonStatus {
+            mode: self.mode(),
+            pin: self.pin(),
+            requested_pin: self.requested_pin(),
+            upcoming: self.upcoming(),
+            history: self.history(),
+            excluded: self
+                .excluded
+                .lock()
+                .expect("Poisoned lock")
+                .iter()
+                .cloned()
+                .sorted()
+                .collect(),
+            update_pending: self.update_pending(),
+        }
+    }
+
+    pub fn mode(&self) -> QueueMode {
+        *self.mode.lock().expect("Poisoned lock")
+    }
+
+    pub fn set_mode(&self, mode: QueueMode) {
+        *self.mode.lock().expect("Poisoned lock") = mode;
+        self.publish(DaemonEvent::ModeChanged { mode });
+    }
+
+    /// Switch between bliss and genres mode, returning the new mode.
+    pub fn toggle_mode(&self) -> QueueMode {
+        let mut mode = self.mode.lock().expect("Poisoned lock");
+        *mode = match *mode {
+            QueueMode::Bliss => QueueMode::Genres,
+            QueueMode::Genres => QueueMode::Bliss,
+        };
+        self.publish(DaemonEvent::ModeChanged { mode: *mode });
+        *mode
+    }
+
+    pub fn pin(&self) -> Option<DaemonSong> {
+        self.pin.lock().expect("Poisoned lock").clone()
+    }
+
+    pub fn set_pin(&self, pin: DaemonSong) {
+        *self.pin.lock().expect("Poisoned lock") = Some(pin.clone());
+        self.publish(DaemonEvent::PinChanged { pin });
+    }
+
+    /// The path (relative to the MPD base path) of a song that should become the pin once it
+    /// shows up in the queue.
+    pub fn requested_pin(&self) -> Option<PathBuf> {
+        self.requested_pin.lock().expect("Poisoned lock").clone()
+    }
+
+    pub fn request_pin(&self, path: PathBuf) {
+        *self.requested_pin.lock().expect("Poisoned lock") = Some(path);
+    }
+
+    pub fn clear_requested_pin(&self) {
+        *self.requested_pin.lock().expect("Poisoned lock") = None;
+    }
+
+    pub fn upcoming(&self) -> Vec<DaemonSong> {
+        self.upcoming.lock().expect("Poisoned lock").clone()
+    }
+
+    pub fn set_upcoming(&self, upcoming: Vec<DaemonSong>) {
+        *self.upcoming.lock().expect("Poisoned lock") = upcoming;
+    }
+
+    /// Most recently queued song last.
+    pub fn history(&self) -> Vec<DaemonSong> {
+        self.history
+            .lock()
+            .expect("Poisoned lock")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    pub fn push_history(&self, song: DaemonSong) {
+        let mut history = self.history.lock().expect("Poisoned lock");
+        if history.len() == HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(song);
+    }
+
+    /// Never recommend the song at `path` (relative to the MPD base path) again while the daemon
+    /// is running.
+    pub fn exclude(&self, path: PathBuf) {
+        self.excluded.lock().expect("Poisoned lock").insert(path);
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.excluded.lock().expect("Poisoned lock").contains(path)
+    }
+
+    /// The flag the daemon checks before handling each MPD event, shared with the signal handlers.
+    pub fn update_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.update_on_next_loop)
+    }
+
+    pub fn request_update(&self) {
+        self.update_on_next_loop.store(true, Ordering::SeqCst);
+    }
+
+    pub fn update_pending(&self) -> bool {
+        self.update_on_next_loop.load(Ordering::SeqCst)
+    }
+}
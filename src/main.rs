@@ -17,9 +17,9 @@
 // - would be nice to have a way to exclude a song from recommendations completely
 // - restart with current song as pin on SIGHUP
 // - switch between genres/bliss/future modes with SIGUSR1
-// - is there some way to do caching? but you immediately run into a cache invalidation at the next update; maybe the update thread will be responsible for updating the cache?
 // - popularity filter on recommendations; gonna need to figure out the tagging (done!)
 
+mod daemon;
 mod mpd_library;
 mod server;
 
@@ -27,29 +27,31 @@ use anyhow::{Context, Result, anyhow, bail};
 use bliss_audio::FeaturesVersion;
 use bliss_audio::playlist::{closest_to_songs, euclidean_distance};
 use clap::{Parser, Subcommand};
+use daemon::{DaemonState, QueueMode};
 use futures::stream::StreamExt;
 use itertools::Itertools;
 use log::info;
 use mpd::Song as MPDSong;
-use mpd_library::{BlissSong, MPDLibrary, closest_to_genre_songs, collapse_genres, pad_slice};
+use mpd_library::{
+    BlissSong, ExportFormat, GenreStats, MPDLibrary, Metric, Normalization, closest_to_genre_songs,
+    collapse_genres, pad_slice,
+};
 use ndarray::arr1;
-use rocket::Config;
 use rocket::fs::{FileServer, Options, relative};
-use rocket::routes;
+use rocket::{Build, Config, Rocket, catchers, routes};
 use server::{
-    CHUNK_SIZE, ChunkedReadOnlyHashMap, ClientLibrary, albumart, all, analysis, info, playlist,
+    ClientLibrary, Cors, albumart, all, all_after, analysis, audio, daemon_history, daemon_mode,
+    daemon_pin, daemon_upcoming, embedding, events, exclude, info, playlist, search, set_mode,
+    set_pin, status, unauthorized, update,
 };
 use signal_hook::consts::signal::*;
 use signal_hook_tokio::Signals;
-use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
-
-use crate::mpd_library::collapse_genres_pad_to;
-use crate::server::SongAnalyses;
+use std::sync::Arc;
 
 pub const NUM_GENRE_FEATURES: usize = 5;
 pub const NUM_BLISS_FEATURES: usize = FeaturesVersion::LATEST.feature_count();
@@ -82,14 +84,36 @@ struct Args {
     #[arg(short = 'f', long)]
     /// Only recommend songs at least as popular as the pin, within 10% (only for `bliss` and `genres`, requires songs tagged with popularity -- see README)
     popularity_filter: bool,
+    #[arg(long, value_enum)]
+    /// Distance metric to queue by (only for `bliss` and `genres`), `distance.metric` in the bliss config if not given. `forest` needs several seeds, so it's only available from `/api/playlist`
+    distance: Option<Metric>,
+    #[arg(long, value_enum)]
+    /// How to rescale bliss features before comparing them (only for `bliss` and `genres`), `distance.normalization` in the bliss config if not given
+    normalization: Option<Normalization>,
+    #[arg(short, long)]
+    /// Also run the server while queueing, bound to this address (only for `bliss` and `genres`). Same formats as `server`
+    serve: Option<String>,
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+#[derive(Subcommand, Debug, PartialEq)]
+enum GenresCommands {
+    /// Report how much of the library the genre map covers
+    Stats {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
 #[derive(Subcommand, Debug, PartialEq)]
 enum Commands {
     /// Queue songs based on genre similarity
-    Genres,
+    Genres {
+        #[command(subcommand)]
+        command: Option<GenresCommands>,
+    },
     /// Queue songs based on audio similarity from bliss
     Bliss,
     /// Serve analysis over the network
@@ -101,37 +125,151 @@ enum Commands {
     Update,
     /// Initialize (or reinitialize) bliss library
     Init,
+    /// Export every analyzed song with its tags, extra info and features
+    Export {
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// File to write to, stdout if not given
+        output: Option<PathBuf>,
+    },
+    /// Merge an export into the bliss library without analyzing the songs again
+    Import {
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// File to read from, stdin if not given
+        input: Option<PathBuf>,
+        /// Replace songs that are already in the library
+        #[arg(long)]
+        overwrite: bool,
+    },
+    /// Rewrite stored song paths after moving the library
+    Relocate {
+        /// Old location of the songs, the configured MPD base path if not given
+        #[arg(long)]
+        from: Option<PathBuf>,
+        /// New location of the songs
+        #[arg(long)]
+        to: PathBuf,
+    },
 }
 
 struct PinnedSong(MPDSong);
 
-async fn handle_signals(
-    mut signals: Signals,
-    current_mode: Arc<Mutex<Commands>>,
-    update_on_next_loop: Arc<AtomicBool>,
-) {
+async fn handle_signals(mut signals: Signals, daemon: Arc<DaemonState>) {
     while let Some(signal) = signals.next().await {
         match signal {
-            SIGHUP => {
-                if *current_mode.lock().expect("Poisoned lock") == Commands::Bliss {
-                    println!("Switching to genre mode");
-                    *current_mode.lock().expect("Poisoned lock") = Commands::Genres;
-                } else if *current_mode.lock().expect("Poisoned lock") == Commands::Genres {
-                    println!("Switching to bliss mode");
-                    *current_mode.lock().expect("Poisoned lock") = Commands::Bliss;
-                } else {
-                    info!("Not in bliss or genres mode, ignoring SIGHUP");
-                }
-            }
+            SIGHUP => match daemon.toggle_mode() {
+                QueueMode::Genres => println!("Switching to genre mode"),
+                QueueMode::Bliss => println!("Switching to bliss mode"),
+            },
             SIGUSR1 => {
                 println!("Updating library on next loop...");
-                update_on_next_loop.store(true, Ordering::SeqCst);
+                daemon.request_update();
             }
             _ => unreachable!(),
         }
     }
 }
 
+/// Print a genre coverage report, with the most common genres.
+fn print_genre_stats(stats: &GenreStats) {
+    const SHOWN_GENRES: usize = 20;
+    let percent = |n: usize| 100.0 * n as f32 / stats.songs.max(1) as f32;
+    println!("{} songs", stats.songs);
+    println!(
+        "{} ({:.1}%) have no genre tag",
+        stats.untagged,
+        percent(stats.untagged)
+    );
+    println!(
+        "{} ({:.1}%) only have genres missing from the genre map",
+        stats.all_unknown,
+        percent(stats.all_unknown)
+    );
+    let covered = stats.songs - stats.untagged - stats.all_unknown;
+    println!("{covered} ({:.1}%) have genre weights", percent(covered));
+    if stats.word_matches > 0 {
+        println!(
+            "{} genres missing from the genre map were matched to known genres by their words",
+            stats.word_matches
+        );
+    }
+    for (title, genres) in [
+        ("Most common unknown genres", &stats.unknown_genres),
+        ("Most common known genres", &stats.known_genres),
+    ] {
+        println!("\n{title} ({} total):", genres.len());
+        for count in genres.iter().take(SHOWN_GENRES) {
+            println!("{:>8}  {}", count.songs, count.genre);
+        }
+    }
+}
+
+/// Parse a bind address in one of the formats `address`, `address:port`, `:port`.
+fn parse_bind_address(bind: &str) -> Result<(&str, u16)> {
+    Ok(match bind.split_once(':') {
+        Some(("", port)) => (
+            "127.0.0.1",
+            port.parse::<u16>()
+                .context("while trying to parse server port")?,
+        ),
+        Some((address, "")) => (address, 8080),
+        Some((address, port)) => (
+            address,
+            port.parse::<u16>()
+                .context("while trying to parse server port")?,
+        ),
+        None => {
+            // no IPv6 but could probably fix that by splitting only on the last `:`
+            if Ipv4Addr::from_str(bind).is_ok() {
+                (bind, 8080)
+            } else {
+                println!("Error parsing server bind address or port, using default 127.0.0.1:8080");
+                ("127.0.0.1", 8080)
+            }
+        }
+    })
+}
+
+/// Build the Rocket server for the frontend and the analysis API. Pass `with_daemon` when the
+/// server runs alongside the queueing loop, so that Ctrl-C ends the whole process instead of just
+/// the server.
+fn build_server(
+    library_interface: ClientLibrary,
+    bind_to: Option<String>,
+    with_daemon: bool,
+) -> Result<Rocket<Build>> {
+    let bind = bind_to.unwrap_or("127.0.0.1:8080".to_string());
+    let (address, port) = parse_bind_address(&bind)?;
+
+    let server_config = &library_interface.mpd_library.bliss.config.server;
+    let is_loopback = address == "localhost"
+        || Ipv4Addr::from_str(address).is_ok_and(|address| address.is_loopback());
+    if !is_loopback && !server_config.requires_auth() {
+        bail!(
+            "Refusing to serve on {address} without authentication, anyone who can reach it could control worf. Add credentials under `server` in the worf config or bind to localhost."
+        );
+    }
+
+    let figment = Config::figment()
+        .merge(("address", address))
+        .merge(("port", port))
+        .merge(("shutdown.ctrlc", !with_daemon));
+
+    Ok(rocket::custom(figment)
+        .mount("/", FileServer::new(relative!("public"), Options::Index))
+        .mount(
+            "/api/",
+            routes![
+                all, all_after, search, info, analysis, embedding, audio, albumart, playlist
+            ],
+        )
+        // .register("/", catchers![not_found])
+        .register("/api/", catchers![unauthorized])
+        .attach(Cors)
+        .manage(library_interface))
+}
+
 #[rocket::main]
 async fn main() -> Result<()> {
     env_logger::init();
@@ -145,22 +283,53 @@ async fn main() -> Result<()> {
     };
 
     match args.command {
-        Some(mode @ (Commands::Genres | Commands::Bliss)) => {
+        Some(mode @ (Commands::Genres { command: None } | Commands::Bliss)) => {
             println!("Queueing songs in background...");
-            let current_mode = Arc::new(Mutex::new(mode));
+            let daemon = Arc::new(DaemonState::new(
+                if mode == (Commands::Genres { command: None }) {
+                    QueueMode::Genres
+                } else {
+                    QueueMode::Bliss
+                },
+            ));
             let mut mpd_library = MPDLibrary::retrieve(config_path.clone())?;
 
-            let track_weights = mpd_library
-                .get_track_genre_weights(args.genres_path)
-                .unwrap_or_else(|e| {
-                    info!("Failed to get genre weights: {e}");
-                    HashMap::new()
-                });
+            if let Err(e) = mpd_library.get_track_genre_weights(args.genres_path) {
+                info!("Failed to get genre weights: {e}");
+            }
 
             if args.update_library {
                 mpd_library.update().await?;
             }
 
+            mpd_library.build_index()?;
+
+            let mpd_library = Arc::new(mpd_library);
+
+            if let Some(bind_to) = args.serve {
+                let library_interface = ClientLibrary::new(Arc::clone(&mpd_library))?;
+                rocket::tokio::spawn(
+                    library_interface
+                        .clone()
+                        .refresh_on_updates(Arc::clone(&daemon)),
+                );
+                let server = build_server(library_interface, Some(bind_to), true)?
+                    .mount(
+                        "/api/daemon/",
+                        routes![daemon_pin, daemon_mode, daemon_upcoming, daemon_history],
+                    )
+                    .mount(
+                        "/api/",
+                        routes![status, events, set_pin, set_mode, exclude, update],
+                    )
+                    .manage(Arc::clone(&daemon));
+                rocket::tokio::spawn(async move {
+                    if let Err(e) = server.launch().await {
+                        println!("Server stopped: {e}");
+                    }
+                });
+            }
+
             let current_song = mpd_library.get_current_song().await?;
 
             let mut pinned_song = PinnedSong(current_song);
@@ -170,26 +339,30 @@ async fn main() -> Result<()> {
                 pinned_song.0.title.clone().unwrap_or("Unknown".to_string())
             );
 
-            // let forest_distance: &dyn DistanceMetricBuilder = &ForestOptions {
-            //     n_trees: 1000,
-            //     sample_size: 200,
-            //     max_tree_depth: None,
-            //     extension_level: 10,
-            // }; // this seems to only work right with multiple songs; with only one song as the pin, it always generates the same playlist
+            let metric = args
+                .distance
+                .unwrap_or(mpd_library.bliss.config.distance.metric);
+            // the pin is the only seed
+            let (bliss_distance, genre_distance) = metric
+                .build(&mpd_library, 1)
+                .with_context(|| format!("while building {} distance", metric.name()))?;
+            let normalization = args
+                .normalization
+                .unwrap_or(mpd_library.bliss.config.distance.normalization);
+            println!(
+                "Using {} distance with {} normalization",
+                metric.name(),
+                normalization.name()
+            );
 
             let signals = Signals::new([SIGHUP, SIGUSR1])?;
             let _handle = signals.handle();
-            let update_on_next_loop = Arc::new(AtomicBool::new(false));
-            signal_hook::flag::register(SIGHUP, Arc::clone(&update_on_next_loop))?;
-            let _signals_task = rocket::tokio::spawn(handle_signals(
-                signals,
-                Arc::clone(&current_mode),
-                Arc::clone(&update_on_next_loop),
-            ));
+            signal_hook::flag::register(SIGHUP, daemon.update_flag())?;
+            let _signals_task = rocket::tokio::spawn(handle_signals(signals, Arc::clone(&daemon)));
 
             loop {
                 let current_genre: Option<String>;
-                if Commands::Genres == *current_mode.lock().expect("Poisoned lock") {
+                if daemon.mode() == QueueMode::Genres {
                     current_genre = Some(
                         mpd_library
                             .mpd_to_bliss_song(&pinned_song.0)
@@ -201,9 +374,9 @@ async fn main() -> Result<()> {
                     );
                     let current_genre_weight = collapse_genres(
                         &mpd_library
-                            .genre_weights
-                            .clone()
+                            .genre_weights()?
                             .ok_or(anyhow!("while getting genre weights"))?,
+                        &mpd_library.bliss.config.genres,
                         // this unwrap is fine because we just set current_genre to Some
                         current_genre
                             .as_ref()
@@ -222,8 +395,7 @@ async fn main() -> Result<()> {
                         println!(
                             "Closest 10 genres: {}",
                             mpd_library
-                                .genre_weights
-                                .clone()
+                                .genre_weights()?
                                 .ok_or(anyhow!("while getting genre weights"))?
                                 .iter()
                                 .sorted_by(|&a, b| euclidean_distance(
@@ -249,40 +421,62 @@ async fn main() -> Result<()> {
                     println!("Pin has no genre, using bliss similarity");
                 }
 
-                let track_weights_clone = track_weights.clone();
+                // kept in memory between pins, and worked out again after an update
+                let track_weights = mpd_library.track_weights()?;
+                // statistics are recomputed after an update, so this is rebuilt for each pin
+                let normalized_distance = normalization
+                    .apply(metric, bliss_distance.as_ref(), || {
+                        mpd_library.feature_stats()
+                    })
+                    .context("while computing feature statistics")?;
 
                 pinned_song = PinnedSong(
                     mpd_library
                         .queue_from_song(
                             &pinned_song.0,
                             10,
-                            &euclidean_distance,
+                            &normalized_distance,
                             |x, y, z| {
-                                Box::new(
-                                    if Commands::Genres
-                                        == *current_mode.lock().expect("Poisoned lock")
-                                    {
-                                        closest_to_genre_songs(x, y, z, &track_weights_clone)
-                                            .collect::<Vec<_>>()
-                                            .into_iter()
-                                    } else {
-                                        closest_to_songs(x, y, z).collect::<Vec<_>>().into_iter()
-                                    },
-                                )
+                                Box::new(if daemon.mode() == QueueMode::Genres {
+                                    closest_to_genre_songs(
+                                        x,
+                                        y,
+                                        z,
+                                        genre_distance.as_ref(),
+                                        &track_weights,
+                                    )
+                                    .collect::<Vec<_>>()
+                                    .into_iter()
+                                } else {
+                                    closest_to_songs(x, y, z).collect::<Vec<_>>().into_iter()
+                                })
                             },
                             Some(popularity_filter),
                             true,
+                            (daemon.mode() == QueueMode::Bliss).then_some((metric, normalization)),
                             true,
                             std::time::Instant::now(),
-                            Arc::clone(&update_on_next_loop),
+                            &daemon,
                         )
                         .await?,
                 );
             }
         }
+        Some(Commands::Genres {
+            command: Some(GenresCommands::Stats { json }),
+        }) => {
+            let mpd_library = MPDLibrary::retrieve(config_path)?;
+            let stats = mpd_library.genre_stats(args.genres_path)?;
+            if json {
+                serde_json::to_writer_pretty(io::stdout().lock(), &stats)
+                    .context("while writing genre stats")?;
+                println!();
+            } else {
+                print_genre_stats(&stats);
+            }
+            Ok(())
+        }
         Some(Commands::Server { bind_to }) => {
-            let bind = bind_to.clone().unwrap_or("127.0.0.1:8080".to_string());
-
             let mut mpd_library = MPDLibrary::retrieve(config_path.clone())?;
 
             if args.update_library {
@@ -290,76 +484,9 @@ async fn main() -> Result<()> {
             }
 
             mpd_library.get_track_genre_weights(args.genres_path)?;
+            mpd_library.build_index()?;
 
-            let songs = mpd_library
-                .bliss
-                .songs_from_library()?
-                .iter()
-                .map(|song: &BlissSong| {
-                    (
-                        song.bliss_song
-                            .path
-                            .strip_prefix(&mpd_library.bliss.config.mpd_base_path)
-                            .expect("failed to strip MPD base path")
-                            .to_path_buf(),
-                        SongAnalyses {
-                            bliss: *song
-                                .bliss_song
-                                .analysis
-                                .as_vec()
-                                .as_array()
-                                .expect("while converting bliss analysis to array"),
-                            genre: collapse_genres_pad_to(
-                                mpd_library
-                                    .genre_weights
-                                    .as_ref()
-                                    .unwrap_or(&HashMap::new()),
-                                song.bliss_song.genre.clone().unwrap_or("".into()),
-                            ),
-                        },
-                    )
-                })
-                .collect();
-
-            let library_interface = ClientLibrary {
-                songs: ChunkedReadOnlyHashMap::new(songs, CHUNK_SIZE),
-                mpd_library,
-            };
-
-            let (address, port) = match bind.split_once(':') {
-                Some(("", port)) => (
-                    "127.0.0.1",
-                    port.parse::<u16>()
-                        .context("while trying to parse server port")?,
-                ),
-                Some((address, "")) => (address, 8080),
-                Some((address, port)) => (
-                    address,
-                    port.parse::<u16>()
-                        .context("while trying to parse server port")?,
-                ),
-                None => {
-                    // no IPv6 but could probably fix that by splitting only on the last `:`
-                    if Ipv4Addr::from_str(&bind).is_ok() {
-                        (bind.as_str(), 8080)
-                    } else {
-                        println!(
-                            "Error parsing server bind address or port, using default 127.0.0.1:8080"
-                        );
-                        ("127.0.0.1", 8080)
-                    }
-                }
-            };
-
-            let figment = Config::figment()
-                .merge(("address", address))
-                .merge(("port", port));
-
-            rocket::custom(figment)
-                .mount("/", FileServer::new(relative!("public"), Options::Index))
-                .mount("/api/", routes![all, info, analysis, albumart, playlist])
-                // .register("/", catchers![not_found])
-                .manage(library_interface)
+            build_server(ClientLibrary::new(Arc::new(mpd_library))?, bind_to, false)?
                 .launch()
                 .await
                 .context("while starting Rocket server")?;
@@ -377,11 +504,79 @@ async fn main() -> Result<()> {
         }
         Some(Commands::Update) => {
             println!("Updating music library analysis...");
-            let mut mpd_library = MPDLibrary::retrieve(config_path)?;
+            let mpd_library = MPDLibrary::retrieve(config_path)?;
             mpd_library.update().await
         }
+        Some(Commands::Export { format, output }) => {
+            let mut mpd_library = MPDLibrary::retrieve(config_path)?;
+            // genre features are optional in exports
+            if let Err(e) = mpd_library.get_track_genre_weights(args.genres_path) {
+                info!("Failed to get genre weights: {e}");
+            }
+            let writer: Box<dyn Write> = match output {
+                Some(path) => Box::new(BufWriter::new(
+                    File::create(path).context("while creating export file")?,
+                )),
+                None => Box::new(BufWriter::new(io::stdout().lock())),
+            };
+            let count = mpd_library.export(format, writer)?;
+            // stdout might be the export itself
+            eprintln!("Exported {count} songs");
+            Ok(())
+        }
+        Some(Commands::Import {
+            format,
+            input,
+            overwrite,
+        }) => {
+            let mpd_library = MPDLibrary::retrieve(config_path)?;
+            let reader: Box<dyn Read> = match input {
+                Some(path) => Box::new(BufReader::new(
+                    File::open(path).context("while opening import file")?,
+                )),
+                None => Box::new(BufReader::new(io::stdin().lock())),
+            };
+            let (imported, skipped) = mpd_library.import(format, reader, overwrite)?;
+            println!("Imported {imported} songs, skipped {skipped} already in the library");
+            Ok(())
+        }
+        Some(Commands::Relocate { from, to }) => {
+            let mpd_library = MPDLibrary::retrieve(config_path)?;
+            let from = from.unwrap_or_else(|| mpd_library.bliss.config.mpd_base_path.clone());
+            let relocated = mpd_library.relocate(&from, &to)?;
+            println!(
+                "Relocated {relocated} songs from {} to {}",
+                from.display(),
+                to.display()
+            );
+            Ok(())
+        }
         None => {
             bail!("No command provided!");
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_address_formats() {
+        assert_eq!(parse_bind_address(":9000").unwrap(), ("127.0.0.1", 9000));
+        assert_eq!(parse_bind_address("0.0.0.0:").unwrap(), ("0.0.0.0", 8080));
+        assert_eq!(
+            parse_bind_address("192.168.1.2:81").unwrap(),
+            ("192.168.1.2", 81)
+        );
+        assert_eq!(parse_bind_address("10.0.0.1").unwrap(), ("10.0.0.1", 8080));
+        // anything else falls back to the default
+        assert_eq!(parse_bind_address("nonsense").unwrap(), ("127.0.0.1", 8080));
+    }
+
+    #[test]
+    fn bad_ports_are_errors() {
+        assert!(parse_bind_address(":http").is_err());
+        assert!(parse_bind_address("0.0.0.0:70000").is_err());
+    }
+}
@@ -0,0 +1,446 @@
+//! The distance metrics songs can be compared with, including ones that count some features more
+//! than others, e.g. to care more about tempo than timbre.
+
+use super::MPDLibrary;
+use super::normalization::Normalization;
+use crate::{NUM_BLISS_FEATURES, NUM_GENRE_FEATURES};
+use anyhow::{Context, Result, bail};
+use bliss_audio::playlist::{
+    DistanceMetric, DistanceMetricBuilder, ForestOptions, cosine_distance, euclidean_distance,
+};
+use ndarray::{Array1, Array2};
+use rocket::form::FromFormField;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The most seeds the isolation forest samples for each of its trees.
+const MAX_FOREST_SAMPLE_SIZE: usize = 256;
+
+/// A distance metric, for the daemon (`--distance` or `distance.metric` in the config) and for
+/// `/api/playlist`.
+#[derive(
+    clap::ValueEnum,
+    FromFormField,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    #[default]
+    Euclidean,
+    Cosine,
+    /// Euclidean, with the feature weights from the config
+    Weighted,
+    /// Euclidean after decorrelating the features, fit on the whole library. Genre weights are
+    /// compared with euclidean distance.
+    Mahalanobis,
+    /// How anomalous a song is to an isolation forest trained on the seeds. Needs more than one
+    /// seed. Genre weights are compared with euclidean distance.
+    Forest,
+}
+
+/// Metrics for bliss analyses and for genre weights.
+pub type Metrics = (
+    Box<dyn DistanceMetricBuilder + Sync>,
+    Box<dyn DistanceMetricBuilder + Sync>,
+);
+
+impl Metric {
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Euclidean => "euclidean",
+            Metric::Cosine => "cosine",
+            Metric::Weighted => "weighted",
+            Metric::Mahalanobis => "mahalanobis",
+            Metric::Forest => "forest",
+        }
+    }
+
+    /// Build the metrics for `seeds` seed songs from `library`'s distance config, genre axes and
+    /// Mahalanobis fit.
+    ///
+    /// May fail if the distance weights are invalid, if the forest doesn't have enough seeds, or
+    /// if Mahalanobis distance can't be fit on the library.
+    pub fn build(self, library: &MPDLibrary, seeds: usize) -> Result<Metrics> {
+        let config = &library.bliss.config.distance;
+        Ok(match self {
+            Metric::Euclidean => (Box::new(euclidean_distance), Box::new(euclidean_distance)),
+            Metric::Cosine => (Box::new(cosine_distance), Box::new(cosine_distance)),
+            Metric::Weighted => (
+                Box::new(config.bliss_metric()?),
+                Box::new(config.genre_metric(library.genre_axes())?),
+            ),
+            Metric::Mahalanobis => (
+                Box::new(Mahalanobis::clone(&library.mahalanobis()?)),
+                Box::new(euclidean_distance),
+            ),
+            Metric::Forest => {
+                // with a single seed every tree is the same, so every playlist would be too
+                if seeds < 2 {
+                    bail!("The forest metric needs more than one seed song");
+                }
+                (
+                    Box::new(ForestOptions {
+                        n_trees: 1000,
+                        sample_size: seeds.min(MAX_FOREST_SAMPLE_SIZE),
+                        max_tree_depth: None,
+                        extension_level: 10,
+                    }),
+                    Box::new(euclidean_distance),
+                )
+            }
+        })
+    }
+}
+
+/// Names of the bliss features that aren't chroma features, in analysis order. The rest are
+/// named `chroma_1`, `chroma_2`, ...
+const BLISS_FEATURES: [&str; 10] = [
+    "tempo",
+    "zero_crossing_rate",
+    "spectral_centroid_mean",
+    "spectral_centroid_deviation",
+    "spectral_rolloff_mean",
+    "spectral_rolloff_deviation",
+    "spectral_flatness_mean",
+    "spectral_flatness_deviation",
+    "loudness_mean",
+    "loudness_deviation",
+];
+
+/// Distance settings in the worf config. Weights are by feature name, and features that aren't
+/// listed have a weight of 1.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DistanceConfig {
+    /// The metric the daemon uses, and `/api/playlist` unless asked for another one
+    pub metric: Metric,
+    /// How bliss features are rescaled before they're compared, same as for `metric`
+    pub normalization: Normalization,
+    /// Weights of bliss features
+    pub bliss: HashMap<String, f32>,
+    /// Weights of genre axes
+    pub genres: HashMap<String, f32>,
+    /// Find bliss playlists with a nearest neighbor index instead of sorting the whole library,
+    /// for the metrics it can search
+    pub index: bool,
+}
+
+impl Default for DistanceConfig {
+    fn default() -> Self {
+        Self {
+            metric: Metric::default(),
+            normalization: Normalization::default(),
+            bliss: HashMap::new(),
+            genres: HashMap::new(),
+            index: true,
+        }
+    }
+}
+
+impl DistanceConfig {
+    /// The distance between bliss analyses.
+    ///
+    /// May fail if a weight is for a feature that doesn't exist or is negative.
+    pub fn bliss_metric(&self) -> Result<WeightedDistance> {
+        WeightedDistance::new(&self.bliss, NUM_BLISS_FEATURES, |i| {
+            BLISS_FEATURES
+                .get(i)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|| format!("chroma_{}", i + 1 - BLISS_FEATURES.len()))
+        })
+    }
+
+    /// The distance between collapsed genre weights, with weights keyed by the names of the
+    /// genre map's `axes`.
+    ///
+    /// May fail if a weight is for an axis the genre map doesn't have or is negative.
+    pub fn genre_metric(&self, axes: &[String]) -> Result<WeightedDistance> {
+        if axes.len() != NUM_GENRE_FEATURES {
+            bail!(
+                "Genre map has {} axes, expected {NUM_GENRE_FEATURES}",
+                axes.len()
+            );
+        }
+        WeightedDistance::new(&self.genres, NUM_GENRE_FEATURES, |i| axes[i].clone())
+            .context("while reading genre axis weights, which are named after the genre map's axes")
+    }
+}
+
+/// Euclidean distance with each feature's squared difference multiplied by its weight.
+pub struct WeightedDistance {
+    weights: Array1<f32>,
+}
+
+impl WeightedDistance {
+    /// Weights for the first `len` features named by `name`, 1 for the rest. Genre weights are
+    /// padded to the length of bliss analyses, which is why there can be more features.
+    fn new(
+        weights: &HashMap<String, f32>,
+        len: usize,
+        name: impl Fn(usize) -> String,
+    ) -> Result<Self> {
+        let names: Vec<String> = (0..len).map(name).collect();
+        let mut result = Array1::ones(NUM_BLISS_FEATURES);
+        for (feature, &weight) in weights {
+            let Some(i) = names.iter().position(|name| name == feature) else {
+                bail!(
+                    "Unknown feature {feature} in distance weights, expected one of {}",
+                    names.join(", ")
+                );
+            };
+            if !weight.is_finite() {
+                bail!("Distance weight for {feature} must be a finite number, got {weight}");
+            }
+            if weight < 0.0 {
+                bail!("Distance weight for {feature} can't be negative, got {weight}");
+            }
+            result[i] = weight;
+        }
+        Ok(Self { weights: result })
+    }
+
+    pub fn weights(&self) -> &Array1<f32> {
+        &self.weights
+    }
+
+    fn distance(&self, a: &Array1<f32>, b: &Array1<f32>) -> f32 {
+        a.iter()
+            .zip(b)
+            .zip(&self.weights)
+            .map(|((a, b), weight)| weight * (a - b).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
+}
+
+impl DistanceMetricBuilder for WeightedDistance {
+    fn build<'a>(&'a self, vectors: &[Array1<f32>]) -> Box<dyn DistanceMetric + 'a> {
+        Box::new(WeightedDistanceMetric {
+            distance: self,
+            vectors: vectors.to_vec(),
+        })
+    }
+}
+
+/// Summed weighted distance to every seed, like bliss' own metrics.
+struct WeightedDistanceMetric<'a> {
+    distance: &'a WeightedDistance,
+    vectors: Vec<Array1<f32>>,
+}
+
+impl DistanceMetric for WeightedDistanceMetric<'_> {
+    fn distance(&self, vector: &Array1<f32>) -> f32 {
+        self.vectors
+            .iter()
+            .map(|other| self.distance.distance(other, vector))
+            .sum()
+    }
+}
+
+impl MPDLibrary {
+    /// Mahalanobis distance fit on the analyses of every song in the library, the first time it's
+    /// needed after each update.
+    ///
+    /// May fail if the bliss library is corrupted or too small.
+    pub fn mahalanobis(&self) -> Result<Arc<Mahalanobis>> {
+        if let Some(mahalanobis) = self.mahalanobis.read().expect("Poisoned lock").as_ref() {
+            return Ok(Arc::clone(mahalanobis));
+        }
+        let analyses: Vec<Array1<f32>> = self
+            .songs()?
+            .iter()
+            .map(|song| song.bliss_song.analysis.as_arr1())
+            .collect();
+        let mahalanobis = Arc::new(Mahalanobis::fit(&analyses)?);
+        *self.mahalanobis.write().expect("Poisoned lock") = Some(Arc::clone(&mahalanobis));
+        Ok(mahalanobis)
+    }
+}
+
+/// Euclidean distance in a space where the features are decorrelated and scaled to unit variance,
+/// so correlated features (like the chroma features) don't count several times.
+#[derive(Clone)]
+pub struct Mahalanobis {
+    inverse_covariance: Array2<f32>,
+}
+
+impl Mahalanobis {
+    /// Fit on every song's analysis, leaving out analyses with NaN or infinite features.
+    ///
+    /// May fail if there are fewer than two usable songs, or if the features are degenerate.
+    pub fn fit(analyses: &[Array1<f32>]) -> Result<Self> {
+        let analyses: Vec<_> = analyses
+            .iter()
+            .filter(|analysis| analysis.iter().all(|value| value.is_finite()))
+            .collect();
+        if analyses.len() < 2 {
+            bail!("Mahalanobis distance needs at least two songs to fit on");
+        }
+        let features = analyses[0].len();
+        let mut data = Array2::<f64>::zeros((analyses.len(), features));
+        for (mut row, analysis) in data.rows_mut().into_iter().zip(&analyses) {
+            row.assign(&analysis.mapv(f64::from));
+        }
+        let mean = data.mean_axis(ndarray::Axis(0)).expect("There are songs");
+        data -= &mean;
+        let mut covariance = data.t().dot(&data) / (analyses.len() - 1) as f64;
+        // a little regularization, so constant features don't make it singular
+        let ridge = 1e-6 * covariance.diag().sum().max(1.0) / features as f64;
+        covariance
+            .diag_mut()
+            .mapv_inplace(|variance| variance + ridge);
+        Ok(Self {
+            inverse_covariance: invert(covariance)?.mapv(|value| value as f32),
+        })
+    }
+
+    fn distance(&self, a: &Array1<f32>, b: &Array1<f32>) -> f32 {
+        let difference = a - b;
+        difference
+            .dot(&self.inverse_covariance.dot(&difference))
+            .max(0.0)
+            .sqrt()
+    }
+}
+
+/// Invert a square matrix by Gauss-Jordan elimination.
+fn invert(mut matrix: Array2<f64>) -> Result<Array2<f64>> {
+    if matrix.iter().any(|value| !value.is_finite()) {
+        bail!("Feature covariance isn't finite");
+    }
+    let n = matrix.nrows();
+    let mut inverse = Array2::<f64>::eye(n);
+    for column in 0..n {
+        let pivot = (column..n)
+            .max_by(|&a, &b| {
+                matrix[[a, column]]
+                    .abs()
+                    .total_cmp(&matrix[[b, column]].abs())
+            })
+            .expect("There are rows left");
+        if matrix[[pivot, column]].abs() < f64::EPSILON {
+            bail!("Feature covariance is singular");
+        }
+        for other in [&mut matrix, &mut inverse] {
+            for j in 0..n {
+                other.swap([column, j], [pivot, j]);
+            }
+        }
+        let scale = matrix[[column, column]];
+        matrix.row_mut(column).mapv_inplace(|value| value / scale);
+        inverse.row_mut(column).mapv_inplace(|value| value / scale);
+        let pivot_row = matrix.row(column).to_owned();
+        let inverse_pivot_row = inverse.row(column).to_owned();
+        for row in (0..n).filter(|&row| row != column) {
+            let factor = matrix[[row, column]];
+            matrix.row_mut(row).scaled_add(-factor, &pivot_row);
+            inverse.row_mut(row).scaled_add(-factor, &inverse_pivot_row);
+        }
+    }
+    Ok(inverse)
+}
+
+impl DistanceMetricBuilder for Mahalanobis {
+    fn build<'a>(&'a self, vectors: &[Array1<f32>]) -> Box<dyn DistanceMetric + 'a> {
+        Box::new(MahalanobisMetric {
+            distance: self,
+            vectors: vectors.to_vec(),
+        })
+    }
+}
+
+struct MahalanobisMetric<'a> {
+    distance: &'a Mahalanobis,
+    vectors: Vec<Array1<f32>>,
+}
+
+impl DistanceMetric for MahalanobisMetric<'_> {
+    fn distance(&self, vector: &Array1<f32>) -> f32 {
+        self.vectors
+            .iter()
+            .map(|other| self.distance.distance(other, vector))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::{arr1, arr2};
+
+    fn assert_close(a: &Array2<f64>, b: &Array2<f64>) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9),
+            "{a} isn't {b}"
+        );
+    }
+
+    #[test]
+    fn invert_matrices() {
+        assert_close(
+            &invert(arr2(&[[4.0, 7.0], [2.0, 6.0]])).unwrap(),
+            &arr2(&[[0.6, -0.7], [-0.2, 0.4]]),
+        );
+        // needs a row swap
+        let swap = arr2(&[[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 2.0]]);
+        assert_close(&invert(swap.clone()).unwrap().dot(&swap), &Array2::eye(3));
+        assert!(invert(arr2(&[[1.0, 2.0], [2.0, 4.0]])).is_err());
+        assert!(invert(arr2(&[[1.0, f64::NAN], [0.0, 1.0]])).is_err());
+    }
+
+    #[test]
+    fn mahalanobis_scales_by_variance() {
+        let analyses = [[2.0, 0.0], [-2.0, 0.0], [0.0, 1.0], [0.0, -1.0]].map(|a| arr1(&a));
+        let mahalanobis = Mahalanobis::fit(&analyses).unwrap();
+        let origin = arr1(&[0.0, 0.0]);
+        // the sample variances are 8/3 along the first feature and 2/3 along the second
+        let along_first = mahalanobis.distance(&origin, &arr1(&[1.0, 0.0]));
+        let along_second = mahalanobis.distance(&origin, &arr1(&[0.0, 1.0]));
+        assert!((along_first - (3.0f32 / 8.0).sqrt()).abs() < 1e-4);
+        assert!((along_second - 1.5f32.sqrt()).abs() < 1e-4);
+        assert!(Mahalanobis::fit(&analyses[..1]).is_err());
+    }
+
+    #[test]
+    fn mahalanobis_skips_non_finite_analyses() {
+        let analyses = [
+            [2.0, 0.0],
+            [f32::NAN, 3.0],
+            [-2.0, 0.0],
+            [0.0, 1.0],
+            [0.0, f32::INFINITY],
+            [0.0, -1.0],
+        ]
+        .map(|a| arr1(&a));
+        let with_nan = Mahalanobis::fit(&analyses).unwrap();
+        let without = Mahalanobis::fit(&[0, 2, 3, 5].map(|i| analyses[i].clone())).unwrap();
+        assert_eq!(with_nan.inverse_covariance, without.inverse_covariance);
+        assert!(Mahalanobis::fit(&analyses[..2]).is_err());
+    }
+
+    #[test]
+    fn invalid_distance_weights() {
+        let error = |feature: &str, weight: f32| {
+            DistanceConfig {
+                bliss: HashMap::from([(feature.to_string(), weight)]),
+                ..Default::default()
+            }
+            .bliss_metric()
+            .err()
+            .map(|e| e.to_string())
+        };
+        assert_eq!(error("tempo", 2.0), None);
+        assert!(error("tempo", -1.0).unwrap().contains("can't be negative"));
+        assert!(error("tempo", f32::NAN).unwrap().contains("finite"));
+        assert!(error("tempo", f32::INFINITY).unwrap().contains("finite"));
+        assert!(error("tempi", 1.0).unwrap().contains("Unknown feature"));
+    }
+}
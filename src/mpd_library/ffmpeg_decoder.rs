@@ -9,6 +9,7 @@ use ::log::warn;
 use bliss_audio::decoder::{Decoder, PreAnalyzedSong};
 use bliss_audio::{BlissError, BlissResult};
 use ffmpeg_next::codec::threading::{Config, Type as ThreadingType};
+use ffmpeg_next::format::stream::Disposition;
 use ffmpeg_next::util::channel_layout::ChannelLayout;
 use ffmpeg_next::util::error::EINVAL;
 use ffmpeg_next::util::error::Error;
@@ -135,6 +136,39 @@ impl FFmpegDecoder {
             .collect();
         sample_array.extend_from_slice(&f32_frame);
     }
+
+    /// The cover embedded in a song file (an attached picture stream), if it has one.
+    pub fn embedded_picture(path: &Path) -> BlissResult<Option<Vec<u8>>> {
+        ffmpeg_next::init().map_err(|e| {
+            BlissError::DecodingError(format!(
+                "ffmpeg init error while reading file '{}': {:?}.",
+                path.display(),
+                e
+            ))
+        })?;
+        log::set_level(Level::Quiet);
+        let mut ictx = ffmpeg_next::format::input(&path).map_err(|e| {
+            BlissError::DecodingError(format!(
+                "while opening format for file '{}': {:?}.",
+                path.display(),
+                e
+            ))
+        })?;
+        let Some(picture_stream) = ictx
+            .streams()
+            .find(|s| s.disposition().contains(Disposition::ATTACHED_PIC))
+            .map(|s| s.index())
+        else {
+            return Ok(None);
+        };
+        // the picture is the one and only packet of its stream
+        for (s, packet) in ictx.packets() {
+            if s.index() == picture_stream {
+                return Ok(packet.data().map(<[u8]>::to_vec));
+            }
+        }
+        Ok(None)
+    }
 }
 
 impl Decoder for FFmpegDecoder {
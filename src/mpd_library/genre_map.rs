@@ -0,0 +1,335 @@
+//! The genre map file, mapping genre names to their position along each genre axis.
+//!
+//! The current format names its axes and their ranges, so every axis can be scaled to 0-1 and
+//! the everynoise pixel coordinates don't outweigh the colors:
+//! ```json
+//! {
+//!     "version": 1,
+//!     "axes": [{ "name": "mechanical", "min": 0, "max": 22000 }, ...],
+//!     "genres": { "techno": [1234, 567, 200, 100, 50], ... }
+//! }
+//! ```
+//! A bare map of genre names to weights (the old format) is still read, with axis ranges taken
+//! from the weights themselves.
+
+use crate::NUM_GENRE_FEATURES;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde::de::{Deserializer, MapAccess, Visitor};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+const GENRE_MAP_VERSION: u32 = 1;
+/// How many problems to list when a genre map doesn't validate.
+const MAX_REPORTED_ERRORS: usize = 10;
+
+/// One of the genre axes, with the range of values along it.
+#[derive(Deserialize, Debug)]
+struct Axis {
+    name: String,
+    min: f32,
+    max: f32,
+}
+
+/// Names of the genre axes, in the order the everynoise script in the README writes them (top,
+/// left, red, green, blue). Maps in the old format don't name their axes, so they get these.
+pub const GENRE_AXES: [&str; NUM_GENRE_FEATURES] = [
+    "mechanical",
+    "spiky",
+    "energy",
+    "dynamic_variation",
+    "instrumentalness",
+];
+
+/// Just enough of a genre map to tell the formats apart.
+#[derive(Deserialize)]
+struct Probe {
+    version: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct VersionedGenreMap {
+    axes: Vec<Axis>,
+    genres: GenreEntries,
+}
+
+/// Genre entries in file order, keeping duplicate names so they can be reported.
+struct GenreEntries(Vec<(String, Vec<f32>)>);
+
+impl<'de> Deserialize<'de> for GenreEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = GenreEntries;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a map of genre names to arrays of weights")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::with_capacity(map.size_hint().unwrap_or_default());
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(GenreEntries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+/// Read and validate a genre map, returning the names of its axes and its genres, with every axis
+/// scaled to 0-1. Genres are in file order.
+///
+/// May fail if the file can't be read, is in neither format, or doesn't validate.
+pub fn load(path: &Path) -> Result<(Vec<String>, Vec<(String, [f32; NUM_GENRE_FEATURES])>)> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("while reading genre map {}", path.display()))?;
+    let probe: Probe = serde_json::from_str(&contents).unwrap_or(Probe { version: None });
+    let (axes, entries) = match probe.version {
+        Some(version) if version > GENRE_MAP_VERSION => bail!(
+            "Genre map {} is version {version}, but this version of worf only reads up to version {GENRE_MAP_VERSION}",
+            path.display()
+        ),
+        Some(_) => {
+            let map: VersionedGenreMap =
+                serde_json::from_str(&contents).context("while parsing genre map")?;
+            (Some(map.axes), map.genres)
+        }
+        None => (
+            None,
+            serde_json::from_str(&contents).context("while parsing genre map")?,
+        ),
+    };
+    let weights = validate(entries)?;
+    let axes = match axes {
+        Some(axes) => axes,
+        None => legacy_axes(&weights),
+    };
+    if axes.len() != NUM_GENRE_FEATURES {
+        bail!(
+            "Genre map has {} axes, expected {NUM_GENRE_FEATURES}",
+            axes.len()
+        );
+    }
+    if let Some(axis) = axes
+        .iter()
+        .find(|axis| !axis.min.is_finite() || !axis.max.is_finite() || axis.min >= axis.max)
+    {
+        bail!(
+            "Genre axis {} has an invalid range {} to {}",
+            axis.name,
+            axis.min,
+            axis.max
+        );
+    }
+    check_ranges(&weights, &axes)?;
+    let genres = weights
+        .into_iter()
+        .map(|(genre, weights)| {
+            let mut scaled = [0.0; NUM_GENRE_FEATURES];
+            for ((value, weight), axis) in scaled.iter_mut().zip(weights).zip(&axes) {
+                *value = (weight - axis.min) / (axis.max - axis.min);
+            }
+            (genre, scaled)
+        })
+        .collect();
+    Ok((axes.into_iter().map(|axis| axis.name).collect(), genres))
+}
+
+/// Check every genre has a finite weight for every axis and appears only once.
+fn validate(entries: GenreEntries) -> Result<Vec<(String, [f32; NUM_GENRE_FEATURES])>> {
+    let mut errors = vec![];
+    let mut seen = HashSet::new();
+    let mut weights = Vec::with_capacity(entries.0.len());
+    for (genre, values) in entries.0 {
+        if !seen.insert(genre.clone()) {
+            errors.push(format!("{genre} appears more than once"));
+        } else if values.iter().any(|value| !value.is_finite()) {
+            errors.push(format!("{genre} has a weight that isn't a number"));
+        } else {
+            match <[f32; NUM_GENRE_FEATURES]>::try_from(values.as_slice()) {
+                Ok(values) => weights.push((genre, values)),
+                Err(_) => errors.push(format!(
+                    "{genre} has {} weights, expected {NUM_GENRE_FEATURES}",
+                    values.len()
+                )),
+            }
+        }
+    }
+    report(errors)?;
+    Ok(weights)
+}
+
+/// Check every weight is within its axis' declared range.
+fn check_ranges(weights: &[(String, [f32; NUM_GENRE_FEATURES])], axes: &[Axis]) -> Result<()> {
+    let errors = weights
+        .iter()
+        .flat_map(|(genre, values)| {
+            values
+                .iter()
+                .zip(axes)
+                .filter(|(value, axis)| **value < axis.min || **value > axis.max)
+                .map(move |(value, axis)| {
+                    format!(
+                        "{genre} has {value} along {}, outside {} to {}",
+                        axis.name, axis.min, axis.max
+                    )
+                })
+        })
+        .collect();
+    report(errors)
+}
+
+/// Fail with the first few `errors`, if there are any.
+fn report(mut errors: Vec<String>) -> Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let more = errors.len().saturating_sub(MAX_REPORTED_ERRORS);
+    errors.truncate(MAX_REPORTED_ERRORS);
+    if more > 0 {
+        errors.push(format!("and {more} more"));
+    }
+    bail!("Invalid genre map: {}", errors.join("; "));
+}
+
+/// Axes for a map in the old format, spanning the weights along each of them.
+fn legacy_axes(weights: &[(String, [f32; NUM_GENRE_FEATURES])]) -> Vec<Axis> {
+    GENRE_AXES
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let values = weights.iter().map(|(_, weights)| weights[i]);
+            let min = values.clone().fold(f32::INFINITY, f32::min);
+            let max = values.fold(f32::NEG_INFINITY, f32::max);
+            let (min, max) = match (min.is_finite(), max > min) {
+                (true, true) => (min, max),
+                // a flat axis still needs a range to scale by
+                (true, false) => (min, min + 1.0),
+                (false, _) => (0.0, 1.0),
+            };
+            Axis {
+                name: name.to_string(),
+                min,
+                max,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Genres = Vec<(String, [f32; NUM_GENRE_FEATURES])>;
+
+    /// Load `contents` as a genre map.
+    fn load_map(name: &str, contents: &str) -> Result<(Vec<String>, Genres)> {
+        let dir = std::env::temp_dir().join(format!("worf-genre-map-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{name}.json"));
+        fs::write(&path, contents)?;
+        let loaded = load(&path);
+        fs::remove_file(&path)?;
+        loaded
+    }
+
+    fn error(name: &str, contents: &str) -> String {
+        match load_map(name, contents) {
+            Ok(_) => panic!("{name} should have been rejected"),
+            Err(e) => format!("{e:#}"),
+        }
+    }
+
+    #[test]
+    fn versioned_maps_are_scaled_by_axis() -> Result<()> {
+        let (axes, genres) = load_map(
+            "versioned",
+            r#"{
+                "version": 1,
+                "axes": [
+                    { "name": "a", "min": 0, "max": 10 },
+                    { "name": "b", "min": -1, "max": 1 },
+                    { "name": "c", "min": 0, "max": 255 },
+                    { "name": "d", "min": 0, "max": 255 },
+                    { "name": "e", "min": 0, "max": 255 }
+                ],
+                "genres": { "techno": [5, 1, 0, 255, 51], "ambient": [0, -1, 255, 0, 0] }
+            }"#,
+        )?;
+        assert_eq!(axes, ["a", "b", "c", "d", "e"]);
+        assert_eq!(
+            genres,
+            [
+                ("techno".to_string(), [0.5, 1.0, 0.0, 1.0, 0.2]),
+                ("ambient".to_string(), [0.0, 0.0, 1.0, 0.0, 0.0]),
+            ]
+        );
+        assert!(
+            error("future", r#"{"version": 2, "axes": [], "genres": {}}"#).contains("version 2")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_maps_span_their_weights() -> Result<()> {
+        let (axes, genres) = load_map(
+            "legacy",
+            r#"{"techno": [10, 0, 3, 7, 1], "ambient": [20, 4, 3, 5, 1]}"#,
+        )?;
+        assert_eq!(axes, GENRE_AXES);
+        assert_eq!(
+            genres,
+            [
+                ("techno".to_string(), [0.0, 0.0, 0.0, 1.0, 0.0]),
+                ("ambient".to_string(), [1.0, 1.0, 0.0, 0.0, 0.0]),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_genres_are_rejected() {
+        let duplicate = error(
+            "duplicate",
+            r#"{"techno": [1, 2, 3, 4, 5], "techno": [1, 2, 3, 4, 5]}"#,
+        );
+        assert!(
+            duplicate.contains("techno appears more than once"),
+            "{duplicate}"
+        );
+        let arity = error("arity", r#"{"techno": [1, 2, 3]}"#);
+        assert!(arity.contains("techno has 3 weights"), "{arity}");
+        // JSON has no NaN, but numbers too large for an f32 end up infinite
+        let infinite = error("infinite", r#"{"techno": [1e300, 2, 3, 4, 5]}"#);
+        assert!(infinite.contains("isn't a number"), "{infinite}");
+        let axes = r#"[
+            { "name": "a", "min": 0, "max": 1 },
+            { "name": "b", "min": 0, "max": 1 },
+            { "name": "c", "min": 0, "max": 1 },
+            { "name": "d", "min": 0, "max": 1 },
+            { "name": "e", "min": 0, "max": 1 }
+        ]"#;
+        let range = error(
+            "range",
+            &format!(
+                r#"{{"version": 1, "axes": {axes}, "genres": {{"techno": [0, 0.5, 2, 1, -1]}}}}"#
+            ),
+        );
+        assert!(
+            range.contains("techno has 2 along c, outside 0 to 1"),
+            "{range}"
+        );
+        assert!(range.contains("techno has -1 along e"), "{range}");
+        let arity = error(
+            "axes",
+            r#"{"version": 1, "axes": [{ "name": "a", "min": 0, "max": 1 }], "genres": {}}"#,
+        );
+        assert!(arity.contains("has 1 axes"), "{arity}");
+    }
+}
@@ -0,0 +1,607 @@
+//! How a song's genre tags are turned into genre features.
+//!
+//! Genre names are normalized (case, whitespace and hyphens) both in the genre map and in tags,
+//! so "Hip-Hop" and "hip hop" are the same genre.
+
+use super::knn::{self, KnnIndex, Nearest, SearchSpace};
+use super::{BlissSong, GenreWeights, MPDLibrary, TrackWeights, genre_map};
+use crate::{NUM_BLISS_FEATURES, NUM_GENRE_FEATURES};
+use anyhow::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::Arc;
+
+/// How to combine the weights of every genre a song is tagged with. All but `Concatenate` give one
+/// value per genre axis, so songs with different numbers of genres can still be compared.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GenreCombination {
+    /// Put each genre's weights one after the other, or each genre's average over its axes if
+    /// they don't all fit
+    #[default]
+    Concatenate,
+    /// Average each axis over all genres
+    Mean,
+    /// Average each axis, weighting earlier tags more (the first tag counts twice as much as the
+    /// second, three times as much as the third, ...)
+    WeightedMean,
+    /// Take the largest value of each axis
+    Max,
+    /// Only use the first genre found in the genre map
+    Primary,
+}
+
+impl GenreCombination {
+    /// Combine genre weights, given in tag order, into `N` features padded with zeros. All zero if
+    /// there are none.
+    pub fn combine<const N: usize>(&self, genres: &[[f32; NUM_GENRE_FEATURES]]) -> [f32; N] {
+        let mut result = [0.0; N];
+        if genres.is_empty() {
+            return result;
+        }
+        let combined: Vec<f32> = match self {
+            Self::Concatenate if genres.len() * NUM_GENRE_FEATURES <= N => {
+                genres.iter().flatten().copied().collect()
+            }
+            Self::Concatenate => genres
+                .iter()
+                .map(|genre| genre.iter().sum::<f32>() / NUM_GENRE_FEATURES as f32)
+                .collect(),
+            Self::Mean | Self::WeightedMean => {
+                let weight = |i: usize| match self {
+                    Self::WeightedMean => 1.0 / (i + 1) as f32,
+                    _ => 1.0,
+                };
+                let total: f32 = (0..genres.len()).map(weight).sum();
+                let mut axes = [0.0; NUM_GENRE_FEATURES];
+                for (i, genre) in genres.iter().enumerate() {
+                    for (axis, value) in axes.iter_mut().zip(genre) {
+                        *axis += value * weight(i) / total;
+                    }
+                }
+                axes.to_vec()
+            }
+            Self::Max => {
+                let mut axes = genres[0];
+                for genre in &genres[1..] {
+                    for (axis, value) in axes.iter_mut().zip(genre) {
+                        *axis = axis.max(*value);
+                    }
+                }
+                axes.to_vec()
+            }
+            Self::Primary => genres[0].to_vec(),
+        };
+        // more genres than features are cut off
+        let len = combined.len().min(N);
+        result[..len].copy_from_slice(&combined[..len]);
+        result
+    }
+}
+
+/// Genre settings in the worf config.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GenreConfig {
+    /// How to combine the weights of songs tagged with several genres
+    pub combination: GenreCombination,
+    /// What separates genres in a genre tag. Multi-valued tags are read joined with `;`.
+    pub delimiters: Vec<String>,
+    /// Other names for genres in the genre map, e.g. `"rap": "hip hop"`
+    pub aliases: HashMap<String, String>,
+    /// Give genres missing from the genre map the weights of the known genres named in them,
+    /// e.g. "dark techno" gets the weights of "techno"
+    pub match_words: bool,
+    /// Give songs without known genres the average genre weights of this many of their closest
+    /// songs by bliss features. Off (0) by default.
+    pub infer_neighbors: usize,
+}
+
+impl Default for GenreConfig {
+    fn default() -> Self {
+        Self {
+            combination: GenreCombination::default(),
+            delimiters: vec![",".into(), ";".into(), "/".into()],
+            aliases: HashMap::new(),
+            match_words: true,
+            infer_neighbors: 0,
+        }
+    }
+}
+
+impl GenreConfig {
+    /// Split a genre tag into normalized genre names.
+    pub fn split(&self, genres: &str) -> Vec<String> {
+        let mut names = vec![genres.to_string()];
+        for delimiter in self
+            .delimiters
+            .iter()
+            .filter(|delimiter| !delimiter.is_empty())
+        {
+            names = names
+                .iter()
+                .flat_map(|name| name.split(delimiter.as_str()))
+                .map(String::from)
+                .collect();
+        }
+        names
+            .iter()
+            .map(|name| normalize(name))
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    /// Load a genre map, with names normalized and aliases added as genres of their own. Also
+    /// returns the names of the map's axes.
+    ///
+    /// May fail if the file doesn't exist or isn't a valid genre map.
+    pub fn load_genre_weights(&self, path: &Path) -> Result<(Vec<String>, GenreWeights)> {
+        let (axes, genres) = genre_map::load(path)?;
+        let mut genre_weights = GenreWeights::new();
+        for (name, weights) in genres {
+            let normalized = normalize(&name);
+            if genre_weights.contains_key(&normalized) {
+                println!("Genre {name} is the same as another genre once normalized, ignoring it");
+                continue;
+            }
+            genre_weights.insert(normalized, weights);
+        }
+        for (alias, genre) in &self.aliases {
+            match genre_weights.get(&normalize(genre)) {
+                Some(&weights) => {
+                    genre_weights.insert(normalize(alias), weights);
+                }
+                None => println!("Alias {alias} is for {genre}, which isn't in the genre map"),
+            }
+        }
+        Ok((axes, genre_weights))
+    }
+
+    /// Add genres from the tags of `songs` that aren't in the genre map, with the average weights
+    /// of the known genres with the most words, out of those all of whose words they contain.
+    /// Returns how many genres were added.
+    pub fn add_word_matches<'a>(
+        &self,
+        genre_weights: &mut GenreWeights,
+        songs: impl IntoIterator<Item = &'a BlissSong>,
+    ) -> usize {
+        if !self.match_words {
+            return 0;
+        }
+        let mut by_word: HashMap<&str, Vec<&str>> = HashMap::new();
+        for genre in genre_weights.keys() {
+            for word in genre.split(' ') {
+                by_word.entry(word).or_default().push(genre);
+            }
+        }
+        let unknown: Vec<String> = songs
+            .into_iter()
+            .filter_map(|song| song.bliss_song.genre.as_deref())
+            .flat_map(|genres| self.split(genres))
+            .filter(|genre| !genre_weights.contains_key(genre))
+            .unique()
+            .collect();
+        let mut matches = vec![];
+        for genre in unknown {
+            let words: Vec<&str> = genre.split(' ').collect();
+            let candidates: Vec<&str> = words
+                .iter()
+                .filter_map(|word| by_word.get(word))
+                .flatten()
+                .copied()
+                .unique()
+                .filter(|known| known.split(' ').all(|word| words.contains(&word)))
+                .collect();
+            let Some(most_words) = candidates
+                .iter()
+                .map(|known| known.split(' ').count())
+                .max()
+            else {
+                continue;
+            };
+            let closest: Vec<[f32; NUM_GENRE_FEATURES]> = candidates
+                .into_iter()
+                .filter(|known| known.split(' ').count() == most_words)
+                .map(|known| genre_weights[known])
+                .collect();
+            matches.push((
+                genre,
+                GenreCombination::Mean.combine::<NUM_GENRE_FEATURES>(&closest),
+            ));
+        }
+        let added = matches.len();
+        genre_weights.extend(matches);
+        added
+    }
+}
+
+/// Give every song in `untagged` the average track weights of its `neighbors` closest songs in
+/// `track_weights` by euclidean distance between bliss features, found with a nearest neighbor
+/// index over the tagged songs. Songs with NaN or infinite features are neither given weights nor
+/// used as neighbors.
+pub fn infer_from_neighbors(
+    track_weights: &mut TrackWeights,
+    tagged: &[&BlissSong],
+    untagged: &[&BlissSong],
+    neighbors: usize,
+) {
+    if tagged.is_empty() || neighbors == 0 {
+        return;
+    }
+    let tagged: Vec<BlissSong> = tagged
+        .iter()
+        .filter(|song| track_weights.contains_key(&song.bliss_song.path))
+        .map(|&song| song.clone())
+        .collect();
+    let index = Arc::new(KnnIndex::new(Arc::new(tagged)));
+    let mut inferred = vec![];
+    for &song in untagged.iter().filter(|song| knn::indexable(song)) {
+        let closest: Vec<&[f32; NUM_BLISS_FEATURES]> = Nearest::new(
+            Arc::clone(&index),
+            SearchSpace::euclidean(),
+            slice::from_ref(song),
+        )
+        .take(neighbors)
+        .filter_map(|neighbor| track_weights.get(&neighbor.bliss_song.path))
+        .collect();
+        if closest.is_empty() {
+            continue;
+        }
+        let mut weights = [0.0; NUM_BLISS_FEATURES];
+        for neighbor_weights in &closest {
+            for (axis, value) in weights.iter_mut().zip(neighbor_weights.iter()) {
+                *axis += value / closest.len() as f32;
+            }
+        }
+        inferred.push((song.bliss_song.path.clone(), weights));
+    }
+    track_weights.extend(inferred);
+}
+
+/// Lowercase, with hyphens and underscores as spaces and runs of whitespace collapsed.
+pub fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .replace(['-', '_'], " ")
+        .split_whitespace()
+        .join(" ")
+}
+
+/// How many songs are tagged with a genre.
+#[derive(Serialize)]
+pub struct GenreCount {
+    pub genre: String,
+    pub songs: usize,
+}
+
+/// How well the genre map covers the library.
+#[derive(Serialize)]
+pub struct GenreStats {
+    pub songs: usize,
+    /// Songs without a genre tag
+    pub untagged: usize,
+    /// Songs with a genre tag, none of whose genres are in the genre map
+    pub all_unknown: usize,
+    /// Genres that aren't in the genre map, most common first
+    pub unknown_genres: Vec<GenreCount>,
+    /// Genres that aren't in the genre map but were matched to genres in it by their words, and
+    /// are counted as known
+    pub word_matches: usize,
+    /// Genres that are in the genre map, most common first
+    pub known_genres: Vec<GenreCount>,
+}
+
+impl GenreStats {
+    pub fn collect<'a>(
+        genre_weights: &GenreWeights,
+        genre_config: &GenreConfig,
+        songs: impl IntoIterator<Item = &'a BlissSong>,
+    ) -> Self {
+        let mut stats = Self {
+            songs: 0,
+            untagged: 0,
+            all_unknown: 0,
+            unknown_genres: vec![],
+            word_matches: 0,
+            known_genres: vec![],
+        };
+        let mut unknown_counts: HashMap<String, usize> = HashMap::new();
+        let mut known_counts: HashMap<String, usize> = HashMap::new();
+        for song in songs {
+            stats.songs += 1;
+            let genres = genre_config.split(song.bliss_song.genre.as_deref().unwrap_or_default());
+            if genres.is_empty() {
+                stats.untagged += 1;
+                continue;
+            }
+            let (known, unknown): (Vec<_>, Vec<_>) = genres
+                .into_iter()
+                .unique()
+                .partition(|genre| genre_weights.contains_key(genre));
+            if known.is_empty() {
+                stats.all_unknown += 1;
+            }
+            for genre in known {
+                *known_counts.entry(genre).or_default() += 1;
+            }
+            for genre in unknown {
+                *unknown_counts.entry(genre).or_default() += 1;
+            }
+        }
+        let by_count = |counts: HashMap<String, usize>| {
+            counts
+                .into_iter()
+                .sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)))
+                .map(|(genre, songs)| GenreCount { genre, songs })
+                .collect()
+        };
+        stats.unknown_genres = by_count(unknown_counts);
+        stats.known_genres = by_count(known_counts);
+        stats
+    }
+}
+
+impl MPDLibrary {
+    /// Load the genre map and work out how much of the library it covers, with unknown genres
+    /// matched by their words like when queueing.
+    ///
+    /// May fail if the genre map can't be loaded or if the bliss library is corrupted.
+    pub fn genre_stats(&self, genres_path: Option<PathBuf>) -> Result<GenreStats> {
+        let genre_config = &self.bliss.config.genres;
+        let (_, mut genre_weights) =
+            genre_config.load_genre_weights(&self.genres_path(genres_path))?;
+        let songs = self.songs()?;
+        let word_matches = genre_config.add_word_matches(&mut genre_weights, songs.iter());
+        Ok(GenreStats {
+            word_matches,
+            ..GenreStats::collect(&genre_weights, genre_config, songs.iter())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mpd_library::genre_map::GENRE_AXES;
+    use crate::mpd_library::{ExtraInfo, collapse_genres_pad_to};
+    use bliss_audio::{Analysis, FeaturesVersion, Song as BareBlissSong};
+    use std::fs;
+
+    fn song(name: &str, value: f32, genre: Option<&str>) -> BlissSong {
+        BlissSong {
+            bliss_song: BareBlissSong {
+                path: PathBuf::from(name),
+                analysis: Analysis::new(vec![value; NUM_BLISS_FEATURES], FeaturesVersion::LATEST)
+                    .expect("Analysis has every feature"),
+                genre: genre.map(String::from),
+                ..Default::default()
+            },
+            extra_info: ExtraInfo::default(),
+        }
+    }
+
+    const ROCK: [f32; NUM_GENRE_FEATURES] = [1.0, 2.0, 3.0, 4.0, 5.0];
+    const JAZZ: [f32; NUM_GENRE_FEATURES] = [5.0, 0.0, 1.0, 2.0, 2.0];
+
+    fn assert_close<const N: usize>(actual: [f32; N], expected: [f32; N]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn combine_per_axis() {
+        let genres = [ROCK, JAZZ];
+        assert_close(
+            GenreCombination::Mean.combine::<5>(&genres),
+            [3.0, 1.0, 2.0, 3.0, 3.5],
+        );
+        // the first tag counts 1, the second 1/2
+        assert_close(
+            GenreCombination::WeightedMean.combine::<5>(&genres),
+            [7.0 / 3.0, 4.0 / 3.0, 7.0 / 3.0, 10.0 / 3.0, 4.0],
+        );
+        assert_close(
+            GenreCombination::Max.combine::<5>(&genres),
+            [5.0, 2.0, 3.0, 4.0, 5.0],
+        );
+        assert_close(GenreCombination::Primary.combine::<5>(&genres), ROCK);
+        // padded to the requested length
+        assert_close(
+            GenreCombination::Primary.combine::<7>(&genres),
+            [1.0, 2.0, 3.0, 4.0, 5.0, 0.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn combine_concatenates_by_default() {
+        assert_eq!(GenreCombination::default(), GenreCombination::Concatenate);
+        let combination = GenreCombination::Concatenate;
+        assert_close(
+            combination.combine::<12>(&[ROCK, JAZZ]),
+            [1.0, 2.0, 3.0, 4.0, 5.0, 5.0, 0.0, 1.0, 2.0, 2.0, 0.0, 0.0],
+        );
+        // too many genres to fit, so each is averaged
+        assert_close(
+            combination.combine::<6>(&[ROCK, JAZZ]),
+            [3.0, 2.0, 0.0, 0.0, 0.0, 0.0],
+        );
+        // and cut off if even that doesn't fit
+        assert_close(combination.combine::<1>(&[ROCK, JAZZ]), [3.0]);
+    }
+
+    #[test]
+    fn combine_nothing() {
+        for combination in [
+            GenreCombination::Concatenate,
+            GenreCombination::Mean,
+            GenreCombination::WeightedMean,
+            GenreCombination::Max,
+            GenreCombination::Primary,
+        ] {
+            assert_eq!(combination.combine::<7>(&[]), [0.0; 7]);
+        }
+    }
+
+    #[test]
+    fn unknown_genres_are_ignored() {
+        let genre_weights = GenreWeights::from([("rock".to_string(), ROCK)]);
+        let config = GenreConfig {
+            combination: GenreCombination::Mean,
+            ..Default::default()
+        };
+        let collapse =
+            |genres: &str| collapse_genres_pad_to::<7>(&genre_weights, &config, genres.into());
+        assert_close(collapse("Rock, polka"), [1.0, 2.0, 3.0, 4.0, 5.0, 0.0, 0.0]);
+        assert_eq!(collapse("polka"), [0.0; 7]);
+        assert_eq!(collapse(""), [0.0; 7]);
+    }
+
+    #[test]
+    fn normalize_names() {
+        assert_eq!(normalize("  Hip-Hop "), "hip hop");
+        assert_eq!(normalize("Drum\tand   Bass"), "drum and bass");
+        assert_eq!(normalize("lo_fi"), "lo fi");
+        assert_eq!(normalize(" - "), "");
+    }
+
+    #[test]
+    fn split_on_every_delimiter() {
+        let config = GenreConfig::default();
+        assert_eq!(
+            config.split("Rock, Hip-Hop;trap / Drum And Bass;;"),
+            ["rock", "hip hop", "trap", "drum and bass"]
+        );
+        assert!(config.split("").is_empty());
+        let commas = GenreConfig {
+            delimiters: vec![",".into(), "".into()],
+            ..Default::default()
+        };
+        assert_eq!(commas.split("rock;pop, jazz"), ["rock;pop", "jazz"]);
+    }
+
+    #[test]
+    fn aliases_resolve_to_normalized_genres() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("worf-aliases-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("genres.json");
+        fs::write(
+            &path,
+            r#"{"Hip Hop": [0, 0, 0, 0, 0], "hip-hop": [5, 5, 5, 5, 5], "Techno": [10, 10, 10, 10, 10]}"#,
+        )?;
+        let config = GenreConfig {
+            aliases: HashMap::from([
+                ("Rap".to_string(), "HIP-HOP".to_string()),
+                ("ghost".to_string(), "missing".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let loaded = config.load_genre_weights(&path);
+        fs::remove_dir_all(&dir)?;
+        let (axes, genre_weights) = loaded?;
+
+        assert_eq!(axes, GENRE_AXES);
+        // the second spelling of hip hop is ignored
+        assert_eq!(genre_weights["hip hop"], [0.0; NUM_GENRE_FEATURES]);
+        assert_eq!(genre_weights["techno"], [1.0; NUM_GENRE_FEATURES]);
+        assert_eq!(genre_weights["rap"], genre_weights["hip hop"]);
+        assert!(!genre_weights.contains_key("ghost"));
+        assert_eq!(genre_weights.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn word_matches() {
+        let mut genre_weights = GenreWeights::from([
+            ("techno".to_string(), ROCK),
+            ("dark ambient".to_string(), JAZZ),
+            ("ambient".to_string(), [0.0; NUM_GENRE_FEATURES]),
+        ]);
+        let songs = [
+            song("a.flac", 0.0, Some("Dark-Techno")),
+            song("b.flac", 0.0, Some("dark ambient techno; polka")),
+            song("c.flac", 0.0, Some("techno")),
+        ];
+        let disabled = GenreConfig {
+            match_words: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            disabled.add_word_matches(&mut genre_weights.clone(), &songs),
+            0
+        );
+        let added = GenreConfig::default().add_word_matches(&mut genre_weights, &songs);
+        assert_eq!(added, 2);
+        assert_eq!(genre_weights["dark techno"], ROCK);
+        // only the known genres with the most words count
+        assert_eq!(genre_weights["dark ambient techno"], JAZZ);
+        assert!(!genre_weights.contains_key("polka"));
+    }
+
+    #[test]
+    fn inference_averages_closest_tagged_songs() {
+        let tagged = [
+            song("zero.flac", 0.0, Some("a")),
+            song("one.flac", 1.0, Some("b")),
+            song("ten.flac", 10.0, Some("c")),
+            song("nan.flac", f32::NAN, Some("d")),
+        ];
+        let untagged = [
+            song("near zero.flac", 0.2, None),
+            song("also nan.flac", f32::NAN, None),
+        ];
+        let mut track_weights: TrackWeights = tagged
+            .iter()
+            .enumerate()
+            .map(|(i, song)| (song.bliss_song.path.clone(), [i as f32; NUM_BLISS_FEATURES]))
+            .collect();
+        infer_from_neighbors(
+            &mut track_weights,
+            &tagged.iter().collect::<Vec<_>>(),
+            &untagged.iter().collect::<Vec<_>>(),
+            2,
+        );
+        assert_eq!(
+            track_weights[Path::new("near zero.flac")],
+            [0.5; NUM_BLISS_FEATURES]
+        );
+        assert!(!track_weights.contains_key(Path::new("also nan.flac")));
+        assert_eq!(track_weights.len(), tagged.len() + 1);
+    }
+
+    #[test]
+    fn stats_count_unknown_genres() {
+        let genre_weights =
+            GenreWeights::from([("rock".to_string(), ROCK), ("jazz".to_string(), JAZZ)]);
+        let songs = [
+            song("a.flac", 0.0, Some("Rock; polka")),
+            song("b.flac", 0.0, Some("polka, Sea Shanty")),
+            song("c.flac", 0.0, Some("rock, ROCK")),
+            song("d.flac", 0.0, Some("jazz")),
+            song("e.flac", 0.0, None),
+            song("f.flac", 0.0, Some(" ; ")),
+        ];
+        let stats = GenreStats::collect(&genre_weights, &GenreConfig::default(), &songs);
+        let counts = |genres: &[GenreCount]| {
+            genres
+                .iter()
+                .map(|count| (count.genre.clone(), count.songs))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(stats.songs, 6);
+        assert_eq!(stats.untagged, 2);
+        assert_eq!(stats.all_unknown, 1);
+        assert_eq!(
+            counts(&stats.unknown_genres),
+            [("polka".to_string(), 2), ("sea shanty".to_string(), 1)]
+        );
+        // genres tagged twice on a song count once
+        assert_eq!(
+            counts(&stats.known_genres),
+            [("rock".to_string(), 2), ("jazz".to_string(), 1)]
+        );
+    }
+}
@@ -0,0 +1,669 @@
+//! A nearest neighbor index over bliss analyses, so recommendations can be found without sorting
+//! the whole library by distance to the seeds.
+//!
+//! The index is a KD-tree searched best-first, so songs come out in order of distance as they're
+//! asked for. That's exact for metrics that compare each feature on its own (euclidean and
+//! weighted distance, normalized or not); other metrics still sort the whole library.
+
+use super::distance::DistanceConfig;
+use super::normalization::{FeatureStats, Normalization};
+use super::{BlissSong, MPDLibrary, Metric};
+use crate::NUM_BLISS_FEATURES;
+use anyhow::Result;
+use log::info;
+use ndarray::Array1;
+use noisy_float::prelude::{N32, n32};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Most songs in a leaf of the tree when it's built. Leaves that grow to twice this from
+/// insertions are split again.
+const LEAF_SIZE: usize = 32;
+
+/// How the index compares songs: each feature is normalized and scaled by the square root of its
+/// weight, and then songs are compared with euclidean distance. That gives the same distances as
+/// the metric itself, and keeps every feature in the same order, so the bounds of a part of the
+/// tree still bound the songs in it.
+pub struct SearchSpace {
+    scale: Array1<f32>,
+    normalization: Normalization,
+    stats: Option<Arc<FeatureStats>>,
+}
+
+impl SearchSpace {
+    /// The space for `metric` with `normalization`, or `None` if the index can't search it.
+    /// `stats` is only called if there's something to normalize.
+    ///
+    /// May fail if the distance weights are invalid or if the feature statistics can't be
+    /// computed.
+    pub fn new(
+        metric: Metric,
+        normalization: Normalization,
+        config: &DistanceConfig,
+        stats: impl FnOnce() -> Result<Arc<FeatureStats>>,
+    ) -> Result<Option<Self>> {
+        let scale = match metric {
+            Metric::Euclidean => Array1::ones(NUM_BLISS_FEATURES),
+            Metric::Weighted => config.bliss_metric()?.weights().mapv(f32::sqrt),
+            Metric::Cosine | Metric::Mahalanobis | Metric::Forest => return Ok(None),
+        };
+        let stats = match normalization {
+            Normalization::None => None,
+            Normalization::ZScore | Normalization::Rank => Some(stats()?),
+        };
+        Ok(Some(Self {
+            scale,
+            normalization,
+            stats,
+        }))
+    }
+
+    /// Plain euclidean distance between analyses.
+    pub(super) fn euclidean() -> Self {
+        Self {
+            scale: Array1::ones(NUM_BLISS_FEATURES),
+            normalization: Normalization::None,
+            stats: None,
+        }
+    }
+
+    fn project_value(&self, feature: usize, value: f32) -> f32 {
+        let value = match &self.stats {
+            Some(stats) => stats.normalize_value(self.normalization, feature, value),
+            None => value,
+        };
+        value * self.scale[feature]
+    }
+
+    fn project(&self, analysis: &[f32]) -> Vec<f32> {
+        analysis
+            .iter()
+            .enumerate()
+            .map(|(feature, &value)| self.project_value(feature, value))
+            .collect()
+    }
+}
+
+/// A distance to sort or order the search queue by. NaN goes last instead of panicking.
+pub(super) fn order(distance: f32) -> N32 {
+    n32(if distance.is_nan() {
+        f32::INFINITY
+    } else {
+        distance
+    })
+}
+
+/// Whether a song can go in the index. Analyses with NaN or infinite features can't be ordered.
+pub(super) fn indexable(song: &BlissSong) -> bool {
+    song.bliss_song
+        .analysis
+        .as_arr1()
+        .iter()
+        .all(|value| value.is_finite())
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+#[derive(Clone)]
+struct Node {
+    /// Smallest and largest value of each feature of the songs under the node, as bliss computes
+    /// them. Removing songs doesn't shrink these, so they only ever bound the songs.
+    min: Vec<f32>,
+    max: Vec<f32>,
+    kind: NodeKind,
+}
+
+#[derive(Clone)]
+enum NodeKind {
+    Leaf(Vec<usize>),
+    /// Songs whose `feature` is below `value` are under `left`, the rest under `right`
+    Split {
+        feature: usize,
+        value: f32,
+        left: usize,
+        right: usize,
+    },
+}
+
+/// A KD-tree over the analyses of every song in the library.
+#[derive(Clone)]
+pub struct KnnIndex {
+    /// Nodes of the tree, the root first
+    nodes: Vec<Node>,
+    /// The library the index was built or last refreshed from, shared with [MPDLibrary::songs]
+    songs: Arc<Vec<BlissSong>>,
+    /// Where each song is in `songs` by id, `None` once they've been removed
+    positions: Vec<Option<usize>>,
+    analyses: Vec<Vec<f32>>,
+    ids: HashMap<PathBuf, usize>,
+}
+
+impl KnnIndex {
+    pub fn new(songs: Arc<Vec<BlissSong>>) -> Self {
+        let mut index = Self {
+            nodes: vec![],
+            songs: Arc::clone(&songs),
+            positions: vec![],
+            analyses: vec![],
+            ids: HashMap::new(),
+        };
+        let ids: Vec<usize> = songs
+            .iter()
+            .enumerate()
+            .filter(|(_, song)| indexable(song))
+            .map(|(position, _)| index.push_song(position))
+            .collect();
+        if ids.len() < songs.len() {
+            info!(
+                "Left {} songs with invalid analyses out of the nearest neighbor index",
+                songs.len() - ids.len()
+            );
+        }
+        index.build_node(ids);
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Give the song at `position` in `songs` an id, without adding it to the tree.
+    fn push_song(&mut self, position: usize) -> usize {
+        let id = self.positions.len();
+        let song = &self.songs[position];
+        self.analyses
+            .push(song.bliss_song.analysis.as_arr1().to_vec());
+        self.ids.insert(song.bliss_song.path.clone(), id);
+        self.positions.push(Some(position));
+        id
+    }
+
+    fn build_node(&mut self, ids: Vec<usize>) -> usize {
+        let mut min = vec![f32::INFINITY; NUM_BLISS_FEATURES];
+        let mut max = vec![f32::NEG_INFINITY; NUM_BLISS_FEATURES];
+        for &id in &ids {
+            for (feature, &value) in self.analyses[id].iter().enumerate() {
+                min[feature] = min[feature].min(value);
+                max[feature] = max[feature].max(value);
+            }
+        }
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            min,
+            max,
+            kind: NodeKind::Leaf(vec![]),
+        });
+        self.nodes[node].kind = self.split(node, ids);
+        node
+    }
+
+    /// Split the songs under `node` at the median of the feature they're most spread out along.
+    fn split(&mut self, node: usize, mut ids: Vec<usize>) -> NodeKind {
+        if ids.len() <= LEAF_SIZE {
+            return NodeKind::Leaf(ids);
+        }
+        let Node { min, max, .. } = &self.nodes[node];
+        let feature = (0..NUM_BLISS_FEATURES)
+            .max_by(|&a, &b| (max[a] - min[a]).total_cmp(&(max[b] - min[b])))
+            .expect("There are features");
+        let analyses = &self.analyses;
+        let median = ids.len() / 2;
+        ids.select_nth_unstable_by(median, |&a, &b| {
+            analyses[a][feature].total_cmp(&analyses[b][feature])
+        });
+        let mut value = analyses[ids[median]][feature];
+        if ids.iter().all(|&id| analyses[id][feature] >= value) {
+            // the median is also the smallest value, so split just above it instead
+            match ids
+                .iter()
+                .map(|&id| analyses[id][feature])
+                .filter(|&other| other > value)
+                .min_by(f32::total_cmp)
+            {
+                Some(above) => value = above,
+                // every feature is the same for all of these songs
+                None => return NodeKind::Leaf(ids),
+            }
+        }
+        let (left, right) = ids
+            .into_iter()
+            .partition(|&id| analyses[id][feature] < value);
+        NodeKind::Split {
+            feature,
+            value,
+            left: self.build_node(left),
+            right: self.build_node(right),
+        }
+    }
+
+    fn insert(&mut self, position: usize) {
+        let id = self.push_song(position);
+        let analysis = self.analyses[id].clone();
+        let mut node = 0;
+        loop {
+            let Node { min, max, kind } = &mut self.nodes[node];
+            for (feature, &value) in analysis.iter().enumerate() {
+                min[feature] = min[feature].min(value);
+                max[feature] = max[feature].max(value);
+            }
+            match kind {
+                NodeKind::Split {
+                    feature,
+                    value,
+                    left,
+                    right,
+                } => {
+                    node = if analysis[*feature] < *value {
+                        *left
+                    } else {
+                        *right
+                    }
+                }
+                NodeKind::Leaf(ids) => {
+                    ids.push(id);
+                    if ids.len() >= 2 * LEAF_SIZE {
+                        let ids = std::mem::take(ids);
+                        self.nodes[node].kind = self.split(node, ids);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, path: &Path) {
+        let Some(id) = self.ids.remove(path) else {
+            return;
+        };
+        self.positions[id] = None;
+        let analysis = &self.analyses[id];
+        let mut node = 0;
+        loop {
+            match &mut self.nodes[node].kind {
+                NodeKind::Split {
+                    feature,
+                    value,
+                    left,
+                    right,
+                } => {
+                    node = if analysis[*feature] < *value {
+                        *left
+                    } else {
+                        *right
+                    }
+                }
+                NodeKind::Leaf(ids) => {
+                    ids.retain(|&other| other != id);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Bring the index up to date with `songs`: add new and reanalyzed songs, remove ones that
+    /// are gone, and pick up changes to the rest. Returns how many songs were added and removed.
+    fn refresh(&mut self, songs: Arc<Vec<BlissSong>>) -> (usize, usize) {
+        let indexable_songs: Vec<(usize, &BlissSong)> = songs
+            .iter()
+            .enumerate()
+            .filter(|(_, song)| indexable(song))
+            .collect();
+        let paths: HashSet<&Path> = indexable_songs
+            .iter()
+            .map(|(_, song)| song.bliss_song.path.as_path())
+            .collect();
+        let removed: Vec<PathBuf> = self
+            .ids
+            .keys()
+            .filter(|path| !paths.contains(path.as_path()))
+            .cloned()
+            .collect();
+        for path in &removed {
+            self.remove(path);
+        }
+        self.songs = Arc::clone(&songs);
+        let mut added = 0;
+        for (position, song) in indexable_songs {
+            match self.ids.get(&song.bliss_song.path).copied() {
+                Some(id) if self.analyses[id] == song.bliss_song.analysis.as_arr1().to_vec() => {
+                    // tags and popularity can change without the analysis changing
+                    self.positions[id] = Some(position);
+                    continue;
+                }
+                Some(_) => self.remove(&song.bliss_song.path),
+                None => (),
+            }
+            self.insert(position);
+            added += 1;
+        }
+        // removed songs still take up space, so start over once they're most of it
+        if self.positions.len() > 2 * self.len() {
+            *self = Self::new(songs);
+        }
+        (added, removed.len())
+    }
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Entry {
+    // songs sort before nodes at the same distance, since nothing under the node can be closer
+    Song(usize),
+    Node(usize),
+}
+
+/// Songs in the index in order of their summed distance to the seeds, found as they're asked for.
+pub struct Nearest {
+    index: Arc<KnnIndex>,
+    space: SearchSpace,
+    seeds: Vec<Vec<f32>>,
+    queue: BinaryHeap<Reverse<(N32, Entry)>>,
+}
+
+impl Nearest {
+    pub(super) fn new(index: Arc<KnnIndex>, space: SearchSpace, seeds: &[BlissSong]) -> Self {
+        let seeds = seeds
+            .iter()
+            .map(|seed| space.project(&seed.bliss_song.analysis.as_arr1().to_vec()))
+            .collect();
+        let mut nearest = Self {
+            index,
+            space,
+            seeds,
+            queue: BinaryHeap::new(),
+        };
+        nearest.push_node(0);
+        nearest
+    }
+
+    fn push_node(&mut self, node: usize) {
+        let Node { min, max, kind } = &self.index.nodes[node];
+        if matches!(kind, NodeKind::Leaf(ids) if ids.is_empty()) {
+            return;
+        }
+        let min = self.space.project(min);
+        let max = self.space.project(max);
+        // the closest any song under the node could be to each seed
+        let bound = self
+            .seeds
+            .iter()
+            .map(|seed| {
+                seed.iter()
+                    .zip(&min)
+                    .zip(&max)
+                    // `max` and `min` rather than `clamp`, which panics on NaN
+                    .map(|((&value, &min), &max)| (value - value.max(min).min(max)).powi(2))
+                    .sum::<f32>()
+                    .sqrt()
+            })
+            .sum();
+        self.queue.push(Reverse((order(bound), Entry::Node(node))));
+    }
+
+    fn push_song(&mut self, id: usize) {
+        let analysis = self.space.project(&self.index.analyses[id]);
+        let total = self
+            .seeds
+            .iter()
+            .map(|seed| distance(seed, &analysis))
+            .sum();
+        self.queue.push(Reverse((order(total), Entry::Song(id))));
+    }
+}
+
+impl Iterator for Nearest {
+    type Item = BlissSong;
+
+    fn next(&mut self) -> Option<BlissSong> {
+        let index = Arc::clone(&self.index);
+        while let Some(Reverse((_, entry))) = self.queue.pop() {
+            match entry {
+                Entry::Song(id) => {
+                    if let Some(position) = index.positions[id] {
+                        return Some(index.songs[position].clone());
+                    }
+                }
+                Entry::Node(node) => match &index.nodes[node].kind {
+                    NodeKind::Leaf(ids) => {
+                        for &id in ids {
+                            self.push_song(id);
+                        }
+                    }
+                    &NodeKind::Split { left, right, .. } => {
+                        self.push_node(left);
+                        self.push_node(right);
+                    }
+                },
+            }
+        }
+        None
+    }
+}
+
+impl MPDLibrary {
+    /// Build the nearest neighbor index over the whole library, unless it's turned off with
+    /// `distance.index` in the config.
+    ///
+    /// May fail if the bliss library is corrupted.
+    pub fn build_index(&self) -> Result<()> {
+        if !self.bliss.config.distance.index {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let index = KnnIndex::new(self.songs()?);
+        info!(
+            "Built nearest neighbor index of {} songs in {}ms",
+            index.len(),
+            now.elapsed().as_millis()
+        );
+        *self.knn_index.write().expect("Poisoned lock") = Some(Arc::new(index));
+        Ok(())
+    }
+
+    /// Bring the nearest neighbor index up to date with the library, if it's been built.
+    /// Playlists already being searched keep the index as it was.
+    ///
+    /// May fail if the bliss library is corrupted.
+    pub(super) fn refresh_index(&self) -> Result<()> {
+        let Some(index) = self.knn_index.read().expect("Poisoned lock").clone() else {
+            return Ok(());
+        };
+        let songs = self.songs()?;
+        let mut index = KnnIndex::clone(&index);
+        let (added, removed) = index.refresh(songs);
+        info!("Nearest neighbor index updated, {added} songs added and {removed} removed");
+        *self.knn_index.write().expect("Poisoned lock") = Some(Arc::new(index));
+        Ok(())
+    }
+
+    /// Songs closest to `seeds` first (the seeds included), from the nearest neighbor index.
+    /// `None` if the index isn't built or can't search `metric`, in which case the library has to
+    /// be sorted instead.
+    ///
+    /// May fail if the distance weights are invalid or if the feature statistics can't be
+    /// computed.
+    pub fn nearest(
+        &self,
+        seeds: &[BlissSong],
+        metric: Metric,
+        normalization: Normalization,
+    ) -> Result<Option<Nearest>> {
+        let Some(index) = self.knn_index.read().expect("Poisoned lock").clone() else {
+            return Ok(None);
+        };
+        let space = SearchSpace::new(metric, normalization, &self.bliss.config.distance, || {
+            self.feature_stats()
+        })?;
+        Ok(space.map(|space| Nearest::new(index, space, seeds)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mpd_library::ExtraInfo;
+    use bliss_audio::playlist::{DistanceMetric, DistanceMetricBuilder, euclidean_distance};
+    use bliss_audio::{Analysis, FeaturesVersion, Song as BareBlissSong};
+    use std::collections::HashMap;
+
+    fn song(name: &str, analysis: Vec<f32>) -> BlissSong {
+        BlissSong {
+            bliss_song: BareBlissSong {
+                path: PathBuf::from(name),
+                analysis: Analysis::new(analysis, FeaturesVersion::LATEST)
+                    .expect("Analysis has every feature"),
+                ..Default::default()
+            },
+            extra_info: ExtraInfo::default(),
+        }
+    }
+
+    /// Songs with reproducible features spread over -1 to 1, with a few duplicates.
+    fn library(count: usize) -> Vec<BlissSong> {
+        let mut state = 0x2545f4914f6cdd1d_u64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 40) as f32 / (1 << 23) as f32 - 1.0
+        };
+        let mut songs: Vec<BlissSong> = (0..count)
+            .map(|i| {
+                song(
+                    &format!("{i}.flac"),
+                    (0..NUM_BLISS_FEATURES).map(|_| next()).collect(),
+                )
+            })
+            .collect();
+        for i in 0..count / 10 {
+            let copy = songs[i].bliss_song.analysis.as_vec();
+            songs.push(song(&format!("copy {i}.flac"), copy));
+        }
+        songs
+    }
+
+    /// Check the index finds every song in `songs`, in the order the metric would sort them.
+    fn assert_matches_sorting(
+        index: KnnIndex,
+        songs: &[BlissSong],
+        seeds: &[BlissSong],
+        metric: Metric,
+        normalization: Normalization,
+        config: &DistanceConfig,
+    ) {
+        let analyses: Vec<Array1<f32>> = songs
+            .iter()
+            .map(|song| song.bliss_song.analysis.as_arr1())
+            .collect();
+        let stats = Arc::new(FeatureStats::new(&analyses));
+        let space = SearchSpace::new(metric, normalization, config, || Ok(Arc::clone(&stats)))
+            .expect("Weights are valid")
+            .expect("Metric can be indexed");
+        let found: Vec<BlissSong> = Nearest::new(Arc::new(index), space, seeds).collect();
+
+        let weighted = config.bliss_metric().expect("Weights are valid");
+        let builder: &(dyn DistanceMetricBuilder + Sync) = match metric {
+            Metric::Weighted => &weighted,
+            _ => &euclidean_distance,
+        };
+        let normalized = normalization
+            .apply(metric, builder, || Ok(Arc::clone(&stats)))
+            .expect("Statistics are given");
+        let seed_analyses: Vec<Array1<f32>> = seeds
+            .iter()
+            .map(|seed| seed.bliss_song.analysis.as_arr1())
+            .collect();
+        let sorting = normalized.build(&seed_analyses);
+        let distances = |songs: &[BlissSong]| -> Vec<f32> {
+            songs
+                .iter()
+                .map(|song| sorting.distance(&song.bliss_song.analysis.as_arr1()))
+                .collect()
+        };
+        let mut expected = distances(songs);
+        expected.sort_by(f32::total_cmp);
+
+        assert_eq!(found.len(), songs.len());
+        let found_paths: HashSet<&PathBuf> =
+            found.iter().map(|song| &song.bliss_song.path).collect();
+        assert_eq!(found_paths.len(), songs.len());
+        for (found, expected) in distances(&found).iter().zip(&expected) {
+            assert!(
+                (found - expected).abs() <= 1e-4 * expected.max(1.0),
+                "Found a song at distance {found}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn search_matches_sorting() {
+        let songs = library(1000);
+        let seeds = [songs[3].clone(), songs[500].clone()];
+        let config = DistanceConfig {
+            bliss: HashMap::from([("tempo".to_string(), 4.0), ("chroma_2".to_string(), 0.0)]),
+            ..Default::default()
+        };
+        for metric in [Metric::Euclidean, Metric::Weighted] {
+            for normalization in [
+                Normalization::None,
+                Normalization::ZScore,
+                Normalization::Rank,
+            ] {
+                assert_matches_sorting(
+                    KnnIndex::new(Arc::new(songs.clone())),
+                    &songs,
+                    &seeds,
+                    metric,
+                    normalization,
+                    &config,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn refresh_matches_rebuilding() {
+        let old = library(600);
+        let mut index = KnnIndex::new(Arc::new(old.clone()));
+        // keep a quarter of the library so the index is rebuilt, move some songs and add others
+        let mut songs = old[..150].to_vec();
+        for song in &mut songs[..20] {
+            let moved = song
+                .bliss_song
+                .analysis
+                .as_vec()
+                .iter()
+                .map(|value| -value)
+                .collect();
+            song.bliss_song.analysis =
+                Analysis::new(moved, FeaturesVersion::LATEST).expect("Analysis has every feature");
+        }
+        songs.extend(old[150..190].iter().map(|other| {
+            song(
+                &format!("new {}", other.bliss_song.path.display()),
+                other.bliss_song.analysis.as_vec(),
+            )
+        }));
+        let mut with_invalid = songs.clone();
+        with_invalid.push(song("invalid.flac", vec![f32::NAN; NUM_BLISS_FEATURES]));
+
+        assert_eq!(index.refresh(Arc::new(with_invalid)), (60, old.len() - 150));
+        assert_eq!(index.len(), songs.len());
+        assert_matches_sorting(
+            index,
+            &songs,
+            &[songs[0].clone()],
+            Metric::Euclidean,
+            Normalization::None,
+            &DistanceConfig::default(),
+        );
+    }
+}
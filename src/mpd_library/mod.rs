@@ -1,5 +1,14 @@
+mod distance;
 mod ffmpeg_decoder;
+mod genre_map;
+mod genres;
+mod knn;
+mod normalization;
+mod pool;
+mod portable;
 
+use crate::daemon::{DaemonEvent, DaemonSong, DaemonState, UPCOMING_LEN};
+use crate::server::ServerConfig;
 use crate::{NUM_BLISS_FEATURES, NUM_GENRE_FEATURES};
 use anyhow::{Context, Result, anyhow, bail};
 use bliss_audio::{
@@ -7,27 +16,35 @@ use bliss_audio::{
     library::{AppConfigTrait, BaseConfig, Library, LibrarySong as BlissSongNoInfo},
     playlist::{DistanceMetricBuilder, euclidean_distance},
 };
+use distance::DistanceConfig;
+use distance::Mahalanobis;
+pub use distance::Metric;
 use fallible_streaming_iterator::FallibleStreamingIterator;
 use ffmpeg_decoder::FFmpegDecoder as Decoder;
+use genre_map::GENRE_AXES;
+use genres::infer_from_neighbors;
+pub use genres::{GenreConfig, GenreStats};
 use itertools::Itertools;
+use knn::KnnIndex;
 use log::{debug, info, warn};
 use mpd::{Client, Idle, Query, Song as MPDSong, Term, search::Window};
 use ndarray::{Array1, arr1};
-use noisy_float::prelude::n32;
-use rocket::tokio::sync::{Mutex, MutexGuard};
+use normalization::FeatureStats;
+pub use normalization::Normalization;
+pub use pool::MPDPool;
+pub use portable::ExportFormat;
 use serde::{Deserialize, Serialize};
-use std::fs::File;
+use std::fs;
 use std::io::{self, Read, Write};
 use std::net::TcpStream;
-#[cfg(target_os = "android")]
-use std::os::android::net::SocketAddrExt;
-#[cfg(target_os = "linux")]
-use std::os::linux::net::SocketAddrExt;
-use std::os::unix::net::{SocketAddr, UnixStream};
+use std::os::unix::net::UnixStream;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, atomic::Ordering};
-use std::{collections::HashMap, time::Instant};
-use std::{env, sync::atomic::AtomicBool};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Instant,
+};
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ExtraInfo {
@@ -76,6 +93,17 @@ pub struct Config {
     #[serde(flatten)]
     base_config: BaseConfig,
     pub mpd_base_path: PathBuf,
+    /// Other base paths songs may have been analyzed under, e.g. where a network share is mounted
+    /// on another machine. Songs stored under these are treated as if they were under
+    /// `mpd_base_path`.
+    #[serde(default)]
+    pub alternate_base_paths: Vec<PathBuf>,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub genres: GenreConfig,
+    #[serde(default)]
+    pub distance: DistanceConfig,
 }
 
 impl AppConfigTrait for Config {
@@ -99,20 +127,34 @@ impl Config {
         Ok(Self {
             base_config,
             mpd_base_path,
+            alternate_base_paths: vec![],
+            server: ServerConfig::default(),
+            genres: GenreConfig::default(),
+            distance: DistanceConfig::default(),
         })
     }
 }
 
-fn average(v: &[f32]) -> f32 {
-    if !v.is_empty() {
-        v.iter().sum::<f32>() / v.len() as f32
-    } else {
-        0.0
-    }
-}
-
-fn genre_vec_avg(v: Vec<[f32; NUM_GENRE_FEATURES]>) -> Vec<f32> {
-    v.iter().map(|arr| average(&arr[..])).collect()
+/// Move every song stored under `from` to the same place under `to` in the bliss database,
+/// returning how many moved.
+///
+/// May fail if the database can't be written to.
+fn rewrite_paths(bliss: &Library<Config, Decoder>, from: &Path, to: &Path) -> Result<usize> {
+    // compare whole components, so that /music doesn't also match /music2
+    let prefix = |path: &Path| {
+        let path = path.to_string_lossy();
+        format!("{}/", path.trim_end_matches('/'))
+    };
+    bliss
+        .sqlite_conn
+        .lock()
+        .expect("Poisoned lock")
+        .execute(
+            "update song set path = ?2 || substr(path, length(?1) + 1)
+                where substr(path, 1, length(?1)) = ?1",
+            [prefix(from), prefix(to)],
+        )
+        .context("while rewriting song paths")
 }
 
 pub fn pad_slice<const N: usize>(v: &[f32]) -> [f32; N] {
@@ -127,8 +169,11 @@ pub fn pad_slice<const N: usize>(v: &[f32]) -> [f32; N] {
     result
 }
 
+/// Combine the weights of the genres in a genre tag as set in `genre_config`, padded with zeros to
+/// `N` features. Genres missing from the genre map are ignored.
 pub fn collapse_genres_pad_to<const N: usize>(
     genre_weights: &GenreWeights,
+    genre_config: &GenreConfig,
     genres: String,
 ) -> [f32; N] {
     assert!(
@@ -142,41 +187,31 @@ pub fn collapse_genres_pad_to<const N: usize>(
     if genres.is_empty() {
         [0.0; N]
     } else {
-        let genres_vec: Vec<_> = genres.split(",").collect();
-        let genres_vec: Vec<_> = genres_vec
-            .into_iter()
+        let genres_vec: Vec<_> = genre_config
+            .split(&genres)
+            .iter()
             .filter_map(|genre| genre_weights.get(genre))
-            .map(|weights| weights.to_owned())
+            .copied()
             .collect();
-        let result = if genres_vec.len() as f32 * NUM_GENRE_FEATURES as f32 <= N as f32 {
-            // use full genre weights
-            genres_vec.into_iter().flatten().collect()
-        } else {
-            // collapse each genre into one feature
-            genre_vec_avg(genres_vec)
-        };
-        if result.len() > N {
-            info!(
-                "Clipping genre features with length {} to fit into array of length {}",
-                result.len(),
-                N
-            );
-            result[..N].try_into().unwrap_or([0.0; N])
-        } else {
-            pad_slice(&result)
-        }
+        genre_config.combination.combine(&genres_vec)
     }
 }
 
-pub fn collapse_genres(genre_weights: &GenreWeights, genres: String) -> [f32; NUM_BLISS_FEATURES] {
-    collapse_genres_pad_to(genre_weights, genres)
+pub fn collapse_genres(
+    genre_weights: &GenreWeights,
+    genre_config: &GenreConfig,
+    genres: String,
+) -> [f32; NUM_BLISS_FEATURES] {
+    collapse_genres_pad_to(genre_weights, genre_config, genres)
 }
 
 /// A modified version of the bliss default playlist creator, sorting by genre weights and using bliss similarity as a tiebreaker.
+/// Genre weights are compared with `genre_metric_builder`, bliss analyses with `metric_builder`.
 pub fn closest_to_genre_songs<'a, T: AsRef<BareBlissSong> + Clone + 'a>(
     initial_songs: &[T],
     candidate_songs: &[T],
     metric_builder: &'a dyn DistanceMetricBuilder,
+    genre_metric_builder: &dyn DistanceMetricBuilder,
     track_weights: &TrackWeights,
 ) -> impl Iterator<Item = T> + 'a {
     assert!(
@@ -192,119 +227,197 @@ pub fn closest_to_genre_songs<'a, T: AsRef<BareBlissSong> + Clone + 'a>(
         .iter()
         .filter_map(|c| Some(arr1(track_weights.get(&*c.as_ref().path)?)))
         .collect();
-    let genre_metric = metric_builder.build(&initial_songs_genre_weights);
+    let genre_metric = genre_metric_builder.build(&initial_songs_genre_weights);
     let mut candidate_songs = candidate_songs.to_vec();
     candidate_songs.sort_by_cached_key(|song| {
         (
-            n32(genre_metric.distance(&arr1(
+            knn::order(
+                genre_metric.distance(&arr1(
+                    track_weights
+                        .get(&*song.as_ref().path)
+                        .unwrap_or(&[0.0; NUM_BLISS_FEATURES]),
+                )),
+            ),
+            knn::order(bliss_metric.distance(&song.as_ref().analysis.as_arr1())),
+        )
+    });
+    candidate_songs.into_iter()
+}
+
+/// Sort by the sum of genre and bliss distances, so that neither one only breaks ties for the other.
+pub fn closest_to_hybrid_songs<'a, T: AsRef<BareBlissSong> + Clone + 'a>(
+    initial_songs: &[T],
+    candidate_songs: &[T],
+    metric_builder: &'a dyn DistanceMetricBuilder,
+    genre_metric_builder: &dyn DistanceMetricBuilder,
+    track_weights: &TrackWeights,
+) -> impl Iterator<Item = T> + 'a {
+    assert!(
+        !track_weights.is_empty(),
+        "Likely tried to call hybrid_sort under `bliss`"
+    );
+    let initial_songs_bliss_weights: Vec<Array1<f32>> = initial_songs
+        .iter()
+        .map(|c| c.as_ref().analysis.as_arr1())
+        .collect();
+    let bliss_metric = metric_builder.build(&initial_songs_bliss_weights);
+    let initial_songs_genre_weights: Vec<Array1<f32>> = initial_songs
+        .iter()
+        .filter_map(|c| Some(arr1(track_weights.get(&*c.as_ref().path)?)))
+        .collect();
+    let genre_metric = genre_metric_builder.build(&initial_songs_genre_weights);
+    let mut candidate_songs = candidate_songs.to_vec();
+    candidate_songs.sort_by_cached_key(|song| {
+        knn::order(
+            genre_metric.distance(&arr1(
                 track_weights
                     .get(&*song.as_ref().path)
                     .unwrap_or(&[0.0; NUM_BLISS_FEATURES]),
-            ))),
-            n32(bliss_metric.distance(&song.as_ref().analysis.as_arr1())),
+            )) + bliss_metric.distance(&song.as_ref().analysis.as_arr1()),
         )
     });
     candidate_songs.into_iter()
 }
 
+/// Associate each song that has a genre tag with its collapsed genre weights. Songs without known
+/// genres are given their neighbors' weights if `genre_config.infer_neighbors` is set.
+pub fn track_genre_weights<'a>(
+    genre_weights: &GenreWeights,
+    genre_config: &GenreConfig,
+    songs: impl IntoIterator<Item = &'a BlissSong>,
+) -> TrackWeights {
+    let mut genre_weights_by_track_path: TrackWeights = HashMap::new();
+    let mut tagged = vec![];
+    let mut untagged = vec![];
+    for song in songs {
+        let genres = song.bliss_song.genre.as_deref().unwrap_or_default();
+        if genre_config.infer_neighbors > 0
+            && !genre_config
+                .split(genres)
+                .iter()
+                .any(|genre| genre_weights.contains_key(genre))
+        {
+            untagged.push(song);
+            continue;
+        }
+        if let Some(genres) = &song.bliss_song.genre {
+            genre_weights_by_track_path
+                .entry(song.bliss_song.path.clone())
+                .or_insert_with(|| collapse_genres(genre_weights, genre_config, genres.clone()));
+            tagged.push(song);
+        }
+    }
+    infer_from_neighbors(
+        &mut genre_weights_by_track_path,
+        &tagged,
+        &untagged,
+        genre_config.infer_neighbors,
+    );
+    genre_weights_by_track_path
+}
+
+/// Drop songs that look like the song before them in a playlist, like bliss' dedup: the same
+/// title and artist, or nearly the same analysis by `distance`.
+pub fn dedup_songs<'a>(
+    songs: impl Iterator<Item = BlissSong> + 'a,
+    distance: &'a dyn DistanceMetricBuilder,
+) -> impl Iterator<Item = BlissSong> + 'a {
+    songs.coalesce(move |previous, song| {
+        if is_duplicate(&previous, &song, distance) {
+            Ok(previous)
+        } else {
+            Err((previous, song))
+        }
+    })
+}
+
+/// Whether `song` would be dropped by [dedup_songs] after `previous`.
+pub fn is_duplicate(
+    previous: &BlissSong,
+    song: &BlissSong,
+    distance: &dyn DistanceMetricBuilder,
+) -> bool {
+    let (a, b) = (&previous.bliss_song, &song.bliss_song);
+    let same_tags =
+        a.title.is_some() && a.artist.is_some() && a.title == b.title && a.artist == b.artist;
+    same_tags
+        || distance
+            .build(&[a.analysis.as_arr1()])
+            .distance(&b.analysis.as_arr1())
+            < DEDUP_DISTANCE
+}
+
+/// Songs closer than this to the song before them are dropped when deduplicating a playlist.
+const DEDUP_DISTANCE: f32 = 0.05;
+/// How many new songs to analyze between progress reports during an update.
+const UPDATE_BATCH_SIZE: usize = 50;
+const GENRES_FILE_NAME: &str = "genres.json";
+
 type GenreName = String;
 /// A mapping of genre names to an array of weights along the axes from everynoise.com: (organicness/mechanicity, etherealness/spikiness, energy, dynamic variation, instrumentalness). (see https://www.furia.com/page.cgi?type=log&id=419 for the last three values).
 pub type GenreWeights = HashMap<GenreName, [f32; NUM_GENRE_FEATURES]>;
 
 type TrackPath = PathBuf;
-/// A mapping of track names to calculated genre weights from a Eucliean average of their genre names. Might make sense to customize the averaging function in the future.
-type TrackWeights = HashMap<TrackPath, [f32; NUM_BLISS_FEATURES]>;
+/// A mapping of track names to calculated genre weights, combined from their genre names as set in [GenreConfig].
+pub type TrackWeights = HashMap<TrackPath, [f32; NUM_BLISS_FEATURES]>;
 
-/// The main struct which holds the bliss library and MPD connection. Also holds the genre weights if present.
-pub struct MPDLibrary {
-    pub bliss: Library<Config, Decoder>,
-    pub mpd_conn: Arc<Mutex<Client<MPDStream>>>,
-    pub genre_weights: Option<GenreWeights>,
+/// Recommendations for a pin, worked out as they're needed.
+struct Playlist<'a> {
+    /// Songs already worked out to show as upcoming
+    upcoming: VecDeque<BlissSong>,
+    rest: Box<dyn Iterator<Item = BlissSong> + 'a>,
 }
 
-/// MPDLibrary holds the connection to MPD, methods to analyze songs with bliss, and the main `queue_from_song` method
-/// that does the queueing of similar songs.
-impl MPDLibrary {
-    /// connect_to_mpd doesn't need to be called directly, building or retrieving an existing MPDLibrary
-    /// will do it for you.
-    fn connect_to_mpd() -> Result<Client<MPDStream>> {
-        let (password, mpd_host) = match env::var("MPD_HOST") {
-            Ok(h) => match h.split_once('@') {
-                // Only host
-                None => (None, h),
-                // Unix socket
-                Some(("", _)) => (None, h),
-                // Password + host
-                Some((password, host)) => (Some(password.to_owned()), host.to_owned()),
-            },
-            Err(_) => {
-                eprintln!("MPD_HOST not set in the environment, defaulting to 127.0.0.1");
-                (None, String::from("127.0.0.1"))
-            }
-        };
-
-        let mpd_port = match env::var("MPD_PORT") {
-            Ok(p) => p
-                .parse::<u16>()
-                .context("while trying to coerce MPD_PORT to int")?,
-            Err(_) => {
-                // Would prefer to defer to MPD for the default port but the mpd crate doesn't have a method for doing that
-                eprintln!("MPD_PORT not set in the environment, using default of 6600");
-                6600
-            }
-        };
-
-        let mut client: Client<MPDStream> = {
-            if mpd_host.starts_with('/') || mpd_host.starts_with('~') {
-                Client::new(MPDStream::Unix(
-                    UnixStream::connect(mpd_host).context("while connecting to Unix stream")?,
-                ))
-                .context("while connecting to MPD")?
-            } else if mpd_host.starts_with('@') {
-                let addr = SocketAddr::from_abstract_name(
-                    mpd_host
-                        .split_once('@')
-                        .ok_or(anyhow!("No socket name provided"))?
-                        .1,
-                )?;
-                Client::new(MPDStream::Unix(
-                    UnixStream::connect_addr(&addr).context("while connecting to Unix stream")?,
-                ))
-                .context("while connecting to MPD")?
-            } else {
-                Client::new(MPDStream::Tcp(
-                    TcpStream::connect(format!("{}:{}", mpd_host, mpd_port))
-                        .context("while connecting to TCP stream")?,
-                ))
-                .context("while connecting to MPD")?
-            }
-        };
-        if let Some(pass) = password {
-            client.login(&pass).context("while logging in to MPD")?;
-        }
-        Ok(client)
+impl Playlist<'_> {
+    fn next_song(&mut self, keep: impl Fn(&BlissSong) -> bool) -> Option<BlissSong> {
+        std::iter::from_fn(|| self.upcoming.pop_front().or_else(|| self.rest.next()))
+            .find(|song| keep(song))
     }
 
-    pub fn reconnect_to_mpd(mpd_conn: &mut MutexGuard<Client<MPDStream>>) {
-        let mut counter = 1;
-        loop {
-            let result = Self::connect_to_mpd();
-            match result {
-                Ok(new_conn) => {
-                    **mpd_conn = new_conn;
-                    println!("Reconnected to MPD!");
-                    break;
-                }
-                Err(_) => {
-                    let backoff = 2_u64.pow(counter);
-                    println!("Reconnecting in {backoff} seconds...");
-                    std::thread::sleep(std::time::Duration::from_secs(backoff));
-                    counter += 1;
-                }
-            }
+    /// The next `count` songs that would be queued.
+    fn upcoming(
+        &mut self,
+        count: usize,
+        keep: impl Fn(&BlissSong) -> bool,
+    ) -> impl Iterator<Item = &BlissSong> {
+        while self.upcoming.iter().filter(|song| keep(song)).count() < count
+            && let Some(song) = self.rest.next()
+        {
+            self.upcoming.push_back(song);
         }
+        self.upcoming
+            .iter()
+            .filter(move |song| keep(song))
+            .take(count)
     }
+}
+
+/// The main struct which holds the bliss library and MPD connections. Also holds the genre weights if present.
+pub struct MPDLibrary {
+    pub bliss: Library<Config, Decoder>,
+    pub mpd: MPDPool,
+    /// The genre map as loaded, before matching the library's unknown genres to it
+    genre_map: Option<GenreWeights>,
+    /// Names of the genre map's axes, in the order of the genre weights
+    genre_axes: Vec<String>,
+    config_path: Option<PathBuf>,
+    /// Every song in the bliss library, cleared on update
+    songs: RwLock<Option<Arc<Vec<BlissSong>>>>,
+    /// The genre map with the library's unknown genres matched by their words, cleared on update
+    genre_weights: RwLock<Option<Arc<GenreWeights>>>,
+    /// Genre weights of every song, cleared on update
+    track_weights: RwLock<Option<Arc<TrackWeights>>>,
+    /// Cleared on update
+    feature_stats: RwLock<Option<Arc<FeatureStats>>>,
+    /// Cleared on update
+    mahalanobis: RwLock<Option<Arc<Mahalanobis>>>,
+    /// Refreshed on update, once it's been built
+    knn_index: RwLock<Option<Arc<KnnIndex>>>,
+}
 
+/// MPDLibrary holds the connection to MPD, methods to analyze songs with bliss, and the main `queue_from_song` method
+/// that does the queueing of similar songs.
+impl MPDLibrary {
     /// Build a new MPDLibrary.
     ///
     /// May fail if paths provided don't exist or if an error occurs connecting to MPD.
@@ -315,26 +428,44 @@ impl MPDLibrary {
         config_path: Option<PathBuf>,
         database_path: Option<PathBuf>,
     ) -> Result<Self> {
-        let config = Config::build(mpd_base_path.clone(), config_path, database_path, None)
-            .context("while building bliss Config")?;
+        let config = Config::build(
+            mpd_base_path.clone(),
+            config_path.clone(),
+            database_path,
+            None,
+        )
+        .context("while building bliss Config")?;
         Ok(Self {
             bliss: Library::new(config).context("while building bliss library")?,
-            mpd_conn: Arc::new(Mutex::new(
-                Self::connect_to_mpd().context("while connecting to MPD")?,
-            )),
-            genre_weights: None,
+            mpd: MPDPool::new().context("while connecting to MPD")?,
+            genre_map: None,
+            genre_axes: GENRE_AXES.map(String::from).to_vec(),
+            config_path,
+            songs: RwLock::new(None),
+            genre_weights: RwLock::new(None),
+            track_weights: RwLock::new(None),
+            feature_stats: RwLock::new(None),
+            mahalanobis: RwLock::new(None),
+            knn_index: RwLock::new(None),
         })
     }
 
     fn maybe_retrieve(config_path: Option<PathBuf>) -> Result<Self> {
         let bliss_library: Library<Config, Decoder> =
-            Library::from_config_path(config_path).context("while retrieving bliss library")?;
+            Library::from_config_path(config_path.clone())
+                .context("while retrieving bliss library")?;
         Ok(Self {
             bliss: bliss_library,
-            mpd_conn: Arc::new(Mutex::new(
-                Self::connect_to_mpd().context("while connecting to MPD")?,
-            )),
-            genre_weights: None,
+            mpd: MPDPool::new().context("while connecting to MPD")?,
+            genre_map: None,
+            genre_axes: GENRE_AXES.map(String::from).to_vec(),
+            config_path,
+            songs: RwLock::new(None),
+            genre_weights: RwLock::new(None),
+            track_weights: RwLock::new(None),
+            feature_stats: RwLock::new(None),
+            mahalanobis: RwLock::new(None),
+            knn_index: RwLock::new(None),
         })
     }
 
@@ -371,10 +502,8 @@ impl MPDLibrary {
         let (mut index, chunk_size) = (0, 10000);
         loop {
             let search = self
-                .mpd_conn
-                .lock()
-                .await
-                // .expect("Poisoned lock")
+                .mpd
+                .command()?
                 .search(query, Window::from((index, index + chunk_size)))?;
             if search.is_empty() {
                 break;
@@ -423,11 +552,159 @@ impl MPDLibrary {
 
     /// Update bliss database with new songs from MPD.
     ///
+    /// Writes through its own handle on the bliss database so that it can run while the library is
+    /// shared with the queue daemon or the server.
+    ///
     /// May fail if the database connection is dropped, if the MPD connection is dropped, if the database is corrupted, or if analysis fails. Analysis will typically continue even if individual songs fail.
-    pub async fn update(&mut self) -> Result<()> {
+    pub async fn update(&self) -> Result<()> {
+        self.update_with_progress(|analyzed, total| {
+            println!("Analyzed {analyzed} of {total} new songs")
+        })
+        .await
+    }
+
+    /// Like [MPDLibrary::update], calling `progress` with how many new songs have been analyzed
+    /// and how many there are in total, before analysis starts and after each batch.
+    ///
+    /// May fail in the same ways as [MPDLibrary::update].
+    pub async fn update_with_progress(&self, mut progress: impl FnMut(usize, usize)) -> Result<()> {
         println!("Updating library...");
+        let songs_extra_info = self.get_songs_extra_info().await?;
+        let mut bliss: Library<Config, Decoder> =
+            Library::from_config_path(self.config_path.clone())
+                .context("while opening bliss library for update")?;
+        let songs = self.songs()?;
+        let analyzed: HashSet<&Path> = songs
+            .iter()
+            .map(|song| song.bliss_song.path.as_path())
+            .collect();
+        let new_songs: Vec<(String, ExtraInfo)> = songs_extra_info
+            .iter()
+            .filter(|(path, _)| !analyzed.contains(Path::new(path)))
+            .cloned()
+            .collect();
+        progress(0, new_songs.len());
+        let mut done = 0;
+        for batch in new_songs.chunks(UPDATE_BATCH_SIZE) {
+            bliss.update_library_extra_info(batch.to_vec(), false, false)?;
+            done += batch.len();
+            progress(done, new_songs.len());
+        }
+        // removes songs that are gone and picks up any left to analyze, like songs analyzed by an
+        // older version of bliss
+        bliss.update_library_extra_info(songs_extra_info, true, true)?;
+        // recomputed from the updated library when they're next needed
+        *self.songs.write().expect("Poisoned lock") = None;
+        *self.genre_weights.write().expect("Poisoned lock") = None;
+        *self.track_weights.write().expect("Poisoned lock") = None;
+        *self.feature_stats.write().expect("Poisoned lock") = None;
+        *self.mahalanobis.write().expect("Poisoned lock") = None;
+        // the files' art may have changed too, and a server in another process reads this
+        if let Ok(files) = fs::read_dir(self.art_cache_dir()) {
+            for file in files.flatten() {
+                let _ = fs::remove_file(file.path());
+            }
+        }
+        self.refresh_index()
+            .context("while updating nearest neighbor index")
+    }
+
+    /// Every song in the bliss library, read from the database the first time they're needed
+    /// after each update.
+    ///
+    /// May fail if the bliss library is corrupted.
+    pub fn songs(&self) -> Result<Arc<Vec<BlissSong>>> {
+        if let Some(songs) = self.songs.read().expect("Poisoned lock").as_ref() {
+            return Ok(Arc::clone(songs));
+        }
+        let songs = Arc::new(
+            self.bliss
+                .songs_from_library()
+                .context("while getting bliss library")?,
+        );
+        *self.songs.write().expect("Poisoned lock") = Some(Arc::clone(&songs));
+        Ok(songs)
+    }
+
+    /// Whether a genre map was loaded with [MPDLibrary::get_track_genre_weights].
+    pub fn has_genre_weights(&self) -> bool {
+        self.genre_map.is_some()
+    }
+
+    /// Names of the loaded genre map's axes, or the default axes if none was loaded.
+    pub fn genre_axes(&self) -> &[String] {
+        &self.genre_axes
+    }
+
+    /// The loaded genre map, plus the library's unknown genres matched to it by their words,
+    /// worked out the first time they're needed after each update. `None` if no genre map was
+    /// loaded.
+    ///
+    /// May fail if the bliss library is corrupted.
+    pub fn genre_weights(&self) -> Result<Option<Arc<GenreWeights>>> {
+        let Some(genre_map) = &self.genre_map else {
+            return Ok(None);
+        };
+        if let Some(genre_weights) = self.genre_weights.read().expect("Poisoned lock").as_ref() {
+            return Ok(Some(Arc::clone(genre_weights)));
+        }
+        let mut genre_weights = genre_map.clone();
         self.bliss
-            .update_library_extra_info(self.get_songs_extra_info().await?, true, true)
+            .config
+            .genres
+            .add_word_matches(&mut genre_weights, self.songs()?.iter());
+        let genre_weights = Arc::new(genre_weights);
+        *self.genre_weights.write().expect("Poisoned lock") = Some(Arc::clone(&genre_weights));
+        Ok(Some(genre_weights))
+    }
+
+    /// Genre weights of every song, worked out the first time they're needed after each update.
+    /// Empty if genre weights aren't loaded.
+    ///
+    /// May fail if the bliss library is corrupted.
+    pub fn track_weights(&self) -> Result<Arc<TrackWeights>> {
+        if let Some(track_weights) = self.track_weights.read().expect("Poisoned lock").as_ref() {
+            return Ok(Arc::clone(track_weights));
+        }
+        let track_weights = Arc::new(match self.genre_weights()? {
+            Some(genre_weights) => {
+                track_genre_weights(&genre_weights, &self.bliss.config.genres, &*self.songs()?)
+            }
+            None => TrackWeights::new(),
+        });
+        *self.track_weights.write().expect("Poisoned lock") = Some(Arc::clone(&track_weights));
+        Ok(track_weights)
+    }
+
+    /// A playlist starting with `seeds`, followed by the rest of the library in the order given by
+    /// `sort_by`, like bliss' `playlist_from_custom` but without reading the library again.
+    ///
+    /// May fail if the bliss library is corrupted.
+    pub fn playlist_from_seeds<'a, F, I>(
+        &self,
+        seeds: &[BlissSong],
+        distance: &'a dyn DistanceMetricBuilder,
+        sort_by: F,
+        dedup: bool,
+    ) -> Result<Box<dyn Iterator<Item = BlissSong> + 'a>>
+    where
+        F: Fn(&[BlissSong], &[BlissSong], &'a dyn DistanceMetricBuilder) -> I,
+        I: Iterator<Item = BlissSong> + 'a,
+    {
+        let songs = self.songs()?;
+        let rest: Vec<BlissSong> = sort_by(seeds, &songs, distance)
+            .filter(|song| {
+                !seeds
+                    .iter()
+                    .any(|seed| seed.bliss_song.path == song.bliss_song.path)
+            })
+            .collect();
+        let playlist = seeds.to_vec().into_iter().chain(rest);
+        Ok(if dedup {
+            Box::new(dedup_songs(playlist, distance))
+        } else {
+            Box::new(playlist)
+        })
     }
 
     /// Analyze all songs in MPD's database with bliss.
@@ -482,40 +759,102 @@ impl MPDLibrary {
         )
     }
 
-    /// Get the bliss path of an MPD song by prepending the MPD base path.
-    fn mpd_to_bliss_path(&self, mpd_song: &MPDSong) -> Result<PathBuf> {
-        let file = &mpd_song.file;
-        let path = file.to_string();
-        let path = self.bliss.config.mpd_base_path.join(PathBuf::from(&path));
-        Ok(path)
+    /// The MPD base path, then any alternate base paths.
+    fn base_paths(&self) -> impl Iterator<Item = &PathBuf> {
+        std::iter::once(&self.bliss.config.mpd_base_path)
+            .chain(self.bliss.config.alternate_base_paths.iter())
     }
 
     /// Convert an MPD song to a bliss song, if previously analyzed.
     pub fn mpd_to_bliss_song(&self, mpd_song: &MPDSong) -> Result<Option<BlissSong>> {
-        let path = self
-            .mpd_to_bliss_path(mpd_song)
-            .context("while converting MPD path to bliss path")?;
-        let song = self.bliss.song_from_path(&path.to_string_lossy()).ok();
-        Ok(song)
+        Ok(self.path_to_bliss_song(&mpd_song.file).ok())
     }
 
-    /// Find the bliss song matching a filename, if previously analyzed.
+    /// Find the bliss song matching a filename relative to the MPD base path, if previously
+    /// analyzed. Also finds songs analyzed under one of the alternate base paths.
     pub fn path_to_bliss_song(&self, filename: &str) -> Result<BlissSong> {
-        self.bliss.song_from_path(
-            &self
-                .bliss
+        let mut base_paths = self.base_paths();
+        let base_path = base_paths.next().expect("There's always an MPD base path");
+        let first_try = self
+            .bliss
+            .song_from_path(&base_path.join(filename).to_string_lossy());
+        if first_try.is_ok() {
+            return first_try;
+        }
+        base_paths
+            .find_map(|base_path| {
+                self.bliss
+                    .song_from_path(&base_path.join(filename).to_string_lossy())
+                    .ok()
+            })
+            .map_or(first_try, Ok)
+    }
+
+    /// Find a file relative to the MPD base path on disk, checking the alternate base paths like
+    /// [MPDLibrary::path_to_bliss_song]. Falls back to the MPD base path if it's nowhere.
+    pub fn file_path(&self, filename: &str) -> PathBuf {
+        self.base_paths()
+            .map(|base_path| base_path.join(filename))
+            .find(|path| path.exists())
+            .unwrap_or_else(|| self.bliss.config.mpd_base_path.join(filename))
+    }
+
+    /// Get the path of a bliss song relative to the MPD base path (or the alternate base path it
+    /// was analyzed under). Songs outside all of them keep their full path.
+    pub fn relative_path<'a>(&self, song: &'a BlissSong) -> &'a Path {
+        self.base_paths()
+            .find_map(|base_path| song.bliss_song.path.strip_prefix(base_path).ok())
+            .unwrap_or_else(|| {
+                debug!(
+                    "{} isn't under any known base path",
+                    song.bliss_song.path.display()
+                );
+                &song.bliss_song.path
+            })
+    }
+
+    /// Rewrite the stored path of every song under `from` to be under `to` instead, returning how
+    /// many songs moved. If `from` is the MPD base path, the base path is moved too.
+    ///
+    /// Writes through its own handle on the bliss database, like [MPDLibrary::update].
+    ///
+    /// May fail if the database or the config can't be written to.
+    pub fn relocate(&self, from: &Path, to: &Path) -> Result<usize> {
+        let mut bliss: Library<Config, Decoder> =
+            Library::from_config_path(self.config_path.clone())
+                .context("while opening bliss library for relocation")?;
+        let moved = rewrite_paths(&bliss, from, to)?;
+        if from == bliss.config.mpd_base_path {
+            bliss.config.mpd_base_path = to.to_path_buf();
+            bliss
                 .config
-                .mpd_base_path
-                .join(PathBuf::from(filename))
-                .to_string_lossy(),
-        )
+                .write()
+                .context("while saving the new MPD base path")?;
+        }
+        Ok(moved)
+    }
+
+    /// Queue a song by its path relative to the MPD base path, without playing it.
+    ///
+    /// May fail if the MPD connection is dropped or if MPD doesn't know the song.
+    pub fn push_path(&self, path: &Path) -> Result<()> {
+        self.mpd
+            .command()?
+            .push(MPDSong {
+                file: path.to_string_lossy().to_string(),
+                ..Default::default()
+            })
+            .context("while adding song to MPD queue")?;
+        Ok(())
     }
 
     /// Convert a bliss song to an MPD song.
+    ///
+    /// May fail if the song isn't under the MPD base path or any alternate base path.
     pub fn bliss_song_to_mpd(&self, song: &BlissSong) -> Result<MPDSong> {
-        let path = song.bliss_song.path.to_owned();
-        let path = path
-            .strip_prefix(&*self.bliss.config.mpd_base_path.to_string_lossy())
+        let path = self
+            .base_paths()
+            .find_map(|base_path| song.bliss_song.path.strip_prefix(base_path).ok())
             .context("while stripping prefix from bliss path")?;
         Ok(MPDSong {
             file: path.to_string_lossy().to_string(),
@@ -523,9 +862,9 @@ impl MPDLibrary {
         })
     }
 
-    /// Retrieve the currently playing song, or wait for one to become available. Needs exclusive access to the MPD connection.
+    /// Retrieve the currently playing song, or wait for one to become available. Needs exclusive access to the idle connection.
     pub async fn get_current_song(&self) -> Result<MPDSong> {
-        let current_song = self.mpd_conn.lock().await.currentsong();
+        let current_song = self.mpd.command()?.currentsong();
         match current_song {
             Ok(song) => {
                 if let Some(song) = song {
@@ -534,8 +873,8 @@ impl MPDLibrary {
                     println!("Start playing a song...");
                     loop {
                         let next_event = self
-                            .mpd_conn
-                            .lock()
+                            .mpd
+                            .idle()
                             .await
                             .wait(&[mpd::Subsystem::Queue])
                             .context("while waiting on events from MPD")?;
@@ -551,7 +890,7 @@ impl MPDLibrary {
         }
     }
 
-    fn get_bliss_similarity(&self, next_song: &BlissSong, original_song: &BlissSong) -> f32 {
+    pub fn get_bliss_similarity(&self, next_song: &BlissSong, original_song: &BlissSong) -> f32 {
         debug!(
             "Original song features: {:?}",
             original_song.bliss_song.analysis.as_arr1()
@@ -566,18 +905,20 @@ impl MPDLibrary {
         )
     }
 
-    fn get_genre_similarity(
+    pub fn get_genre_similarity(
         &self,
         next_song: &BlissSong,
         original_song: &BlissSong,
     ) -> Result<f32> {
-        let original_genre_weights = self.genre_weights.as_ref().ok_or(anyhow!(
+        let original_genre_weights = &self.genre_weights()?.ok_or(anyhow!(
             "Genre weights not found, likely forgot to call `get_track_genre_weights`"
         ))?;
+        let genre_config = &self.bliss.config.genres;
         Ok(100.0
             - euclidean_distance(
                 &arr1(&collapse_genres(
                     original_genre_weights,
+                    genre_config,
                     original_song
                         .bliss_song
                         .genre
@@ -586,6 +927,7 @@ impl MPDLibrary {
                 )),
                 &arr1(&collapse_genres(
                     original_genre_weights,
+                    genre_config,
                     next_song
                         .bliss_song
                         .genre
@@ -595,17 +937,16 @@ impl MPDLibrary {
             ) * 100.0)
     }
 
-    fn add_next_song_from_playlist<P>(
+    fn add_next_song_from_playlist(
         &self,
-        playlist: &mut P,
-        mpd_conn: &mut MutexGuard<Client<MPDStream>>,
+        playlist: &mut Playlist,
+        mpd_conn: &mut Client<MPDStream>,
         history: &mut Vec<String>,
         original_song: &BlissSong,
-    ) -> Result<()>
-    where
-        P: Iterator<Item = BlissSong>,
-    {
-        let next_song_object = playlist.next();
+        daemon: &DaemonState,
+    ) -> Result<()> {
+        let not_excluded = |song: &BlissSong| !daemon.is_excluded(self.relative_path(song));
+        let next_song_object = playlist.next_song(not_excluded);
         let next_song = next_song_object
             .as_ref()
             .ok_or(anyhow!("while getting next song from bliss"))?;
@@ -613,6 +954,16 @@ impl MPDLibrary {
             .bliss_song_to_mpd(next_song)
             .context("while converting bliss path to MPD path")?;
         history.push(mpd_song.file.clone());
+        let next_daemon_song = DaemonSong::from_bliss(next_song, self.relative_path(next_song));
+        daemon.push_history(next_daemon_song.clone());
+        daemon.set_upcoming(
+            playlist
+                .upcoming(UPCOMING_LEN, not_excluded)
+                .map(|song| DaemonSong::from_bliss(song, self.relative_path(song)))
+                .collect(),
+        );
+        let bliss_distance = self.get_bliss_similarity(next_song, original_song);
+        let genre_similarity = self.get_genre_similarity(next_song, original_song).ok();
         info!(
             "Bliss distance from next song ({}) to pin ({}): {:.2} units",
             next_song
@@ -625,7 +976,7 @@ impl MPDLibrary {
                 .title
                 .as_ref()
                 .unwrap_or(&"Unknown".into()),
-            self.get_bliss_similarity(next_song, original_song)
+            bliss_distance
         );
         info!(
             "Next song genres: {}",
@@ -635,7 +986,7 @@ impl MPDLibrary {
                 .as_ref()
                 .unwrap_or(&"Unknown".into())
         );
-        if let Ok(genre_similarity) = self.get_genre_similarity(next_song, original_song) {
+        if let Some(genre_similarity) = genre_similarity {
             info!("Genre similarity: {:?}", genre_similarity);
         }
         info!("Popularity: {}", next_song.extra_info.popularity);
@@ -647,6 +998,13 @@ impl MPDLibrary {
                 "Error while pushing song {} to MPD queue, skipping: {e}",
                 title.clone().unwrap_or("Unknown".to_string())
             );
+        } else {
+            daemon.publish(DaemonEvent::SongQueued {
+                song: next_daemon_song,
+                bliss_distance,
+                genre_similarity,
+                popularity: next_song.extra_info.popularity,
+            });
         }
         debug!("Queued song {}", title.unwrap_or(filename),);
         Ok(())
@@ -666,7 +1024,7 @@ impl MPDLibrary {
         I: Iterator<Item = BlissSong> + 'a,
     {
         let mut mpd_conn = self.mpd_conn.lock().expect("Poisoned lock");
-        let path = self.bliss.config.mpd_base_path.join(&song.file);
+        let path = self.file_path(&song.file);
         let mut playlist = self
             .bliss
             .playlist_from_custom(&[&path.to_string_lossy().clone()], distance, sort_by, dedup)
@@ -692,22 +1050,26 @@ impl MPDLibrary {
     /// many recommendations will be queued up at a time. The distance metric can be customized,
     /// as well as the sort function. A filter function can optionally be provided. Use `keep_queue`
     /// to set the pin whenever a new song(s) is queued without immediately overwriting the queue --
-    /// useful for queueing playlists and generating recommendations at the end.
+    /// useful for queueing playlists and generating recommendations at the end. The pin, queued
+    /// songs, and upcoming recommendations are reported to `daemon`. Bliss playlists pass their
+    /// metric as `index_metric`, so they can come from the nearest neighbor index instead of
+    /// sorting the whole library.
     ///
     /// May fail if the database connection is dropped, if bliss fails to create a playlist, or if
     /// the song passed in has not been analyzed.
     #[allow(clippy::too_many_arguments)]
     pub async fn queue_from_song<'a, F, G>(
-        &mut self,
+        &self,
         song: &MPDSong,
         queue_length: u32,
         distance: &'a (dyn DistanceMetricBuilder + Sync),
         sort_by: F,
         mut filter_by: Option<G>,
         dedup: bool,
+        index_metric: Option<(Metric, Normalization)>,
         keep_queue: bool,
         timestamp: Instant,
-        update_on_next_loop: Arc<AtomicBool>,
+        daemon: &DaemonState,
     ) -> Result<MPDSong>
     where
         F: for<'c, 'd, 'e> Fn(
@@ -717,10 +1079,15 @@ impl MPDLibrary {
         ) -> Box<dyn Iterator<Item = BlissSong> + 'e>,
         G: for<'b> FnMut(&'b BlissSong, &'b BlissSong) -> bool,
     {
-        let mut mpd_conn = self.mpd_conn.lock().await;
-        let path = self.bliss.config.mpd_base_path.join(&song.file);
+        let update_on_next_loop = daemon.update_flag();
+        let mut idle_conn = self.mpd.idle().await;
+        let mut mpd_conn = self.mpd.command()?;
         let bliss_song = self.path_to_bliss_song(&song.file)?;
         info!("Pin popularity: {}", bliss_song.extra_info.popularity);
+        daemon.set_pin(DaemonSong::from_bliss(
+            &bliss_song,
+            self.relative_path(&bliss_song),
+        ));
         let filter = |s: &BlissSong| {
             if let Some(ref mut filter_fn) = filter_by {
                 filter_fn(s, &bliss_song)
@@ -728,14 +1095,29 @@ impl MPDLibrary {
                 true
             }
         };
-        let mut playlist = self
-            .bliss
-            .playlist_from_custom(&[&path.to_string_lossy().clone()], distance, sort_by, dedup)
-            .context("while building bliss playlist")?
-            .filter(filter)
-            .skip(1)
-            .collect::<Vec<_>>()
-            .into_iter();
+        let nearest = match index_metric {
+            Some((metric, normalization)) => self
+                .nearest(std::slice::from_ref(&bliss_song), metric, normalization)
+                .context("while searching nearest neighbor index")?,
+            None => None,
+        };
+        let candidates: Box<dyn Iterator<Item = BlissSong> + 'a> = match nearest {
+            Some(nearest) if dedup => Box::new(dedup_songs(nearest, distance)),
+            Some(nearest) => Box::new(nearest),
+            None => self
+                .playlist_from_seeds(std::slice::from_ref(&bliss_song), distance, sort_by, dedup)
+                .context("while building bliss playlist")?,
+        };
+        // the pin usually comes first, but a song with the same analysis can tie with it
+        let pin_path = bliss_song.bliss_song.path.clone();
+        let mut playlist = Playlist {
+            upcoming: VecDeque::new(),
+            rest: Box::new(
+                candidates
+                    .filter(move |s| s.bliss_song.path != pin_path)
+                    .filter(filter),
+            ),
+        };
 
         let current_pos = song
             .place
@@ -760,6 +1142,7 @@ impl MPDLibrary {
             &mut playlist,
             &mut history,
             queue_length,
+            daemon,
         )?;
 
         info!(
@@ -768,37 +1151,81 @@ impl MPDLibrary {
         );
 
         let mut last_queue = mpd_conn.queue()?;
+        drop(mpd_conn); // don't keep a command connection checked out while idling
 
         loop {
             if update_on_next_loop.load(Ordering::SeqCst) {
-                drop(mpd_conn); // release lock to allow update() to acquire it
-                self.update().await?;
-                mpd_conn = self.mpd_conn.lock().await;
+                daemon.publish(DaemonEvent::UpdateStarted);
+                let progress = |analyzed, total| {
+                    info!("Analyzed {analyzed} of {total} new songs");
+                    daemon.publish(DaemonEvent::UpdateProgress { analyzed, total });
+                };
+                if let Err(e) = self.update_with_progress(progress).await {
+                    daemon.publish(DaemonEvent::UpdateFailed {
+                        error: e.to_string(),
+                    });
+                    return Err(e);
+                }
                 println!("Library updated!");
+                daemon.publish(DaemonEvent::UpdateFinished);
                 update_on_next_loop.store(false, Ordering::SeqCst);
             }
-            let next_event = match mpd_conn
-                .wait(&[mpd::Subsystem::Queue])
+            let next_event = match idle_conn
+                .wait(&[mpd::Subsystem::Queue, mpd::Subsystem::Message])
                 .context("while waiting on events from MPD")
             {
                 Ok(events) => events,
                 Err(e) => {
                     println!("MPD connection lost, waiting to reconnect... (error: {e})");
-                    Self::reconnect_to_mpd(&mut mpd_conn);
+                    daemon.publish(DaemonEvent::MpdDisconnected {
+                        error: e.to_string(),
+                    });
+                    self.mpd.reconnect_idle(&mut idle_conn).await;
+                    daemon.publish(DaemonEvent::MpdReconnected);
                     self.fill_song_queue(
-                        &mut mpd_conn,
+                        &mut self.mpd.command()?,
                         &bliss_song,
                         &mut playlist,
                         &mut history,
                         queue_length,
+                        daemon,
                     )?; // catch up on changes while disconnected
                     continue;
                 }
             };
 
-            if !next_event.is_empty() && next_event[0] == mpd::Subsystem::Queue {
+            if next_event.contains(&mpd::Subsystem::Message) {
+                // woken by the server (see MPDPool::wake), read the messages so they don't pile
+                // up. Updates are picked up at the top of the loop.
+                idle_conn
+                    .readmessages()
+                    .context("while reading messages from MPD")?;
+            }
+
+            if next_event.contains(&mpd::Subsystem::Queue) {
+                let mut mpd_conn = self.mpd.command()?;
                 let status = mpd_conn.status()?;
                 let new_queue = mpd_conn.queue()?;
+                if let Some(requested_pin) = daemon.requested_pin()
+                    && let Some(pos) = new_queue
+                        .iter()
+                        .rposition(|song| Path::new(&song.file) == requested_pin)
+                {
+                    // requested over the server, which has already added it to the queue
+                    daemon.clear_requested_pin();
+                    mpd_conn
+                        .switch(pos as u32)
+                        .context("while playing requested pin")?;
+                    let new_pin = mpd_conn
+                        .currentsong()
+                        .context("while getting current song from MPD")?
+                        .ok_or(anyhow!("while getting current song from MPD"))?;
+                    println!(
+                        "Restarting with requested pin: {}",
+                        new_pin.title.as_ref().unwrap_or(&new_pin.file)
+                    );
+                    return Ok(new_pin);
+                }
                 if new_queue.len() != last_queue.len() {
                     // don't restart if the new queue is the old queue plus any of the songs from the generated playlist, otherwise use the currently playing song as the new pin
                     let last_queue_songs: Vec<&MPDSong> = last_queue.iter().collect();
@@ -860,6 +1287,7 @@ impl MPDLibrary {
                         &mut mpd_conn,
                         &mut history,
                         &bliss_song,
+                        daemon,
                     )?;
                 }
             }
@@ -868,11 +1296,12 @@ impl MPDLibrary {
 
     fn fill_song_queue(
         &self,
-        mpd_conn: &mut MutexGuard<Client<MPDStream>>,
+        mpd_conn: &mut Client<MPDStream>,
         bliss_song: &BlissSong,
-        playlist: &mut std::vec::IntoIter<BlissSong>,
+        playlist: &mut Playlist,
         history: &mut Vec<String>,
         queue_length: u32,
+        daemon: &DaemonState,
     ) -> Result<()> {
         let status = mpd_conn.status()?;
         let queue_pos = status
@@ -882,7 +1311,7 @@ impl MPDLibrary {
         let mut queue_diff = queue_length.saturating_sub(status.queue_len - queue_pos + 1);
         if (status.queue_len <= queue_length) || (queue_length > queue_diff) {
             while queue_diff > 0 {
-                self.add_next_song_from_playlist(playlist, mpd_conn, history, bliss_song)?;
+                self.add_next_song_from_playlist(playlist, mpd_conn, history, bliss_song, daemon)?;
                 queue_diff -= 1;
             }
         }
@@ -896,42 +1325,158 @@ impl MPDLibrary {
     pub fn get_track_genre_weights(
         &mut self,
         genres_path: Option<PathBuf>,
-    ) -> Result<TrackWeights> {
-        let all_bliss_songs: Vec<BlissSong> = self
-            .bliss
-            .songs_from_library()
-            .context("while getting bliss library")?;
+    ) -> Result<Arc<TrackWeights>> {
+        let all_bliss_songs = self.songs()?;
 
-        let genre_weights: GenreWeights = serde_json::from_reader(
-            File::open(genres_path.unwrap_or("./genres.json".into()))
-                .context("while opening genre weights json")?,
-        )
-        .context("while parsing genre weights json")?;
+        let genre_config = &self.bliss.config.genres;
+        let (genre_axes, genre_map) =
+            genre_config.load_genre_weights(&self.genres_path(genres_path))?;
 
-        self.genre_weights = Some(genre_weights.clone());
-
-        let mut genre_weights_by_track_path: TrackWeights = HashMap::new();
-        for song in all_bliss_songs {
-            if let Some(genres) = song.bliss_song.genre {
-                genre_weights_by_track_path
-                    .entry(song.bliss_song.path)
-                    .or_insert(collapse_genres(&genre_weights, genres));
+        let unknown_genres =
+            GenreStats::collect(&genre_map, genre_config, all_bliss_songs.iter()).unknown_genres;
+        let known = genre_map.len();
+        self.genre_axes = genre_axes;
+        self.genre_map = Some(genre_map);
+        *self.genre_weights.write().expect("Poisoned lock") = None;
+        *self.track_weights.write().expect("Poisoned lock") = None;
+        if !unknown_genres.is_empty() {
+            println!(
+                "{} genres aren't in the genre map, most common: {} (see `worf genres stats`)",
+                unknown_genres.len(),
+                unknown_genres
+                    .iter()
+                    .take(10)
+                    .map(|count| format!("{} ({})", count.genre, count.songs))
+                    .join(", ")
+            );
+            let matched = self
+                .genre_weights()?
+                .map_or(0, |genre_weights| genre_weights.len() - known);
+            if matched > 0 {
+                println!("{matched} of them were matched to known genres by their words");
             }
         }
-        Ok(genre_weights_by_track_path)
+        self.track_weights()
+    }
+
+    /// The genre map to load: `genres_path` if given, otherwise genres.json next to the config
+    /// file, or in the working directory if there isn't one there.
+    fn genres_path(&self, genres_path: Option<PathBuf>) -> PathBuf {
+        if let Some(genres_path) = genres_path {
+            return genres_path;
+        }
+        let in_config_dir = self
+            .bliss
+            .config
+            .base_config
+            .config_path
+            .with_file_name(GENRES_FILE_NAME);
+        if in_config_dir.exists() {
+            in_config_dir
+        } else {
+            PathBuf::from(GENRES_FILE_NAME)
+        }
     }
 
-    /// Retrieve album art for a song from MPD.
+    /// Where to keep files derived from the library, next to the bliss database.
+    pub fn cache_dir(&self) -> PathBuf {
+        self.bliss
+            .config
+            .base_config
+            .database_path
+            .with_file_name("worf_cache")
+    }
+
+    /// Where the server keeps album art, emptied by [MPDLibrary::update].
+    pub fn art_cache_dir(&self) -> PathBuf {
+        self.cache_dir().join("album_art")
+    }
+
+    /// Retrieve album art for a song from MPD, falling back to the picture embedded in the file
+    /// if MPD doesn't have any. `None` if there's no art anywhere.
     ///
-    /// May fail if MPD connection is dropped or song doesn't exist in MPD database.
-    pub async fn get_album_art(&self, song: &MPDSong) -> Result<Vec<u8>> {
-        let mut mpd_conn = self.mpd_conn.lock().await;
-        match mpd_conn
-            .albumart(&song)
-            .context("while getting album art from MPD")
-        {
+    /// May fail if the MPD connection is dropped.
+    pub async fn get_album_art(&self, song: &MPDSong) -> Result<Option<Vec<u8>>> {
+        let mut mpd_conn = self.mpd.command()?;
+        let from_mpd = match mpd_conn.albumart(&song) {
+            Ok(album_art) => Ok(album_art),
+            Err(_) => mpd_conn.readpicture(&song),
+        };
+        match from_mpd {
+            Ok(album_art) if !album_art.is_empty() => return Ok(Some(album_art)),
+            // MPD answered, it just has nothing for this song
+            Ok(_) | Err(mpd::error::Error::Parse(_)) | Err(mpd::error::Error::Server(_)) => {}
+            Err(e) => {
+                // don't hand a broken connection back to the pool
+                mpd_conn.discard();
+                return Err(e).context("while getting album art from MPD");
+            }
+        }
+        // decoding can take a while, so don't hold on to the connection or the async worker
+        drop(mpd_conn);
+        let path = self.file_path(&song.file);
+        let decode_path = path.clone();
+        let embedded =
+            rocket::tokio::task::spawn_blocking(move || Decoder::embedded_picture(&decode_path))
+                .await
+                .context("while reading embedded album art")?;
+        match embedded {
             Ok(album_art) => Ok(album_art),
-            Err(_) => Ok(mpd_conn.readpicture(&song)?),
+            Err(e) => {
+                warn!(
+                    "Couldn't read embedded album art from {}: {e}",
+                    path.display()
+                );
+                Ok(None)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bliss_audio::{Analysis, FeaturesVersion};
+
+    #[test]
+    fn relocation_only_moves_whole_path_prefixes() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("worf-relocate-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let config = Config::build(
+            PathBuf::from("/music"),
+            Some(dir.join("config.json")),
+            Some(dir.join("songs.db")),
+            None,
+        )?;
+        let mut bliss: Library<Config, Decoder> = Library::new(config)?;
+        for path in ["/music/a.flac", "/music/album/b.flac", "/music2/c.flac"] {
+            bliss.store_song(&BlissSong {
+                bliss_song: BareBlissSong {
+                    path: PathBuf::from(path),
+                    analysis: Analysis::new(
+                        vec![0.0; NUM_BLISS_FEATURES],
+                        FeaturesVersion::LATEST,
+                    )?,
+                    ..Default::default()
+                },
+                extra_info: ExtraInfo::default(),
+            })?;
+        }
+
+        let moved = rewrite_paths(&bliss, Path::new("/music/"), Path::new("/mnt/music"))?;
+        let songs: Vec<BlissSong> = bliss.songs_from_library()?;
+        let paths: HashSet<PathBuf> = songs.into_iter().map(|song| song.bliss_song.path).collect();
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(moved, 2);
+        assert_eq!(
+            paths,
+            HashSet::from([
+                PathBuf::from("/mnt/music/a.flac"),
+                PathBuf::from("/mnt/music/album/b.flac"),
+                PathBuf::from("/music2/c.flac"),
+            ])
+        );
+        Ok(())
+    }
+}
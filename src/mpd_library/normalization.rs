@@ -0,0 +1,272 @@
+//! Rescaling bliss features by how they're spread across the library, so features with a wide
+//! range don't decide every distance on their own.
+//!
+//! The statistics are computed once and kept until the library is updated.
+
+use super::{MPDLibrary, Metric};
+use anyhow::Result;
+use bliss_audio::playlist::{DistanceMetric, DistanceMetricBuilder};
+use ndarray::Array1;
+use rocket::form::FromFormField;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// How to rescale bliss features before comparing them.
+#[derive(
+    clap::ValueEnum,
+    FromFormField,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// Compare the features as bliss computes them
+    #[default]
+    None,
+    /// Subtract the library mean and divide by the standard deviation
+    ZScore,
+    /// Replace each value with the fraction of the library below it
+    Rank,
+}
+
+impl Normalization {
+    pub fn name(self) -> &'static str {
+        match self {
+            Normalization::None => "none",
+            Normalization::ZScore => "z_score",
+            Normalization::Rank => "rank",
+        }
+    }
+
+    /// Make a bliss metric compare normalized analyses. `stats` is only called if there's
+    /// something to normalize. Mahalanobis distance already accounts for how spread out each
+    /// feature is, so it's left as it is.
+    ///
+    /// May fail if the feature statistics can't be computed.
+    pub fn apply<'a>(
+        self,
+        metric: Metric,
+        builder: &'a (dyn DistanceMetricBuilder + Sync),
+        stats: impl FnOnce() -> Result<Arc<FeatureStats>>,
+    ) -> Result<Normalized<'a>> {
+        let stats = if self == Normalization::None || metric == Metric::Mahalanobis {
+            None
+        } else {
+            Some(stats()?)
+        };
+        Ok(Normalized {
+            normalization: self,
+            stats,
+            inner: builder,
+        })
+    }
+}
+
+/// Per-feature statistics over every song in the library. Analyses with NaN or infinite features
+/// are left out, like they are from the search index.
+pub struct FeatureStats {
+    mean: Array1<f32>,
+    deviation: Array1<f32>,
+    /// Every song's value of each feature, sorted
+    sorted: Vec<Vec<f32>>,
+}
+
+impl FeatureStats {
+    pub fn new(analyses: &[Array1<f32>]) -> Self {
+        let analyses: Vec<_> = analyses
+            .iter()
+            .filter(|analysis| analysis.iter().all(|value| value.is_finite()))
+            .collect();
+        let features = analyses.first().map_or(0, |analysis| analysis.len());
+        let mut sorted = vec![Vec::with_capacity(analyses.len()); features];
+        for analysis in &analyses {
+            for (values, &value) in sorted.iter_mut().zip(analysis.iter()) {
+                values.push(value);
+            }
+        }
+        for values in &mut sorted {
+            values.sort_by(f32::total_cmp);
+        }
+        let count = analyses.len().max(1) as f32;
+        let mean: Array1<f32> = sorted
+            .iter()
+            .map(|values| values.iter().sum::<f32>() / count)
+            .collect();
+        let deviation = sorted
+            .iter()
+            .zip(&mean)
+            .map(|(values, mean)| {
+                let variance = values
+                    .iter()
+                    .map(|value| (value - mean).powi(2))
+                    .sum::<f32>()
+                    / count;
+                // constant features stay constant instead of dividing by zero
+                if variance > 0.0 { variance.sqrt() } else { 1.0 }
+            })
+            .collect();
+        Self {
+            mean,
+            deviation,
+            sorted,
+        }
+    }
+
+    pub fn normalize(&self, normalization: Normalization, analysis: &Array1<f32>) -> Array1<f32> {
+        analysis
+            .iter()
+            .enumerate()
+            .map(|(feature, &value)| self.normalize_value(normalization, feature, value))
+            .collect()
+    }
+
+    /// Normalize a single feature. Never changes which of two values is bigger.
+    pub fn normalize_value(&self, normalization: Normalization, feature: usize, value: f32) -> f32 {
+        match normalization {
+            Normalization::None => value,
+            Normalization::ZScore => (value - self.mean[feature]) / self.deviation[feature],
+            Normalization::Rank => {
+                let values = &self.sorted[feature];
+                // the midpoint of any ties, so equal values land in the middle of their run
+                let below = values.partition_point(|&other| other < value);
+                let not_above = values.partition_point(|&other| other <= value);
+                (below + not_above) as f32 / 2.0 / values.len().max(1) as f32
+            }
+        }
+    }
+}
+
+/// A distance metric that normalizes analyses before handing them to another one, or hands them
+/// over as they are without statistics.
+pub struct Normalized<'a> {
+    normalization: Normalization,
+    stats: Option<Arc<FeatureStats>>,
+    inner: &'a (dyn DistanceMetricBuilder + Sync),
+}
+
+impl DistanceMetricBuilder for Normalized<'_> {
+    fn build<'b>(&'b self, vectors: &[Array1<f32>]) -> Box<dyn DistanceMetric + 'b> {
+        let Some(stats) = &self.stats else {
+            return self.inner.build(vectors);
+        };
+        let vectors: Vec<_> = vectors
+            .iter()
+            .map(|vector| stats.normalize(self.normalization, vector))
+            .collect();
+        Box::new(NormalizedMetric {
+            normalization: self.normalization,
+            stats,
+            inner: self.inner.build(&vectors),
+        })
+    }
+}
+
+struct NormalizedMetric<'a> {
+    normalization: Normalization,
+    stats: &'a FeatureStats,
+    inner: Box<dyn DistanceMetric + 'a>,
+}
+
+impl DistanceMetric for NormalizedMetric<'_> {
+    fn distance(&self, vector: &Array1<f32>) -> f32 {
+        self.inner
+            .distance(&self.stats.normalize(self.normalization, vector))
+    }
+}
+
+impl MPDLibrary {
+    /// Feature statistics over the whole library, computed the first time they're needed after
+    /// each update.
+    ///
+    /// May fail if the bliss library is corrupted.
+    pub fn feature_stats(&self) -> Result<Arc<FeatureStats>> {
+        if let Some(stats) = self.feature_stats.read().expect("Poisoned lock").as_ref() {
+            return Ok(Arc::clone(stats));
+        }
+        let analyses: Vec<Array1<f32>> = self
+            .songs()?
+            .iter()
+            .map(|song| song.bliss_song.analysis.as_arr1())
+            .collect();
+        let stats = Arc::new(FeatureStats::new(&analyses));
+        *self.feature_stats.write().expect("Poisoned lock") = Some(Arc::clone(&stats));
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use bliss_audio::playlist::euclidean_distance;
+    use ndarray::arr1;
+
+    fn stats() -> FeatureStats {
+        FeatureStats::new(&[arr1(&[1.0, 5.0]), arr1(&[3.0, 5.0]), arr1(&[5.0, 5.0])])
+    }
+
+    #[test]
+    fn z_score() {
+        let stats = stats();
+        let deviation = (8.0f32 / 3.0).sqrt();
+        let normalized = stats.normalize(Normalization::ZScore, &arr1(&[1.0, 7.0]));
+        assert!((normalized[0] + 2.0 / deviation).abs() < 1e-6);
+        // constant features are only shifted
+        assert!((normalized[1] - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rank() {
+        let stats = stats();
+        let rank = |feature, value| stats.normalize_value(Normalization::Rank, feature, value);
+        assert_eq!(rank(0, 0.0), 0.0);
+        assert_eq!(rank(0, 1.0), 1.0 / 6.0);
+        assert_eq!(rank(0, 3.0), 0.5);
+        assert_eq!(rank(0, 4.0), 2.0 / 3.0);
+        assert_eq!(rank(0, 9.0), 1.0);
+        // ties land in the middle
+        assert_eq!(rank(1, 5.0), 0.5);
+    }
+
+    #[test]
+    fn non_finite_analyses_are_skipped() {
+        let with_nan = FeatureStats::new(&[
+            arr1(&[1.0, 5.0]),
+            arr1(&[f32::NAN, 5.0]),
+            arr1(&[3.0, 5.0]),
+            arr1(&[7.0, f32::INFINITY]),
+            arr1(&[5.0, 5.0]),
+        ]);
+        let stats = stats();
+        assert_eq!(with_nan.mean, stats.mean);
+        assert_eq!(with_nan.deviation, stats.deviation);
+        assert_eq!(with_nan.sorted, stats.sorted);
+    }
+
+    #[test]
+    fn statistics_only_when_needed() {
+        let no_stats = || bail!("Statistics weren't needed");
+        let analysis = arr1(&[1.0, 5.0]);
+        for (normalization, metric) in [
+            (Normalization::None, Metric::Euclidean),
+            (Normalization::ZScore, Metric::Mahalanobis),
+        ] {
+            let normalized = normalization
+                .apply(metric, &euclidean_distance, no_stats)
+                .unwrap();
+            let seeds = [analysis.clone()];
+            assert_eq!(normalized.build(&seeds).distance(&arr1(&[4.0, 9.0])), 5.0);
+        }
+        assert!(
+            Normalization::Rank
+                .apply(Metric::Euclidean, &euclidean_distance, no_stats)
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! A small pool of MPD connections.
+//!
+//! MPD won't answer commands on a connection that's sitting in `idle`, so the queue daemon gets a
+//! dedicated idle connection and everything else (updates, album art, the server) checks out
+//! short-lived command connections that are handed back when dropped.
+//!
+//! The idle connection also listens on a client-to-client channel, so the server can wake the
+//! daemon with [MPDPool::wake] for requests that don't otherwise make MPD report anything.
+
+use super::MPDStream;
+use anyhow::{Context, Result, anyhow};
+use mpd::Client;
+use mpd::message::Channel;
+use rocket::tokio::sync::{Mutex, MutexGuard};
+use rocket::tokio::{task, time};
+use std::env;
+use std::net::TcpStream;
+use std::ops::{Deref, DerefMut};
+#[cfg(target_os = "android")]
+use std::os::android::net::SocketAddrExt;
+#[cfg(target_os = "linux")]
+use std::os::linux::net::SocketAddrExt;
+use std::os::unix::net::{SocketAddr, UnixStream};
+use std::time::Duration;
+
+/// How many command connections to keep around after they're returned.
+const MAX_SPARE_CONNECTIONS: usize = 4;
+/// Reconnection attempts back off up to 2^this seconds apart.
+const MAX_BACKOFF_EXPONENT: u32 = 6;
+/// The client-to-client channel the idle connection is subscribed to.
+const WAKE_CHANNEL: &str = "worf_wake";
+
+fn wake_channel() -> Channel {
+    Channel::new(WAKE_CHANNEL).expect("Wake channel name is valid")
+}
+
+/// Connect the idle connection and subscribe it to the wake channel.
+fn connect_idle() -> Result<Client<MPDStream>> {
+    let mut client = connect_to_mpd()?;
+    client
+        .subscribe(wake_channel())
+        .context("while subscribing to the wake channel")?;
+    Ok(client)
+}
+
+/// Connect to MPD using `MPD_HOST` and `MPD_PORT`, the same way `mpc` does.
+fn connect_to_mpd() -> Result<Client<MPDStream>> {
+    let (password, mpd_host) = match env::var("MPD_HOST") {
+        Ok(h) => match h.split_once('@') {
+            // Only host
+            None => (None, h),
+            // Unix socket
+            Some(("", _)) => (None, h),
+            // Password + host
+            Some((password, host)) => (Some(password.to_owned()), host.to_owned()),
+        },
+        Err(_) => {
+            eprintln!("MPD_HOST not set in the environment, defaulting to 127.0.0.1");
+            (None, String::from("127.0.0.1"))
+        }
+    };
+
+    let mpd_port = match env::var("MPD_PORT") {
+        Ok(p) => p
+            .parse::<u16>()
+            .context("while trying to coerce MPD_PORT to int")?,
+        Err(_) => {
+            // Would prefer to defer to MPD for the default port but the mpd crate doesn't have a method for doing that
+            eprintln!("MPD_PORT not set in the environment, using default of 6600");
+            6600
+        }
+    };
+
+    let mut client: Client<MPDStream> = {
+        if mpd_host.starts_with('/') || mpd_host.starts_with('~') {
+            Client::new(MPDStream::Unix(
+                UnixStream::connect(mpd_host).context("while connecting to Unix stream")?,
+            ))
+            .context("while connecting to MPD")?
+        } else if mpd_host.starts_with('@') {
+            let addr = SocketAddr::from_abstract_name(
+                mpd_host
+                    .split_once('@')
+                    .ok_or(anyhow!("No socket name provided"))?
+                    .1,
+            )?;
+            Client::new(MPDStream::Unix(
+                UnixStream::connect_addr(&addr).context("while connecting to Unix stream")?,
+            ))
+            .context("while connecting to MPD")?
+        } else {
+            Client::new(MPDStream::Tcp(
+                TcpStream::connect(format!("{}:{}", mpd_host, mpd_port))
+                    .context("while connecting to TCP stream")?,
+            ))
+            .context("while connecting to MPD")?
+        }
+    };
+    if let Some(pass) = password {
+        client.login(&pass).context("while logging in to MPD")?;
+    }
+    Ok(client)
+}
+
+/// Keep trying to connect the idle connection with exponential backoff until it succeeds.
+async fn connect_with_backoff() -> Client<MPDStream> {
+    let mut counter = 1;
+    loop {
+        // connecting blocks, so keep it off the runtime's worker threads
+        match task::spawn_blocking(connect_idle).await {
+            Ok(Ok(new_conn)) => {
+                println!("Reconnected to MPD!");
+                return new_conn;
+            }
+            Ok(Err(_)) | Err(_) => {
+                let backoff = 2_u64.pow(counter.min(MAX_BACKOFF_EXPONENT));
+                println!("Reconnecting in {backoff} seconds...");
+                time::sleep(Duration::from_secs(backoff)).await;
+                counter += 1;
+            }
+        }
+    }
+}
+
+/// The dedicated idle connection plus a stack of spare command connections.
+pub struct MPDPool {
+    idle_conn: Mutex<Client<MPDStream>>,
+    command_conns: std::sync::Mutex<Vec<Client<MPDStream>>>,
+}
+
+/// A command connection checked out of an [MPDPool]. Goes back to the pool when dropped unless
+/// [PooledClient::discard] is called.
+pub struct PooledClient<'a> {
+    client: Option<Client<MPDStream>>,
+    pool: &'a MPDPool,
+}
+
+impl Deref for PooledClient<'_> {
+    type Target = Client<MPDStream>;
+    fn deref(&self) -> &Self::Target {
+        self.client
+            .as_ref()
+            .expect("PooledClient used after being discarded")
+    }
+}
+
+impl DerefMut for PooledClient<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.client
+            .as_mut()
+            .expect("PooledClient used after being discarded")
+    }
+}
+
+impl Drop for PooledClient<'_> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            let mut spares = self.pool.command_conns.lock().expect("Poisoned lock");
+            if spares.len() < MAX_SPARE_CONNECTIONS {
+                spares.push(client);
+            }
+        }
+    }
+}
+
+impl PooledClient<'_> {
+    /// Drop the connection instead of returning it to the pool, e.g. after an IO error.
+    pub fn discard(mut self) {
+        self.client = None;
+    }
+}
+
+impl MPDPool {
+    /// Connect the idle connection. Command connections are opened lazily.
+    ///
+    /// May fail if MPD can't be reached.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            idle_conn: Mutex::new(connect_idle().context("while connecting to MPD")?),
+            command_conns: std::sync::Mutex::new(vec![]),
+        })
+    }
+
+    /// Lock the dedicated idle connection. Only the queue daemon should hold this for long.
+    pub async fn idle(&self) -> MutexGuard<'_, Client<MPDStream>> {
+        self.idle_conn.lock().await
+    }
+
+    /// Check out a command connection, reusing a spare one if it still answers a ping.
+    ///
+    /// May fail if MPD can't be reached.
+    pub fn command(&self) -> Result<PooledClient<'_>> {
+        loop {
+            let spare = self.command_conns.lock().expect("Poisoned lock").pop();
+            match spare {
+                Some(mut client) => {
+                    // MPD closes connections that have been quiet for too long
+                    if client.ping().is_ok() {
+                        return Ok(PooledClient {
+                            client: Some(client),
+                            pool: self,
+                        });
+                    }
+                }
+                None => {
+                    return Ok(PooledClient {
+                        client: Some(connect_to_mpd().context("while connecting to MPD")?),
+                        pool: self,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Wait until MPD is reachable again, then replace the idle connection.
+    pub async fn reconnect_idle(&self, idle_conn: &mut MutexGuard<'_, Client<MPDStream>>) {
+        **idle_conn = connect_with_backoff().await;
+    }
+
+    /// Wake the queue daemon from `idle` with a message on the wake channel, so it picks up
+    /// requests from the server right away.
+    ///
+    /// May fail if MPD can't be reached.
+    pub fn wake(&self) -> Result<()> {
+        self.command()?
+            .sendmessage(wake_channel(), "wake")
+            .context("while waking the queue daemon")
+    }
+}
@@ -0,0 +1,590 @@
+//! Dumping the analyzed library to portable formats and merging such dumps back in, so analyses
+//! can be explored elsewhere or moved between machines without decoding everything again.
+//!
+//! Paths are written relative to the MPD base path, so a dump can be imported into a library with
+//! a different base path. Imported paths must stay relative and under the base path.
+
+use super::{
+    BlissSong, Config, Decoder, ExtraInfo, GenreWeights, MPDLibrary, collapse_genres_pad_to,
+};
+use crate::NUM_GENRE_FEATURES;
+use anyhow::{Context, Result, anyhow, bail};
+use bliss_audio::library::{Library, LibrarySong};
+use bliss_audio::{Analysis, FeaturesVersion, Song as BareBlissSong};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON object per song
+    Json,
+    /// One row per song, one column per feature
+    Csv,
+    /// Columnar JSON: one array per column, ready for a dataframe library's JSON reader. Not
+    /// Arrow or Parquet.
+    Columnar,
+}
+
+/// Everything worf knows about a song.
+#[derive(Serialize, Deserialize)]
+pub struct ExportedSong {
+    /// Relative to the MPD base path, unless the song is outside of it. Such songs can't be
+    /// imported.
+    pub path: PathBuf,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<i32>,
+    pub disc_number: Option<i32>,
+    pub genre: Option<String>,
+    /// In seconds
+    pub duration: f64,
+    pub features_version: FeaturesVersion,
+    #[serde(flatten)]
+    pub extra_info: ExtraInfo,
+    pub bliss: Vec<f32>,
+    /// Collapsed genre weights, all zero if they weren't loaded. Ignored on import, since they're
+    /// derived from the genre tag.
+    #[serde(default)]
+    pub genre_features: Vec<f32>,
+}
+
+/// The same songs, one array per column. Features are split into one array per feature.
+#[derive(Serialize, Deserialize, Default)]
+struct ColumnarExport {
+    path: Vec<PathBuf>,
+    artist: Vec<Option<String>>,
+    title: Vec<Option<String>>,
+    album: Vec<Option<String>>,
+    album_artist: Vec<Option<String>>,
+    track_number: Vec<Option<i32>>,
+    disc_number: Vec<Option<i32>>,
+    genre: Vec<Option<String>>,
+    duration: Vec<f64>,
+    features_version: Vec<FeaturesVersion>,
+    popularity: Vec<i32>,
+    bliss: Vec<Vec<f32>>,
+    genre_features: Vec<Vec<f32>>,
+}
+
+impl From<Vec<ExportedSong>> for ColumnarExport {
+    fn from(songs: Vec<ExportedSong>) -> Self {
+        let mut columns = ColumnarExport {
+            bliss: vec![vec![]; songs.first().map_or(0, |song| song.bliss.len())],
+            genre_features: vec![vec![]; NUM_GENRE_FEATURES],
+            ..Default::default()
+        };
+        for song in songs {
+            columns.path.push(song.path);
+            columns.artist.push(song.artist);
+            columns.title.push(song.title);
+            columns.album.push(song.album);
+            columns.album_artist.push(song.album_artist);
+            columns.track_number.push(song.track_number);
+            columns.disc_number.push(song.disc_number);
+            columns.genre.push(song.genre);
+            columns.duration.push(song.duration);
+            columns.features_version.push(song.features_version);
+            columns.popularity.push(song.extra_info.popularity);
+            for (column, feature) in columns.bliss.iter_mut().zip(song.bliss) {
+                column.push(feature);
+            }
+            for (column, feature) in columns.genre_features.iter_mut().zip(song.genre_features) {
+                column.push(feature);
+            }
+        }
+        columns
+    }
+}
+
+impl ColumnarExport {
+    fn into_songs(self) -> Result<Vec<ExportedSong>> {
+        let len = self.path.len();
+        let lengths = [
+            self.artist.len(),
+            self.title.len(),
+            self.album.len(),
+            self.album_artist.len(),
+            self.track_number.len(),
+            self.disc_number.len(),
+            self.genre.len(),
+            self.duration.len(),
+            self.features_version.len(),
+            self.popularity.len(),
+        ];
+        if lengths
+            .into_iter()
+            .chain(self.bliss.iter().map(Vec::len))
+            .any(|column_len| column_len != len)
+        {
+            bail!("Columns in columnar export have different lengths");
+        }
+        let feature = |columns: &[Vec<f32>], i: usize| -> Vec<f32> {
+            columns
+                .iter()
+                .map(|column| column.get(i).copied().unwrap_or_default())
+                .collect()
+        };
+        Ok((0..len)
+            .map(|i| ExportedSong {
+                path: self.path[i].clone(),
+                artist: self.artist[i].clone(),
+                title: self.title[i].clone(),
+                album: self.album[i].clone(),
+                album_artist: self.album_artist[i].clone(),
+                track_number: self.track_number[i],
+                disc_number: self.disc_number[i],
+                genre: self.genre[i].clone(),
+                duration: self.duration[i],
+                features_version: self.features_version[i],
+                extra_info: ExtraInfo {
+                    popularity: self.popularity[i],
+                },
+                bliss: feature(&self.bliss, i),
+                genre_features: feature(&self.genre_features, i),
+            })
+            .collect())
+    }
+}
+
+const CSV_COLUMNS: [&str; 11] = [
+    "path",
+    "artist",
+    "title",
+    "album",
+    "album_artist",
+    "track_number",
+    "disc_number",
+    "genre",
+    "duration",
+    "features_version",
+    "popularity",
+];
+
+fn write_csv<W: Write>(songs: &[ExportedSong], writer: W) -> Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    let num_bliss = songs.first().map_or(0, |song| song.bliss.len());
+    let header = CSV_COLUMNS
+        .iter()
+        .map(|column| column.to_string())
+        .chain((0..num_bliss).map(|i| format!("bliss_{i}")))
+        .chain((0..NUM_GENRE_FEATURES).map(|i| format!("genre_feature_{i}")));
+    writer
+        .write_record(header)
+        .context("while writing CSV header")?;
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    for song in songs {
+        let record = [
+            song.path.to_string_lossy().into_owned(),
+            optional(&song.artist),
+            optional(&song.title),
+            optional(&song.album),
+            optional(&song.album_artist),
+            song.track_number.map(|n| n.to_string()).unwrap_or_default(),
+            song.disc_number.map(|n| n.to_string()).unwrap_or_default(),
+            optional(&song.genre),
+            song.duration.to_string(),
+            serde_json::to_value(song.features_version)?
+                .as_str()
+                .map(String::from)
+                .unwrap_or_default(),
+            song.extra_info.popularity.to_string(),
+        ]
+        .into_iter()
+        .chain(song.bliss.iter().map(f32::to_string))
+        .chain(song.genre_features.iter().map(f32::to_string));
+        writer
+            .write_record(record)
+            .with_context(|| format!("while writing CSV row for {}", song.path.display()))?;
+    }
+    writer.flush().context("while flushing CSV")?;
+    Ok(())
+}
+
+fn read_csv<R: Read>(reader: R) -> Result<Vec<ExportedSong>> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader
+        .headers()
+        .context("while reading CSV header")?
+        .clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .ok_or(anyhow!("CSV is missing the `{name}` column"))
+    };
+    let columns = CSV_COLUMNS
+        .iter()
+        .map(|name| column(name))
+        .collect::<Result<Vec<_>>>()?;
+    let columns_with_prefix = |prefix: &str| -> Vec<usize> {
+        headers
+            .iter()
+            .enumerate()
+            .filter(|(_, header)| header.starts_with(prefix))
+            .map(|(i, _)| i)
+            .collect()
+    };
+    let bliss_columns = columns_with_prefix("bliss_");
+    let genre_columns = columns_with_prefix("genre_feature_");
+
+    let mut songs = vec![];
+    for (row, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("while reading CSV row {}", row + 1))?;
+        let field = |i: usize| record.get(columns[i]).unwrap_or_default();
+        let optional = |i: usize| Some(field(i).to_string()).filter(|value| !value.is_empty());
+        let number = |i: usize| -> Result<Option<i32>> {
+            optional(i)
+                .map(|value| value.parse())
+                .transpose()
+                .with_context(|| format!("while parsing {} in CSV row {}", CSV_COLUMNS[i], row + 1))
+        };
+        let features = |indices: &[usize]| -> Result<Vec<f32>> {
+            indices
+                .iter()
+                .map(|&i| record.get(i).unwrap_or_default().parse::<f32>())
+                .collect::<Result<_, _>>()
+                .with_context(|| format!("while parsing features in CSV row {}", row + 1))
+        };
+        songs.push(ExportedSong {
+            path: PathBuf::from(field(0)),
+            artist: optional(1),
+            title: optional(2),
+            album: optional(3),
+            album_artist: optional(4),
+            track_number: number(5)?,
+            disc_number: number(6)?,
+            genre: optional(7),
+            duration: field(8)
+                .parse()
+                .with_context(|| format!("while parsing duration in CSV row {}", row + 1))?,
+            features_version: serde_json::from_value(field(9).into()).with_context(|| {
+                format!("while parsing features version in CSV row {}", row + 1)
+            })?,
+            extra_info: ExtraInfo {
+                popularity: number(10)?.unwrap_or_default(),
+            },
+            bliss: features(&bliss_columns)?,
+            genre_features: features(&genre_columns)?,
+        });
+    }
+    Ok(songs)
+}
+
+fn write_songs<W: Write>(
+    format: ExportFormat,
+    songs: Vec<ExportedSong>,
+    mut writer: W,
+) -> Result<()> {
+    match format {
+        ExportFormat::Json => serde_json::to_writer(&mut writer, &songs)?,
+        ExportFormat::Csv => write_csv(&songs, &mut writer)?,
+        ExportFormat::Columnar => serde_json::to_writer(&mut writer, &ColumnarExport::from(songs))?,
+    }
+    Ok(())
+}
+
+fn read_songs<R: Read>(format: ExportFormat, mut reader: R) -> Result<Vec<ExportedSong>> {
+    Ok(match format {
+        ExportFormat::Json => {
+            serde_json::from_reader(&mut reader).context("while parsing JSON export")?
+        }
+        ExportFormat::Csv => read_csv(&mut reader)?,
+        ExportFormat::Columnar => serde_json::from_reader::<_, ColumnarExport>(&mut reader)
+            .context("while parsing columnar export")?
+            .into_songs()?,
+    })
+}
+
+/// Fail unless `path` is relative and stays under the directory it's joined to.
+fn check_relative(path: &Path) -> Result<()> {
+    if path.is_absolute() || path.has_root() {
+        bail!(
+            "{} is absolute, expected a path relative to the MPD base path",
+            path.display()
+        );
+    }
+    if path
+        .components()
+        .any(|component| component == Component::ParentDir)
+    {
+        bail!(
+            "{} contains `..`, which could escape the MPD base path",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+impl MPDLibrary {
+    fn export_song(&self, song: &BlissSong, genre_weights: Option<&GenreWeights>) -> ExportedSong {
+        let bliss_song = &song.bliss_song;
+        ExportedSong {
+            path: self.relative_path(song).to_path_buf(),
+            artist: bliss_song.artist.clone(),
+            title: bliss_song.title.clone(),
+            album: bliss_song.album.clone(),
+            album_artist: bliss_song.album_artist.clone(),
+            track_number: bliss_song.track_number,
+            disc_number: bliss_song.disc_number,
+            genre: bliss_song.genre.clone(),
+            duration: bliss_song.duration.as_secs_f64(),
+            features_version: bliss_song.features_version,
+            extra_info: song.extra_info.clone(),
+            bliss: bliss_song.analysis.as_vec(),
+            genre_features: match genre_weights {
+                Some(genre_weights) => collapse_genres_pad_to::<NUM_GENRE_FEATURES>(
+                    genre_weights,
+                    &self.bliss.config.genres,
+                    bliss_song.genre.clone().unwrap_or_default(),
+                )
+                .to_vec(),
+                None => vec![0.0; NUM_GENRE_FEATURES],
+            },
+        }
+    }
+
+    /// Write every analyzed song to `writer`, returning how many were written. Load genre weights
+    /// first to include collapsed genre features.
+    ///
+    /// May fail if the bliss library can't be read or the export can't be written.
+    pub fn export<W: Write>(&self, format: ExportFormat, writer: W) -> Result<usize> {
+        let genre_weights = self.genre_weights()?;
+        let songs: Vec<ExportedSong> = self
+            .songs()?
+            .iter()
+            .map(|song| self.export_song(song, genre_weights.as_deref()))
+            .collect();
+        let count = songs.len();
+        write_songs(format, songs, writer)?;
+        Ok(count)
+    }
+
+    /// Merge songs from an export into the bliss database, returning how many were imported and
+    /// how many were skipped because they were already analyzed. Songs already in the library are
+    /// replaced if `overwrite` is set.
+    ///
+    /// Writes through its own handle on the bliss database, like [MPDLibrary::update].
+    ///
+    /// May fail if the export can't be parsed, if it contains absolute paths or paths with `..`,
+    /// or if the database can't be written to.
+    pub fn import<R: Read>(
+        &self,
+        format: ExportFormat,
+        reader: R,
+        overwrite: bool,
+    ) -> Result<(usize, usize)> {
+        let songs = read_songs(format, reader)?;
+        let existing: HashSet<PathBuf> = self
+            .songs()?
+            .iter()
+            .map(|song| song.bliss_song.path.clone())
+            .collect();
+        let mut bliss: Library<Config, Decoder> =
+            Library::from_config_path(self.config_path.clone())
+                .context("while opening bliss library for import")?;
+        store_songs(
+            &mut bliss,
+            &self.bliss.config.mpd_base_path,
+            songs,
+            &existing,
+            overwrite,
+        )
+    }
+}
+
+/// Store exported songs under `base_path`, skipping the `existing` paths unless `overwrite` is
+/// set. Returns how many were imported and how many were skipped.
+///
+/// Nothing is stored if any path is absolute or contains `..`.
+fn store_songs(
+    bliss: &mut Library<Config, Decoder>,
+    base_path: &Path,
+    songs: Vec<ExportedSong>,
+    existing: &HashSet<PathBuf>,
+    overwrite: bool,
+) -> Result<(usize, usize)> {
+    for song in &songs {
+        check_relative(&song.path)?;
+    }
+    let (mut imported, mut skipped) = (0, 0);
+    for song in songs {
+        let path = base_path.join(&song.path);
+        if !overwrite && existing.contains(&path) {
+            skipped += 1;
+            continue;
+        }
+        let analysis = Analysis::new(song.bliss, song.features_version)
+            .with_context(|| format!("while reading analysis of {}", song.path.display()))?;
+        let duration = Duration::try_from_secs_f64(song.duration)
+            .with_context(|| format!("while reading duration of {}", song.path.display()))?;
+        let library_song = LibrarySong {
+            bliss_song: BareBlissSong {
+                path,
+                artist: song.artist,
+                title: song.title,
+                album: song.album,
+                album_artist: song.album_artist,
+                track_number: song.track_number,
+                disc_number: song.disc_number,
+                genre: song.genre,
+                analysis,
+                duration,
+                features_version: song.features_version,
+                ..Default::default()
+            },
+            extra_info: song.extra_info,
+        };
+        bliss
+            .store_song(&library_song)
+            .with_context(|| format!("while storing {}", song.path.display()))?;
+        imported += 1;
+    }
+    Ok((imported, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NUM_BLISS_FEATURES;
+    use std::fs;
+
+    fn exported(path: &str, title: &str, feature: f32) -> ExportedSong {
+        ExportedSong {
+            path: path.into(),
+            artist: None,
+            title: Some(title.to_string()),
+            album: None,
+            album_artist: None,
+            track_number: None,
+            disc_number: None,
+            genre: None,
+            duration: 180.0,
+            features_version: FeaturesVersion::LATEST,
+            extra_info: ExtraInfo::default(),
+            bliss: vec![feature; NUM_BLISS_FEATURES],
+            genre_features: vec![],
+        }
+    }
+
+    fn round_trip(format: ExportFormat) -> Result<()> {
+        let songs = vec![
+            ExportedSong {
+                artist: Some("Artist, with a comma".to_string()),
+                album: Some("Album".to_string()),
+                album_artist: Some("Album \"Artist\"".to_string()),
+                track_number: Some(3),
+                disc_number: Some(1),
+                genre: Some("Rock; Jazz".to_string()),
+                duration: 123.5,
+                extra_info: ExtraInfo { popularity: 7 },
+                genre_features: (0..NUM_GENRE_FEATURES).map(|i| i as f32 / 10.0).collect(),
+                ..exported("album/first.flac", "First", 0.25)
+            },
+            ExportedSong {
+                bliss: (0..NUM_BLISS_FEATURES).map(|i| i as f32 * -0.1).collect(),
+                genre_features: vec![0.0; NUM_GENRE_FEATURES],
+                ..exported("second.mp3", "Second", 0.0)
+            },
+        ];
+        let expected = serde_json::to_value(&songs)?;
+        let mut buffer = vec![];
+        write_songs(format, songs, &mut buffer)?;
+        let read = read_songs(format, buffer.as_slice())?;
+        assert_eq!(serde_json::to_value(&read)?, expected, "{format:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn json_round_trip() -> Result<()> {
+        round_trip(ExportFormat::Json)
+    }
+
+    #[test]
+    fn csv_round_trip() -> Result<()> {
+        round_trip(ExportFormat::Csv)
+    }
+
+    #[test]
+    fn columnar_round_trip() -> Result<()> {
+        round_trip(ExportFormat::Columnar)
+    }
+
+    #[test]
+    fn paths_must_stay_under_base_path() {
+        assert!(check_relative(Path::new("album/song.flac")).is_ok());
+        assert!(check_relative(Path::new("./album/song.flac")).is_ok());
+        assert!(check_relative(Path::new("/etc/passwd")).is_err());
+        assert!(check_relative(Path::new("../song.flac")).is_err());
+        assert!(check_relative(Path::new("album/../../song.flac")).is_err());
+    }
+
+    #[test]
+    fn unsafe_paths_store_nothing() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("worf-import-unsafe-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let base_path = Path::new("/music");
+        let config = Config::build(
+            base_path.to_path_buf(),
+            Some(dir.join("config.json")),
+            Some(dir.join("songs.db")),
+            None,
+        )?;
+        let mut bliss: Library<Config, Decoder> = Library::new(config)?;
+        let songs = vec![
+            exported("album/song.flac", "Safe", 0.1),
+            exported("../outside.flac", "Unsafe", 0.1),
+        ];
+
+        let result = store_songs(&mut bliss, base_path, songs, &HashSet::new(), false);
+        let stored: Vec<BlissSong> = bliss.songs_from_library()?;
+        fs::remove_dir_all(&dir)?;
+
+        assert!(result.is_err());
+        assert!(stored.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn overwrite_import_updates_songs_in_place() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("worf-import-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let base_path = Path::new("/music");
+        let config = Config::build(
+            base_path.to_path_buf(),
+            Some(dir.join("config.json")),
+            Some(dir.join("songs.db")),
+            None,
+        )?;
+        let mut bliss: Library<Config, Decoder> = Library::new(config)?;
+        let song = |title, feature| vec![exported("album/song.flac", title, feature)];
+
+        store_songs(
+            &mut bliss,
+            base_path,
+            song("Old", 0.1),
+            &HashSet::new(),
+            false,
+        )?;
+        let existing = HashSet::from([base_path.join("album/song.flac")]);
+        let without_overwrite =
+            store_songs(&mut bliss, base_path, song("New", 0.2), &existing, false)?;
+        let with_overwrite = store_songs(&mut bliss, base_path, song("New", 0.2), &existing, true)?;
+        let songs: Vec<BlissSong> = bliss.songs_from_library()?;
+        fs::remove_dir_all(&dir)?;
+
+        assert_eq!(without_overwrite, (0, 1));
+        assert_eq!(with_overwrite, (1, 0));
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].bliss_song.title.as_deref(), Some("New"));
+        assert_eq!(
+            songs[0].bliss_song.analysis.as_vec(),
+            vec![0.2; NUM_BLISS_FEATURES]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,430 @@
+//! Authentication and CORS for the server, configured under `server` in the worf config.
+//!
+//! With no credentials configured, requests can only read unless `anonymous_scope` says otherwise
+//! (and the server refuses to bind anywhere but localhost). Once any are configured, requests need
+//! a bearer token or HTTP basic credentials, and each credential grants either read-only or control
+//! access.
+//!
+//! Control requests must also be JSON and come from the server's own origin or a listed one, so
+//! other pages can't steer the daemon with plain form posts, and servers bound to localhost only
+//! answer to localhost host names, which keeps DNS rebinding out.
+
+use super::ClientLibrary;
+use rocket::fairing::{Fairing, Info as FairingInfo, Kind};
+use rocket::http::{Header, Method, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::{Responder, Response, catch};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::net::IpAddr;
+
+/// What a request is allowed to do. Control implies read.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Read,
+    Control,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Credential {
+    /// Sent as `Authorization: Bearer <token>`
+    Token { token: String, scope: Scope },
+    /// Sent as HTTP basic auth
+    Basic {
+        username: String,
+        password: String,
+        scope: Scope,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Origins allowed to call the API from another page, e.g. `http://localhost:3000`. `*` allows any
+    /// origin, but without credentials.
+    pub cors_origins: Vec<String>,
+    /// Accepted credentials. If empty, the server doesn't require authentication.
+    pub credentials: Vec<Credential>,
+    /// What requests without credentials may do. If unset, they can only read while no credentials
+    /// are configured, and are rejected once there are.
+    pub anonymous_scope: Option<Scope>,
+    /// Extra host names the server answers to when bound to localhost, e.g. behind a reverse proxy.
+    pub allowed_hosts: Vec<String>,
+}
+
+impl ServerConfig {
+    pub fn requires_auth(&self) -> bool {
+        !self.credentials.is_empty()
+    }
+
+    /// Work out the scope granted by an `Authorization` header, if any.
+    fn scope_for(&self, authorization: Option<&str>) -> Option<Scope> {
+        if !self.requires_auth() {
+            return Some(self.anonymous_scope.unwrap_or(Scope::Read));
+        }
+        let Some(authorization) = authorization else {
+            return self.anonymous_scope;
+        };
+        let (kind, value) = authorization.trim().split_once(' ')?;
+        if kind.eq_ignore_ascii_case("bearer") {
+            self.credentials
+                .iter()
+                .find_map(|credential| match credential {
+                    Credential::Token { token, scope } if constant_time_eq(token, value.trim()) => {
+                        Some(*scope)
+                    }
+                    _ => None,
+                })
+        } else if kind.eq_ignore_ascii_case("basic") {
+            let decoded = String::from_utf8(decode_base64(value.trim())?).ok()?;
+            let (user, pass) = decoded.split_once(':')?;
+            self.credentials
+                .iter()
+                .find_map(|credential| match credential {
+                    Credential::Basic {
+                        username,
+                        password,
+                        scope,
+                    } if constant_time_eq(username, user) && constant_time_eq(password, pass) => {
+                        Some(*scope)
+                    }
+                    _ => None,
+                })
+        } else {
+            None
+        }
+    }
+
+    /// The `Access-Control-Allow-Origin` value for a request from `origin`, and whether the
+    /// request may send credentials. Only explicitly listed origins get credentials, `*` is sent as
+    /// is so browsers never attach them for arbitrary pages.
+    fn allowed_origin<'a>(&self, origin: &'a str) -> Option<(&'a str, bool)> {
+        if self.cors_origins.iter().any(|allowed| allowed == origin) {
+            Some((origin, true))
+        } else if self.cors_origins.iter().any(|allowed| allowed == "*") {
+            Some(("*", false))
+        } else {
+            None
+        }
+    }
+
+    /// Whether a request for `host` should be answered by a server bound to `bind`. Names other
+    /// than localhost only reach a loopback server through DNS rebinding or a configured proxy.
+    fn host_allowed(&self, host: Option<&str>, bind: IpAddr) -> bool {
+        if !bind.is_loopback() {
+            return true;
+        }
+        let Some(host) = host else {
+            return false;
+        };
+        let name = host_name(host);
+        matches!(name, "localhost" | "127.0.0.1" | "[::1]")
+            || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+            || self
+                .allowed_hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether a control request from `origin` may go through. Requests without an `Origin` don't
+    /// come from a page, same-origin ones come from the bundled frontend, and anything else has to
+    /// be listed explicitly (`*` doesn't count).
+    fn control_origin_allowed(&self, origin: Option<&str>, host: Option<&str>) -> bool {
+        let Some(origin) = origin else {
+            return true;
+        };
+        let same_origin = origin
+            .split_once("://")
+            .zip(host)
+            .is_some_and(|((_, authority), host)| authority.eq_ignore_ascii_case(host));
+        same_origin || self.cors_origins.iter().any(|allowed| allowed == origin)
+    }
+}
+
+/// The host name of a `Host` header, without the port.
+fn host_name(host: &str) -> &str {
+    if host.starts_with('[') {
+        // IPv6 literals keep their brackets
+        host.find(']').map_or(host, |end| &host[..=end])
+    } else {
+        host.split_once(':').map_or(host, |(name, _)| name)
+    }
+}
+
+/// Compare secrets without bailing out at the first differing byte.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+// Not worth pulling in a crate just to read basic auth headers
+fn decode_base64(input: &str) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+    let input = input.trim_end_matches('=').as_bytes();
+    let mut output = Vec::with_capacity(input.len() * 3 / 4);
+    for chunk in input.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut buf = 0;
+        for (i, &c) in chunk.iter().enumerate() {
+            buf |= value(c)? << (18 - 6 * i);
+        }
+        let bytes = buf.to_be_bytes();
+        output.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(output)
+}
+
+fn authorize(req: &Request<'_>, needed: Scope) -> Outcome<(), &'static str> {
+    let Some(library) = req.rocket().state::<ClientLibrary>() else {
+        return Outcome::Error((Status::InternalServerError, "Server not configured"));
+    };
+    let config = &library.mpd_library.bliss.config.server;
+    let host = req.headers().get_one("Host");
+    if !config.host_allowed(host, req.rocket().config().address) {
+        return Outcome::Error((Status::Forbidden, "Unknown host"));
+    }
+    if needed == Scope::Control {
+        // browsers can't send JSON to another origin without a preflight, which Cors only answers
+        // for listed origins
+        if !req
+            .content_type()
+            .is_some_and(|content_type| content_type.is_json())
+        {
+            return Outcome::Error((
+                Status::UnsupportedMediaType,
+                "Control requests must be sent as application/json",
+            ));
+        }
+        if !config.control_origin_allowed(req.headers().get_one("Origin"), host) {
+            return Outcome::Error((Status::Forbidden, "Origin not allowed"));
+        }
+    }
+    match config.scope_for(req.headers().get_one("Authorization")) {
+        Some(scope) if scope >= needed => Outcome::Success(()),
+        Some(_) => Outcome::Error((Status::Forbidden, "Credentials don't allow control")),
+        None => Outcome::Error((Status::Unauthorized, "Missing or invalid credentials")),
+    }
+}
+
+/// Request guard for endpoints that only read the library or the daemon state.
+pub struct ReadAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ReadAccess {
+    type Error = &'static str;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authorize(req, Scope::Read).map(|_| ReadAccess)
+    }
+}
+
+/// Request guard for endpoints that steer the daemon.
+pub struct ControlAccess;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ControlAccess {
+    type Error = &'static str;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authorize(req, Scope::Control).map(|_| ControlAccess)
+    }
+}
+
+#[derive(Responder)]
+pub enum Unauthorized {
+    #[response(status = 401)]
+    Challenge(&'static str, Header<'static>),
+    #[response(status = 401)]
+    Plain(&'static str),
+}
+
+/// Ask browsers for basic auth credentials, but only when the user opened the API directly.
+/// Prompting from other pages' requests would have the browser cache credentials it then
+/// attaches to every later cross-site request.
+#[catch(401)]
+pub fn unauthorized(req: &Request<'_>) -> Unauthorized {
+    let message = "Missing or invalid credentials";
+    let direct = req.headers().get_one("Origin").is_none()
+        && req
+            .headers()
+            .get_one("Sec-Fetch-Site")
+            .is_none_or(|site| site == "none" || site == "same-origin");
+    if direct {
+        Unauthorized::Challenge(
+            message,
+            Header::new("WWW-Authenticate", r#"Basic realm="worf", charset="UTF-8""#),
+        )
+    } else {
+        Unauthorized::Plain(message)
+    }
+}
+
+/// Adds CORS headers for the configured origins and answers preflight requests.
+pub struct Cors;
+
+#[rocket::async_trait]
+impl Fairing for Cors {
+    fn info(&self) -> FairingInfo {
+        FairingInfo {
+            name: "CORS",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let Some(origin) = req.headers().get_one("Origin") else {
+            return;
+        };
+        let Some(library) = req.rocket().state::<ClientLibrary>() else {
+            return;
+        };
+        let Some((allowed, credentials)) = library
+            .mpd_library
+            .bliss
+            .config
+            .server
+            .allowed_origin(origin)
+        else {
+            return;
+        };
+        res.set_header(Header::new(
+            "Access-Control-Allow-Origin",
+            allowed.to_owned(),
+        ));
+        res.set_header(Header::new("Vary", "Origin"));
+        res.set_header(Header::new(
+            "Access-Control-Allow-Methods",
+            "GET, POST, OPTIONS",
+        ));
+        res.set_header(Header::new(
+            "Access-Control-Allow-Headers",
+            "Authorization, Content-Type",
+        ));
+        if credentials {
+            res.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
+        }
+        if req.method() == Method::Options {
+            // there are no OPTIONS routes, so turn the 404 into an empty preflight response
+            res.set_status(Status::NoContent);
+            res.set_sized_body(0, Cursor::new(""));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_time_eq_compares_whole_strings() {
+        assert!(constant_time_eq("secret", "secret"));
+        assert!(constant_time_eq("", ""));
+        assert!(!constant_time_eq("secret", "secreT"));
+        assert!(!constant_time_eq("secret", "secret2"));
+        assert!(!constant_time_eq("secret", ""));
+    }
+
+    #[test]
+    fn decode_base64_reads_basic_auth() {
+        assert_eq!(
+            decode_base64("dXNlcjpwYXNz").as_deref(),
+            Some(&b"user:pass"[..])
+        );
+        assert_eq!(decode_base64("YQ==").as_deref(), Some(&b"a"[..]));
+        assert_eq!(decode_base64("!!!!"), None);
+    }
+
+    #[test]
+    fn wildcard_origin_never_gets_credentials() {
+        let config = ServerConfig {
+            cors_origins: vec!["*".to_string(), "http://dashboard.local".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.allowed_origin("http://dashboard.local"),
+            Some(("http://dashboard.local", true))
+        );
+        assert_eq!(
+            config.allowed_origin("http://evil.example"),
+            Some(("*", false))
+        );
+        assert_eq!(ServerConfig::default().allowed_origin("http://a"), None);
+    }
+
+    #[test]
+    fn scopes_follow_credentials() {
+        let config = ServerConfig {
+            credentials: vec![
+                Credential::Token {
+                    token: "abc".to_string(),
+                    scope: Scope::Read,
+                },
+                Credential::Basic {
+                    username: "user".to_string(),
+                    password: "pass".to_string(),
+                    scope: Scope::Control,
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(config.scope_for(Some("Bearer abc")), Some(Scope::Read));
+        assert_eq!(config.scope_for(Some("Bearer abd")), None);
+        assert_eq!(
+            config.scope_for(Some("Basic dXNlcjpwYXNz")),
+            Some(Scope::Control)
+        );
+        assert_eq!(config.scope_for(None), None);
+        assert_eq!(ServerConfig::default().scope_for(None), Some(Scope::Read));
+        let open = ServerConfig {
+            anonymous_scope: Some(Scope::Control),
+            ..Default::default()
+        };
+        assert_eq!(open.scope_for(None), Some(Scope::Control));
+    }
+
+    #[test]
+    fn loopback_servers_only_answer_to_localhost() {
+        let loopback: IpAddr = "127.0.0.1".parse().unwrap();
+        let config = ServerConfig {
+            allowed_hosts: vec!["worf.lan".to_string()],
+            ..Default::default()
+        };
+        assert!(config.host_allowed(Some("localhost:8080"), loopback));
+        assert!(config.host_allowed(Some("127.0.0.1:8080"), loopback));
+        assert!(config.host_allowed(Some("[::1]:8080"), loopback));
+        assert!(config.host_allowed(Some("WORF.lan"), loopback));
+        assert!(!config.host_allowed(Some("evil.example:8080"), loopback));
+        assert!(!config.host_allowed(None, loopback));
+        assert!(config.host_allowed(Some("evil.example"), "0.0.0.0".parse().unwrap()));
+    }
+
+    #[test]
+    fn control_needs_own_or_listed_origin() {
+        let config = ServerConfig {
+            cors_origins: vec!["*".to_string(), "http://dashboard.local".to_string()],
+            ..Default::default()
+        };
+        let host = Some("localhost:8080");
+        assert!(config.control_origin_allowed(None, host));
+        assert!(config.control_origin_allowed(Some("http://localhost:8080"), host));
+        assert!(config.control_origin_allowed(Some("http://dashboard.local"), host));
+        assert!(!config.control_origin_allowed(Some("http://evil.example"), host));
+        assert!(!config.control_origin_allowed(Some("null"), host));
+    }
+}
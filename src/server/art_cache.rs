@@ -0,0 +1,141 @@
+//! Album art cached by album directory, so the visualizer's burst of requests (one per plotted
+//! song) doesn't turn into a burst of MPD commands.
+//!
+//! Art is kept in memory and written to disk, both per requested thumbnail size. Songs without
+//! art are only remembered in memory. Everything is thrown away when the library is updated: the
+//! files by `MPDLibrary::update` itself, the memory when the served library is refreshed.
+
+use image::ImageFormat;
+use image::imageops::FilterType;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// How many images (or known misses) to keep in memory.
+const MAX_MEMORY_ENTRIES: usize = 2000;
+pub const MIN_THUMBNAIL_SIZE: u32 = 16;
+pub const MAX_THUMBNAIL_SIZE: u32 = 1024;
+
+/// Album directory (relative to the MPD base path) and thumbnail size, `None` for full size.
+type ArtKey = (PathBuf, Option<u32>);
+
+#[derive(Default)]
+struct MemoryCache {
+    entries: HashMap<ArtKey, Option<Arc<Vec<u8>>>>,
+    /// Oldest first, for eviction
+    order: VecDeque<ArtKey>,
+}
+
+pub struct ArtCache {
+    memory: Mutex<MemoryCache>,
+    /// Where to write cached art, if anywhere
+    dir: Option<PathBuf>,
+}
+
+impl ArtCache {
+    /// Cache on disk in `dir`, or only in memory if it can't be created.
+    pub fn new(dir: PathBuf) -> Self {
+        let dir = match fs::create_dir_all(&dir) {
+            Ok(()) => Some(dir),
+            Err(e) => {
+                println!(
+                    "Couldn't create album art cache at {}, only caching in memory: {e}",
+                    dir.display()
+                );
+                None
+            }
+        };
+        Self {
+            memory: Mutex::new(MemoryCache::default()),
+            dir,
+        }
+    }
+
+    /// The cache key for a song at `path` (relative to the MPD base path).
+    pub fn key(path: &Path, size: Option<u32>) -> ArtKey {
+        (path.parent().unwrap_or(Path::new("")).to_path_buf(), size)
+    }
+
+    /// `Some(None)` means the album is known to have no art.
+    pub fn get(&self, key: &ArtKey) -> Option<Option<Arc<Vec<u8>>>> {
+        if let Some(entry) = self.memory.lock().expect("Poisoned lock").entries.get(key) {
+            return Some(entry.clone());
+        }
+        let data = Arc::new(fs::read(self.file_for(key)?).ok()?);
+        self.remember(key.clone(), Some(Arc::clone(&data)));
+        Some(Some(data))
+    }
+
+    pub fn insert(&self, key: ArtKey, data: Option<Arc<Vec<u8>>>) {
+        if let Some(data) = &data
+            && let Some(file) = self.file_for(&key)
+            && let Err(e) = fs::write(&file, data.as_slice())
+        {
+            println!("Couldn't cache album art at {}: {e}", file.display());
+        }
+        self.remember(key, data);
+    }
+
+    /// Forget everything in memory after a library update, which already removed the files.
+    pub fn clear(&self) {
+        *self.memory.lock().expect("Poisoned lock") = MemoryCache::default();
+    }
+
+    fn remember(&self, key: ArtKey, data: Option<Arc<Vec<u8>>>) {
+        let mut memory = self.memory.lock().expect("Poisoned lock");
+        if memory.entries.insert(key.clone(), data).is_none() {
+            memory.order.push_back(key);
+        }
+        while memory.order.len() > MAX_MEMORY_ENTRIES {
+            if let Some(oldest) = memory.order.pop_front() {
+                memory.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn file_for(&self, key: &ArtKey) -> Option<PathBuf> {
+        let size = key.1.map_or("full".into(), |size| size.to_string());
+        Some(self.dir.as_ref()?.join(format!(
+            "{:016x}-{size}",
+            fnv1a(key.0.as_os_str().as_encoded_bytes())
+        )))
+    }
+}
+
+/// 64-bit FNV-1a, so file names stay the same across builds (unlike `DefaultHasher`).
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Shrink album art to fit in a `size`x`size` square, as a JPEG. Images that are already small
+/// enough are returned as they are.
+///
+/// May fail if the image can't be decoded.
+pub fn thumbnail(data: &[u8], size: u32) -> image::ImageResult<Vec<u8>> {
+    let image = image::load_from_memory(data)?;
+    if image.width() <= size && image.height() <= size {
+        return Ok(data.to_vec());
+    }
+    let mut thumbnail = Cursor::new(vec![]);
+    image
+        .resize(size, size, FilterType::Triangle)
+        .into_rgb8()
+        .write_to(&mut thumbnail, ImageFormat::Jpeg)?;
+    Ok(thumbnail.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+    }
+}
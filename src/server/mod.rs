@@ -1,29 +1,75 @@
-use crate::mpd_library::{ExtraInfo, MPDLibrary, collapse_genres_pad_to};
-use crate::{NUM_BLISS_FEATURES, NUM_GENRE_FEATURES};
+mod access;
+mod art_cache;
+mod projection;
+mod store;
+mod streaming;
 
-use anyhow::{Context, anyhow};
+use crate::daemon::{DaemonEvent, DaemonSong, DaemonState, DaemonStatus, QueueMode};
+use crate::mpd_library::{
+    ExtraInfo, GenreConfig, GenreWeights, MPDLibrary, Metric, Normalization,
+    closest_to_genre_songs, closest_to_hybrid_songs, collapse_genres_pad_to, is_duplicate,
+};
+use crate::{NUM_BLISS_FEATURES, NUM_GENRE_FEATURES, POPULARITY_DIFFERENCE_FLOOR};
+
+use anyhow::Context;
 use bliss_audio::FeaturesVersion;
 use bliss_audio::library::LibrarySong as BlissSongNoInfo;
-use bliss_audio::playlist::{closest_to_songs, euclidean_distance};
+use bliss_audio::playlist::{DistanceMetricBuilder, closest_to_songs};
 use itertools::Itertools;
 use log::info;
+use rocket::http::{ContentType, Header};
 use rocket::response::Responder;
-use rocket::response::status::{BadRequest, NotFound};
-use rocket::{State, get, http::Status, serde::json::Json};
-use serde::Serialize;
+use rocket::response::status::{BadRequest, Custom, NotFound};
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{
+    FromForm, FromFormField, Shutdown, State, get, http::Status, post, serde::json::Json, uri,
+};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::hash::Hash;
-use std::path::PathBuf;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use access::{ControlAccess, ReadAccess};
+pub use access::{Cors, ServerConfig, unauthorized};
+use art_cache::{ArtCache, MAX_THUMBNAIL_SIZE, MIN_THUMBNAIL_SIZE, thumbnail};
+use projection::Embedding;
+use store::{IfNoneMatch, SongStore, StoreEntry};
+use streaming::{AudioFile, RangeHeader};
 
 type BlissSong = BlissSongNoInfo<ExtraInfo>;
 
 pub const CHUNK_SIZE: usize = 50;
+/// The most songs a listing request can ask for at once.
+pub const MAX_PAGE_SIZE: usize = 1000;
+/// How many times to ask MPD for album art before giving up.
+const ALBUM_ART_ATTEMPTS: u32 = 3;
+/// How long to wait before the first retry, doubling for each one after.
+const ALBUM_ART_BACKOFF: Duration = Duration::from_millis(100);
 
 /// Custom responder for binary image data
 #[derive(Responder)]
-#[response(status = 200, content_type = "image/jpeg")]
-pub struct ImageData(Vec<u8>);
+pub struct ImageData(Vec<u8>, ContentType);
+
+impl ImageData {
+    /// Work out the image type from its magic bytes, since MPD doesn't say.
+    fn new(data: Vec<u8>) -> Self {
+        let content_type = if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            ContentType::PNG
+        } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+            ContentType::JPEG
+        } else if data.starts_with(b"GIF8") {
+            ContentType::GIF
+        } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(&b"WEBP"[..]) {
+            ContentType::WEBP
+        } else {
+            ContentType::Binary
+        };
+        Self(data, content_type)
+    }
+}
 
 #[derive(Serialize, Clone)]
 pub struct SongAnalyses {
@@ -31,139 +77,471 @@ pub struct SongAnalyses {
     pub genre: [f32; NUM_GENRE_FEATURES],
 }
 
+impl SongAnalyses {
+    /// Genre features are all zero without genre weights.
+    fn from_song(
+        song: &BlissSong,
+        genre_weights: Option<&GenreWeights>,
+        genre_config: &GenreConfig,
+    ) -> Self {
+        Self {
+            bliss: *song
+                .bliss_song
+                .analysis
+                .as_vec()
+                .as_array()
+                .expect("while converting bliss analysis to array"),
+            genre: match genre_weights {
+                Some(genre_weights) => collapse_genres_pad_to(
+                    genre_weights,
+                    genre_config,
+                    song.bliss_song.genre.clone().unwrap_or_default(),
+                ),
+                None => [0.0; NUM_GENRE_FEATURES],
+            },
+        }
+    }
+}
+
+/// Tags and extra info for a song, so clients can show more than its path.
+#[derive(Serialize, Clone)]
+pub struct SongInfo {
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub popularity: i32,
+    pub duration: u64,
+}
+
+impl From<&BlissSong> for SongInfo {
+    fn from(song: &BlissSong) -> Self {
+        let bliss_song = &song.bliss_song;
+        Self {
+            artist: bliss_song.artist.clone(),
+            title: bliss_song.title.clone(),
+            album: bliss_song.album.clone(),
+            album_artist: bliss_song.album_artist.clone(),
+            genre: bliss_song.genre.clone(),
+            popularity: song.extra_info.popularity,
+            duration: bliss_song.duration.as_secs(),
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct AllSongsPage {
     page: usize,
-    songs: HashMap<PathBuf, SongAnalyses>,
+    songs: HashMap<PathBuf, Arc<SongAnalyses>>,
 }
 
-pub struct ClientLibrary {
-    pub songs: ChunkedReadOnlyHashMap<PathBuf, SongAnalyses>,
-    pub mpd_library: MPDLibrary,
+#[derive(Serialize)]
+pub struct CursorPage {
+    songs: Vec<ListedSong>,
+    /// Pass as `cursor` to get the next songs. Null on the last page.
+    next: Option<PathBuf>,
 }
 
 #[derive(Serialize)]
-pub struct ChunkedReadOnlyHashMap<K, V> {
-    chunks: Vec<HashMap<K, V>>,
-    total_len: usize,
+pub struct ListedSong {
+    path: PathBuf,
+    info: Arc<SongInfo>,
+    analysis: Arc<SongAnalyses>,
 }
 
-impl<K: Hash + Eq + Clone + Ord, V: Clone> ChunkedReadOnlyHashMap<K, V> {
-    pub fn new(base: HashMap<K, V>, chunk_size: usize) -> Self {
-        let total_len = base.len();
-        let num_chunks = total_len / chunk_size + 1;
-        let chunked_entries = base
-            .into_iter()
-            .sorted_by_cached_key(|x| x.0.clone())
-            .chunks(chunk_size);
-        let chunks: Vec<_> = (&chunked_entries)
-            .into_iter()
-            .map(HashMap::from_iter)
-            .collect();
-        assert!(num_chunks == chunks.len());
-        Self { chunks, total_len }
+impl From<StoreEntry> for ListedSong {
+    fn from(entry: StoreEntry) -> Self {
+        Self {
+            path: entry.path,
+            info: entry.info,
+            analysis: entry.analysis,
+        }
     }
+}
 
-    pub fn get_chunk_index(&self, key: &K) -> Option<usize> {
-        for (i, chunk) in self.chunks.iter().enumerate() {
-            if chunk.contains_key(key) {
-                return Some(i);
-            }
-        }
-        None
+/// Cheap to clone, so that a background task can keep the store up to date.
+#[derive(Clone)]
+pub struct ClientLibrary {
+    pub songs: Arc<SongStore>,
+    pub mpd_library: Arc<MPDLibrary>,
+    art: Arc<ArtCache>,
+}
+
+impl ClientLibrary {
+    /// Collect the analyses of every song in the library for serving.
+    ///
+    /// May fail if the bliss library can't be read.
+    pub fn new(mpd_library: Arc<MPDLibrary>) -> anyhow::Result<Self> {
+        Ok(Self {
+            songs: Arc::new(SongStore::new(
+                Self::collect_songs(&mpd_library)?,
+                CHUNK_SIZE,
+            )),
+            art: Arc::new(ArtCache::new(mpd_library.art_cache_dir())),
+            mpd_library,
+        })
     }
 
-    pub fn get(&self, key: K) -> Option<V> {
-        let idx = self.get_chunk_index(&key)?;
-        self.chunks[idx].get(&key).cloned()
+    /// Rebuild the store from the bliss library and drop cached album art, e.g. after an update.
+    ///
+    /// May fail if the bliss library can't be read.
+    pub fn refresh(&self) -> anyhow::Result<()> {
+        self.songs.rebuild(Self::collect_songs(&self.mpd_library)?);
+        self.art.clear();
+        Ok(())
+    }
+
+    /// Refresh the store whenever the daemon finishes updating the library.
+    pub async fn refresh_on_updates(self, daemon: Arc<DaemonState>) {
+        let mut rx = daemon.subscribe();
+        loop {
+            match rx.recv().await {
+                // might have missed an update while lagging
+                Ok(DaemonEvent::UpdateFinished) | Err(RecvError::Lagged(_)) => {
+                    match self.refresh() {
+                        Ok(()) => daemon.publish(DaemonEvent::LibraryRefreshed),
+                        Err(e) => println!("Failed to refresh served library after update: {e}"),
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
     }
 
-    pub fn get_chunk_at_index(&self, idx: usize) -> Option<&HashMap<K, V>> {
-        self.chunks.get(idx)
+    fn collect_songs(
+        mpd_library: &MPDLibrary,
+    ) -> anyhow::Result<HashMap<PathBuf, (SongInfo, SongAnalyses)>> {
+        let genre_weights = mpd_library.genre_weights()?;
+        Ok(mpd_library
+            .songs()?
+            .iter()
+            .map(|song: &BlissSong| {
+                (
+                    mpd_library.relative_path(song).to_path_buf(),
+                    (
+                        SongInfo::from(song),
+                        SongAnalyses::from_song(
+                            song,
+                            genre_weights.as_deref(),
+                            &mpd_library.bliss.config.genres,
+                        ),
+                    ),
+                )
+            })
+            .collect())
     }
 }
 
-#[get("/all")]
-pub fn info(state: &State<ClientLibrary>) -> Json<Info> {
+#[get("/all", rank = 2)]
+pub fn info(_access: ReadAccess, state: &State<ClientLibrary>) -> Json<Info> {
     Json(Info {
-        max_page: state.songs.chunks.len() - 1,
+        max_page: state.songs.page_count().saturating_sub(1),
+        total_songs: state.songs.song_count(),
     })
 }
 
-#[get("/all/<page>")]
-pub fn all(page: usize, state: &State<ClientLibrary>) -> Result<Json<AllSongsPage>, Status> {
-    if page >= state.songs.chunks.len() {
-        return Err(Status::BadRequest);
+/// A page of songs sorted after `cursor` (a path; pass an empty cursor to start from the
+/// beginning). Ranked above `info` so that requests without a cursor fall through to it.
+#[get("/all?<cursor>&<limit>", rank = 1)]
+pub fn all_after(
+    cursor: &str,
+    limit: Option<usize>,
+    _access: ReadAccess,
+    state: &State<ClientLibrary>,
+) -> Result<Json<CursorPage>, BadRequest<String>> {
+    let limit = limit.unwrap_or(CHUNK_SIZE);
+    if limit == 0 || limit > MAX_PAGE_SIZE {
+        return Err(BadRequest(format!(
+            "Limit must be between 1 and {MAX_PAGE_SIZE}"
+        )));
     }
-    Ok(Json(AllSongsPage {
-        page,
-        songs: state
-            .songs
-            .get_chunk_at_index(page)
-            .ok_or(Status::InternalServerError)?
-            .iter()
-            .map(|(key, val)| (key.clone(), val.clone()))
+    let (songs, next) = state.songs.after(Path::new(cursor), limit);
+    Ok(Json(CursorPage {
+        songs: songs.into_iter().map(ListedSong::from).collect(),
+        next,
+    }))
+}
+
+#[derive(FromFormField, Clone, Copy)]
+pub enum SortKey {
+    Path,
+    Title,
+    Artist,
+    Album,
+    Popularity,
+    Duration,
+}
+
+/// Query parameters for searching the library. Text matches are case-insensitive substrings.
+#[derive(FromForm)]
+pub struct SongQuery {
+    /// Matched against artist, title, album and genre
+    q: Option<String>,
+    genre: Option<String>,
+    min_popularity: Option<i32>,
+    max_popularity: Option<i32>,
+    /// In seconds
+    min_duration: Option<u64>,
+    /// In seconds
+    max_duration: Option<u64>,
+    #[field(default = SortKey::Path)]
+    sort: SortKey,
+    #[field(default = false)]
+    desc: bool,
+    #[field(default = 0)]
+    page: usize,
+    #[field(default = CHUNK_SIZE)]
+    page_size: usize,
+}
+
+impl SongQuery {
+    fn matches(&self, entry: &StoreEntry) -> bool {
+        fn contains(haystack: Option<&str>, needle: &str) -> bool {
+            haystack.is_some_and(|haystack| haystack.to_lowercase().contains(needle))
+        }
+        let info = &entry.info;
+        if let Some(q) = &self.q {
+            let q = q.to_lowercase();
+            if !(contains(info.artist.as_deref(), &q)
+                || contains(info.title.as_deref(), &q)
+                || contains(info.album.as_deref(), &q)
+                || contains(info.genre.as_deref(), &q))
+            {
+                return false;
+            }
+        }
+        if let Some(genre) = &self.genre
+            && !contains(info.genre.as_deref(), &genre.to_lowercase())
+        {
+            return false;
+        }
+        self.min_popularity.is_none_or(|min| info.popularity >= min)
+            && self.max_popularity.is_none_or(|max| info.popularity <= max)
+            && self.min_duration.is_none_or(|min| info.duration >= min)
+            && self.max_duration.is_none_or(|max| info.duration <= max)
+    }
+
+    fn sort_songs(&self, songs: &mut [StoreEntry]) {
+        // songs come out of the store in path order, and the sort is stable
+        match self.sort {
+            SortKey::Path => {}
+            SortKey::Title => songs.sort_by(|a, b| a.info.title.cmp(&b.info.title)),
+            SortKey::Artist => songs.sort_by(|a, b| a.info.artist.cmp(&b.info.artist)),
+            SortKey::Album => songs.sort_by(|a, b| a.info.album.cmp(&b.info.album)),
+            SortKey::Popularity => songs.sort_by_key(|song| song.info.popularity),
+            SortKey::Duration => songs.sort_by_key(|song| song.info.duration),
+        }
+        if self.desc {
+            songs.reverse();
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SearchResults {
+    page: usize,
+    page_size: usize,
+    /// Number of songs matching the query across all pages
+    total: usize,
+    songs: Vec<ListedSong>,
+}
+
+#[get("/all/search?<query..>")]
+pub fn search(
+    query: SongQuery,
+    _access: ReadAccess,
+    state: &State<ClientLibrary>,
+) -> Result<Json<SearchResults>, BadRequest<String>> {
+    if query.page_size == 0 || query.page_size > MAX_PAGE_SIZE {
+        return Err(BadRequest(format!(
+            "Page size must be between 1 and {MAX_PAGE_SIZE}"
+        )));
+    }
+    let Some(offset) = query.page.checked_mul(query.page_size) else {
+        return Err(BadRequest("Page number too large".to_string()));
+    };
+    let mut songs = state.songs.filter(|entry| query.matches(entry));
+    query.sort_songs(&mut songs);
+    let total = songs.len();
+    Ok(Json(SearchResults {
+        page: query.page,
+        page_size: query.page_size,
+        total,
+        songs: songs
+            .into_iter()
+            .skip(offset)
+            .take(query.page_size)
+            .map(ListedSong::from)
             .collect(),
     }))
 }
 
+#[derive(Responder)]
+pub enum PageResponse {
+    Page(Json<AllSongsPage>, Header<'static>),
+    #[response(status = 304)]
+    NotModified((), Header<'static>),
+}
+
+#[get("/all/<page>")]
+pub fn all(
+    page: usize,
+    _access: ReadAccess,
+    if_none_match: IfNoneMatch,
+    state: &State<ClientLibrary>,
+) -> Result<PageResponse, Status> {
+    let store_page = state.songs.page(page).ok_or(Status::BadRequest)?;
+    let etag = Header::new("ETag", store_page.etag.clone());
+    if if_none_match.matches(&store_page.etag) {
+        return Ok(PageResponse::NotModified((), etag));
+    }
+    Ok(PageResponse::Page(
+        Json(AllSongsPage {
+            page,
+            songs: store_page
+                .songs
+                .into_iter()
+                .map(|entry| (entry.path, entry.analysis))
+                .collect(),
+        }),
+        etag,
+    ))
+}
+
 #[derive(Serialize)]
 pub struct Info {
     max_page: usize,
+    total_songs: usize,
 }
 
 #[get("/analysis/<path>")]
 pub fn analysis(
     path: &str,
+    _access: ReadAccess,
     state: &State<ClientLibrary>,
-) -> Result<Json<SongAnalyses>, NotFound<String>> {
+) -> Result<Json<Arc<SongAnalyses>>, NotFound<String>> {
     Ok(Json(
         state
             .songs
-            .get(path.into())
+            .get(Path::new(path))
             .context("Song does not exist in bliss database")
             .map_err(|e| NotFound(e.to_string()))?,
     ))
 }
 
-#[get("/albumart/<path>")]
+/// Every song in the library projected onto a 2D map, recomputed when the library is updated.
+#[get("/embedding")]
+pub fn embedding(_access: ReadAccess, state: &State<ClientLibrary>) -> Json<Arc<Embedding>> {
+    Json(state.songs.embedding())
+}
+
+/// The song file itself, with range support so the visualizer can play previews.
+#[get("/audio/<path>")]
+pub async fn audio(
+    path: &str,
+    range: RangeHeader,
+    _access: ReadAccess,
+    state: &State<ClientLibrary>,
+) -> Result<AudioFile, NotFound<String>> {
+    // only hand out files that are in the library, whatever the path says
+    if state.songs.get(Path::new(path)).is_none() {
+        return Err(NotFound("Song does not exist in bliss database".into()));
+    }
+    // the song may have been analyzed under one of the alternate base paths
+    let song = state
+        .mpd_library
+        .path_to_bliss_song(path)
+        .map_err(|e| NotFound(e.to_string()))?;
+    AudioFile::open(&song.bliss_song.path, &range)
+        .await
+        .context("while opening song file")
+        .map_err(|e| NotFound(e.to_string()))
+}
+
+/// Album art for a song, optionally shrunk to fit in a `size`x`size` square.
+#[get("/albumart/<path>?<size>")]
 pub async fn albumart(
     path: &str,
+    size: Option<u32>,
+    _access: ReadAccess,
     state: &State<ClientLibrary>,
-) -> Result<ImageData, NotFound<String>> {
-    let song = &state
+) -> Result<ImageData, Custom<String>> {
+    let not_found = || {
+        Custom(
+            Status::NotFound,
+            "Album art not found for this song".to_string(),
+        )
+    };
+    let size = size.map(|size| size.clamp(MIN_THUMBNAIL_SIZE, MAX_THUMBNAIL_SIZE));
+    let key = ArtCache::key(Path::new(path), size);
+    if let Some(cached) = state.art.get(&key) {
+        return cached
+            .map(|album_art| ImageData::new(album_art.to_vec()))
+            .ok_or_else(not_found);
+    }
+
+    // thumbnails are made from the full size art, which is worth caching too
+    let full_key = ArtCache::key(Path::new(path), None);
+    let album_art = match state.art.get(&full_key) {
+        Some(cached) => cached,
+        None => {
+            let album_art = fetch_album_art(path, state).await?.map(Arc::new);
+            state.art.insert(full_key, album_art.clone());
+            album_art
+        }
+    };
+    let Some(album_art) = album_art else {
+        if size.is_some() {
+            state.art.insert(key, None);
+        }
+        return Err(not_found());
+    };
+    let Some(size) = size else {
+        return Ok(ImageData::new(album_art.to_vec()));
+    };
+
+    let resized = rocket::tokio::task::spawn_blocking(move || thumbnail(&album_art, size))
+        .await
+        .context("while resizing album art")
+        .and_then(|resized| resized.context("while resizing album art"))
+        .map_err(|e| Custom(Status::InternalServerError, e.to_string()))?;
+    state.art.insert(key, Some(Arc::new(resized.clone())));
+    Ok(ImageData::new(resized))
+}
+
+/// Ask MPD (or the file itself) for a song's album art, retrying a few times on connection
+/// errors.
+async fn fetch_album_art(
+    path: &str,
+    state: &ClientLibrary,
+) -> Result<Option<Vec<u8>>, Custom<String>> {
+    let song = state
         .mpd_library
-        .bliss_song_to_mpd(
-            &state
+        .path_to_bliss_song(path)
+        .context("while finding bliss song")
+        .and_then(|song| {
+            state
                 .mpd_library
-                .path_to_bliss_song(path)
-                .context("while finding bliss song")
-                .map_err(|e| NotFound(e.to_string()))
-                .unwrap(),
-        )
-        .context("while converting bliss song to MPD song")
-        .map_err(|e| NotFound(e.to_string()))
-        .unwrap();
+                .bliss_song_to_mpd(&song)
+                .context("while converting bliss song to MPD song")
+        })
+        .map_err(|e| Custom(Status::NotFound, e.to_string()))?;
+    let mut attempt = 1;
     loop {
-        match state.mpd_library.get_album_art(song).await.map(ImageData) {
+        match state.mpd_library.get_album_art(&song).await {
             Ok(album_art) => return Ok(album_art),
+            Err(e) if attempt < ALBUM_ART_ATTEMPTS => {
+                info!("Error retrieving album art: {e}, retrying with a new connection...");
+                rocket::tokio::time::sleep(ALBUM_ART_BACKOFF * 2_u32.pow(attempt - 1)).await;
+                attempt += 1;
+            }
             Err(e) => {
-                match e.downcast_ref::<mpd::error::Error>() {
-                    Some(mpd::error::Error::Parse(e)) => {
-                        return Err(NotFound(format!(
-                            "Album art not found for this song: {}",
-                            e
-                        )));
-                    }
-                    _ => {
-                        info!(
-                            "Error retrieving album art: {}, attempting to reconnect...",
-                            e
-                        );
-                    }
-                }
-                MPDLibrary::reconnect_to_mpd(&mut state.mpd_library.mpd_conn.lock().await);
-                continue;
+                return Err(Custom(
+                    Status::ServiceUnavailable,
+                    format!("Couldn't get album art from MPD: {e}"),
+                ));
             }
         }
     }
@@ -171,7 +549,8 @@ pub async fn albumart(
 
 #[derive(Serialize, Clone)]
 pub struct ClientPlaylistSong {
-    href: PathBuf,
+    /// Where to stream the song from
+    href: String,
     path: PathBuf,
     artist: Option<String>,
     title: Option<String>,
@@ -184,18 +563,102 @@ pub struct ClientPlaylistSong {
     analysis: SongAnalyses,
     duration: u64,
     features_version: FeaturesVersion,
+    /// Bliss distance to the first seed
+    bliss_distance: f32,
+    /// Genre similarity to the first seed, if both have genres and genre weights are loaded
+    genre_similarity: Option<f32>,
+    /// Position in the sorted library before deduplication and the popularity filter, starting
+    /// at 1. Seeds are 0.
+    rank: usize,
+}
+
+/// A filter applied to every song in the playlist.
+#[derive(Serialize, Clone)]
+#[serde(tag = "filter", rename_all = "snake_case")]
+pub enum FilterDecision {
+    /// Only the first of any near-identical songs was kept
+    Dedup,
+    /// Only songs with popularity above the floor set by the first seed were kept
+    Popularity { floor: i32 },
+}
+
+impl ClientPlaylistSong {
+    fn new(
+        song: BlissSong,
+        mpd_library: &MPDLibrary,
+        genre_weights: Option<&GenreWeights>,
+        seed: &BlissSong,
+        rank: usize,
+    ) -> Self {
+        let analysis =
+            SongAnalyses::from_song(&song, genre_weights, &mpd_library.bliss.config.genres);
+        let bliss_distance = mpd_library.get_bliss_similarity(&song, seed);
+        let genre_similarity = mpd_library.get_genre_similarity(&song, seed).ok();
+        let path = mpd_library.relative_path(&song).to_path_buf();
+        let bliss_song = song.bliss_song;
+        ClientPlaylistSong {
+            href: uri!("/api", audio(path.to_string_lossy().into_owned())).to_string(),
+            path,
+            artist: bliss_song.artist,
+            title: bliss_song.title,
+            album: bliss_song.album,
+            album_artist: bliss_song.album_artist,
+            track_number: bliss_song.track_number,
+            disc_number: bliss_song.disc_number,
+            genre: bliss_song.genre,
+            popularity: song.extra_info.popularity,
+            analysis,
+            duration: bliss_song.duration.as_secs(),
+            features_version: bliss_song.features_version,
+            bliss_distance,
+            genre_similarity,
+            rank,
+        }
+    }
 }
 
 #[derive(Serialize)]
 pub struct ClientPlaylist {
     head: ClientPlaylistSong,
+    /// Seeds after the first one, if several were given
+    extra_seeds: Vec<ClientPlaylistSong>,
     tail: Vec<ClientPlaylistSong>,
+    /// The filters the songs in `tail` went through
+    filters: Vec<FilterDecision>,
+}
+
+/// How to sort the library against the seeds, mirroring the daemon's modes.
+#[derive(FromFormField, Clone, Copy)]
+pub enum PlaylistMode {
+    Bliss,
+    Genres,
+    /// Genre and bliss distances added together
+    Hybrid,
 }
 
-#[get("/playlist/<path>?<length>")]
+#[derive(FromForm)]
+pub struct PlaylistQuery {
+    #[field(default = PlaylistMode::Bliss)]
+    mode: PlaylistMode,
+    /// The configured metric if not given
+    distance: Option<Metric>,
+    /// The configured normalization if not given, e.g. `none` to compare with raw features
+    normalization: Option<Normalization>,
+    #[field(default = true)]
+    dedup: bool,
+    /// Same filter as the daemon's `--popularity-filter`, relative to the first seed
+    #[field(default = false)]
+    popularity_filter: bool,
+    /// More seed paths, on top of the one in the route
+    seed: Vec<String>,
+}
+
+#[get("/playlist/<path>?<length>&<query..>")]
 pub fn playlist(
     path: &str,
     length: usize,
+    query: PlaylistQuery,
+    _access: ReadAccess,
     state: &State<ClientLibrary>,
 ) -> Result<Json<ClientPlaylist>, BadRequest<String>> {
     if length == 0 {
@@ -203,79 +666,296 @@ pub fn playlist(
             "Playlist length must be greater than zero".into(),
         ));
     }
-    let bliss_sort = |x: &[BlissSong], y: &[BlissSong], z| -> Box<dyn Iterator<Item = BlissSong>> {
-        Box::new(closest_to_songs(x, y, z))
+    let mpd_library = &state.mpd_library;
+    if !mpd_library.has_genre_weights() && !matches!(query.mode, PlaylistMode::Bliss) {
+        return Err(BadRequest(
+            "Genre weights aren't loaded, only bliss mode is available".into(),
+        ));
+    }
+
+    let seeds = std::iter::once(path)
+        .chain(query.seed.iter().map(String::as_str))
+        .map(|seed| {
+            mpd_library
+                .path_to_bliss_song(seed)
+                .with_context(|| format!("while getting seed song {seed}"))
+                .map_err(|e| BadRequest(e.to_string()))
+        })
+        .collect::<Result<Vec<BlissSong>, _>>()?;
+    let genre_weights = mpd_library
+        .genre_weights()
+        .context("while matching genres")
+        .map_err(|e| BadRequest(e.to_string()))?;
+
+    let distance_config = &mpd_library.bliss.config.distance;
+    let metric = query.distance.unwrap_or(distance_config.metric);
+    let (bliss_metric, genre_metric) = metric
+        .build(mpd_library, seeds.len())
+        .with_context(|| format!("while building {} distance", metric.name()))
+        .map_err(|e| BadRequest(e.to_string()))?;
+    let normalization = query.normalization.unwrap_or(distance_config.normalization);
+    let bliss_metric = normalization
+        .apply(metric, bliss_metric.as_ref(), || {
+            mpd_library.feature_stats()
+        })
+        .context("while computing feature statistics")
+        .map_err(|e| BadRequest(e.to_string()))?;
+
+    let track_weights = match query.mode {
+        PlaylistMode::Bliss => None,
+        PlaylistMode::Genres | PlaylistMode::Hybrid => Some(
+            mpd_library
+                .track_weights()
+                .context("while getting track genre weights")
+                .map_err(|e| BadRequest(e.to_string()))?,
+        ),
+    };
+    let sort = |x: &[BlissSong],
+                y: &[BlissSong],
+                z: &dyn DistanceMetricBuilder|
+     -> Box<dyn Iterator<Item = BlissSong>> {
+        match (query.mode, &track_weights) {
+            (PlaylistMode::Genres, Some(track_weights)) => Box::new(
+                closest_to_genre_songs(x, y, z, genre_metric.as_ref(), track_weights)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            (PlaylistMode::Hybrid, Some(track_weights)) => Box::new(
+                closest_to_hybrid_songs(x, y, z, genre_metric.as_ref(), track_weights)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            _ => Box::new(closest_to_songs(x, y, z).collect::<Vec<_>>().into_iter()),
+        }
     };
-    let full_song_path = PathBuf::new()
-        .join(state.mpd_library.bliss.config.mpd_base_path.clone())
-        .join(path);
+    let first_seed = seeds[0].clone();
+    let popularity_floor = first_seed.extra_info.popularity - POPULARITY_DIFFERENCE_FLOOR;
+    let mut filters = vec![];
+    if query.dedup {
+        filters.push(FilterDecision::Dedup);
+    }
+    if query.popularity_filter {
+        filters.push(FilterDecision::Popularity {
+            floor: popularity_floor,
+        });
+    }
+
     let now = Instant::now();
-    let playlist: Vec<ClientPlaylistSong> = state
-        .mpd_library
-        .bliss
-        .playlist_from_custom(
-            &[full_song_path
-                .to_str()
-                .ok_or(BadRequest("Song path wasn't valid Unicode".into()))?],
-            &euclidean_distance,
-            bliss_sort,
-            true,
-        )
-        .context("while building bliss playlist")
-        .map_err(|e| BadRequest(e.to_string()))?
-        .map(|song| {
-            let bliss_song = song.bliss_song;
-            ClientPlaylistSong {
-                href: bliss_song.path.clone(),
-                path: {
-                    bliss_song
-                        .path
-                        .strip_prefix(state.mpd_library.bliss.config.mpd_base_path.clone())
-                        .expect("Couldn't strip MPD base path")
-                        .to_path_buf()
-                },
-                artist: bliss_song.artist,
-                title: bliss_song.title,
-                album: bliss_song.album,
-                album_artist: bliss_song.album_artist,
-                track_number: bliss_song.track_number,
-                disc_number: bliss_song.disc_number,
-                genre: bliss_song.genre.clone(),
-                popularity: song.extra_info.popularity,
-                analysis: SongAnalyses {
-                    bliss: *bliss_song
-                        .analysis
-                        .as_vec()
-                        .as_array()
-                        .expect("while converting bliss analysis to array"),
-                    genre: {
-                        let current_genre = Some(bliss_song.genre.unwrap_or_default());
-                        collapse_genres_pad_to(
-                            &state
-                                .mpd_library
-                                .genre_weights
-                                .clone()
-                                .ok_or(anyhow!("while getting genre weights"))
-                                .expect("while getting genre weights"),
-                            current_genre
-                                .as_ref()
-                                .ok_or(anyhow!("while getting current genre weight"))
-                                .expect("while getting current genre weight")
-                                .clone(),
-                        )
-                    },
-                },
-                duration: bliss_song.duration.as_secs(),
-                features_version: bliss_song.features_version,
+    let nearest = match query.mode {
+        PlaylistMode::Bliss => mpd_library
+            .nearest(&seeds, metric, normalization)
+            .context("while searching nearest neighbor index")
+            .map_err(|e| BadRequest(e.to_string()))?,
+        PlaylistMode::Genres | PlaylistMode::Hybrid => None,
+    };
+    let indexed = nearest.is_some();
+    let candidates: Box<dyn Iterator<Item = BlissSong> + '_> = match nearest {
+        Some(nearest) => Box::new(nearest),
+        None => mpd_library
+            .playlist_from_seeds(&seeds, &bliss_metric, sort, false)
+            .context("while building bliss playlist")
+            .map_err(|e| BadRequest(e.to_string()))?,
+    };
+    // ranked before deduplicating, so the rank shows how far filters pushed a song down
+    let mut rank = 0;
+    let ranked = candidates.map(|song| {
+        if seeds
+            .iter()
+            .any(|seed| seed.bliss_song.path == song.bliss_song.path)
+        {
+            (0, song)
+        } else {
+            rank += 1;
+            (rank, song)
+        }
+    });
+    let deduped: Box<dyn Iterator<Item = (usize, BlissSong)> + '_> = if query.dedup {
+        Box::new(ranked.coalesce(|previous, song| {
+            if is_duplicate(&previous.1, &song.1, &bliss_metric) {
+                Ok(previous)
+            } else {
+                Err((previous, song))
             }
+        }))
+    } else {
+        Box::new(ranked)
+    };
+    let tail: Vec<ClientPlaylistSong> = deduped
+        .filter(|&(rank, _)| rank > 0)
+        .filter(|(_, song)| {
+            !query.popularity_filter || song.extra_info.popularity > popularity_floor
+        })
+        .take(length)
+        .map(|(rank, song)| {
+            ClientPlaylistSong::new(
+                song,
+                mpd_library,
+                genre_weights.as_deref(),
+                &first_seed,
+                rank,
+            )
         })
-        .take(length + 1)
         .collect();
 
-    info!("Playlist generated in {}ms", now.elapsed().as_millis());
+    info!(
+        "Playlist generated with {} distance and {} normalization in {}ms{}",
+        metric.name(),
+        normalization.name(),
+        now.elapsed().as_millis(),
+        if indexed { " from the index" } else { "" }
+    );
 
+    let mut seeds = seeds.into_iter().map(|seed| {
+        ClientPlaylistSong::new(seed, mpd_library, genre_weights.as_deref(), &first_seed, 0)
+    });
     Ok(Json(ClientPlaylist {
-        head: playlist[0].clone(),
-        tail: playlist[1..].to_vec(),
+        head: seeds.next().expect("There's always at least one seed"),
+        extra_seeds: seeds.collect(),
+        tail,
+        filters,
     }))
 }
+
+#[get("/pin")]
+pub fn daemon_pin(
+    _access: ReadAccess,
+    daemon: &State<Arc<DaemonState>>,
+) -> Result<Json<DaemonSong>, NotFound<String>> {
+    daemon
+        .pin()
+        .map(Json)
+        .ok_or(NotFound("Daemon hasn't picked a pin yet".into()))
+}
+
+#[get("/mode")]
+pub fn daemon_mode(_access: ReadAccess, daemon: &State<Arc<DaemonState>>) -> Json<QueueMode> {
+    Json(daemon.mode())
+}
+
+#[get("/upcoming")]
+pub fn daemon_upcoming(
+    _access: ReadAccess,
+    daemon: &State<Arc<DaemonState>>,
+) -> Json<Vec<DaemonSong>> {
+    Json(daemon.upcoming())
+}
+
+#[get("/history")]
+pub fn daemon_history(
+    _access: ReadAccess,
+    daemon: &State<Arc<DaemonState>>,
+) -> Json<Vec<DaemonSong>> {
+    Json(daemon.history())
+}
+
+#[get("/status")]
+pub fn status(_access: ReadAccess, daemon: &State<Arc<DaemonState>>) -> Json<DaemonStatus> {
+    Json(daemon.status())
+}
+
+#[derive(Deserialize)]
+pub struct PathRequest {
+    path: PathBuf,
+}
+
+/// Queue the song and have the daemon switch to it as the new pin. Adding it to the queue is what
+/// wakes the daemon, so this fails if that does.
+#[post("/pin", data = "<request>")]
+pub fn set_pin(
+    request: Json<PathRequest>,
+    _access: ControlAccess,
+    state: &State<ClientLibrary>,
+    daemon: &State<Arc<DaemonState>>,
+) -> Result<Status, Custom<String>> {
+    state
+        .mpd_library
+        .path_to_bliss_song(&request.path.to_string_lossy())
+        .context("Song does not exist in bliss database")
+        .map_err(|e| Custom(Status::NotFound, e.to_string()))?;
+    daemon.request_pin(request.path.clone());
+    if let Err(e) = state.mpd_library.push_path(&request.path) {
+        daemon.clear_requested_pin();
+        return Err(Custom(Status::ServiceUnavailable, e.to_string()));
+    }
+    Ok(Status::Accepted)
+}
+
+#[derive(Deserialize)]
+pub struct ModeRequest {
+    mode: QueueMode,
+}
+
+/// Switch modes. Like `SIGHUP`, this takes effect at the next pin.
+#[post("/mode", data = "<request>")]
+pub fn set_mode(
+    request: Json<ModeRequest>,
+    _access: ControlAccess,
+    state: &State<ClientLibrary>,
+    daemon: &State<Arc<DaemonState>>,
+) -> Result<Json<QueueMode>, BadRequest<String>> {
+    if request.mode == QueueMode::Genres && !state.mpd_library.has_genre_weights() {
+        return Err(BadRequest(
+            "Genre weights weren't loaded, can't switch to genre mode".into(),
+        ));
+    }
+    daemon.set_mode(request.mode);
+    Ok(Json(daemon.mode()))
+}
+
+/// Stop recommending a song, starting with the next one queued.
+#[post("/exclude", data = "<request>")]
+pub fn exclude(
+    request: Json<PathRequest>,
+    _access: ControlAccess,
+    state: &State<ClientLibrary>,
+    daemon: &State<Arc<DaemonState>>,
+) -> Result<Status, Custom<String>> {
+    state
+        .mpd_library
+        .path_to_bliss_song(&request.path.to_string_lossy())
+        .context("Song does not exist in bliss database")
+        .map_err(|e| Custom(Status::NotFound, e.to_string()))?;
+    daemon.exclude(request.into_inner().path);
+    Ok(Status::NoContent)
+}
+
+/// Like `SIGUSR1`, but the daemon is woken up to run the update right away instead of at the next
+/// MPD event.
+#[post("/update")]
+pub fn update(
+    _access: ControlAccess,
+    state: &State<ClientLibrary>,
+    daemon: &State<Arc<DaemonState>>,
+) -> Result<Status, Custom<String>> {
+    daemon.request_update();
+    state.mpd_library.mpd.wake().map_err(|e| {
+        Custom(
+            Status::ServiceUnavailable,
+            format!("Update will only run at the next MPD event: {e:#}"),
+        )
+    })?;
+    Ok(Status::Accepted)
+}
+
+/// Stream daemon events as they happen. Subscribers that fall too far behind skip ahead.
+#[get("/events")]
+pub fn events(
+    _access: ReadAccess,
+    daemon: &State<Arc<DaemonState>>,
+    mut end: Shutdown,
+) -> EventStream![] {
+    let mut rx = daemon.subscribe();
+    EventStream! {
+        loop {
+            let event: DaemonEvent = select! {
+                msg = rx.recv() => match msg {
+                    Ok(event) => event,
+                    Err(RecvError::Closed) => break,
+                    Err(RecvError::Lagged(_)) => continue,
+                },
+                _ = &mut end => break,
+            };
+            yield Event::json(&event);
+        }
+    }
+}
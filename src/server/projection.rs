@@ -0,0 +1,194 @@
+//! A 2D map of the whole library, so the visualizer can plot every song at once.
+//!
+//! The map is a PCA projection of the bliss analyses onto their two main axes. There's no linear
+//! algebra crate in the tree, so the axes are found by power iteration on the covariance matrix,
+//! which is plenty for a few dozen features.
+
+use super::store::StoreEntry;
+use crate::NUM_BLISS_FEATURES;
+use ndarray::{Array1, Array2, Axis};
+use serde::Serialize;
+use std::path::PathBuf;
+
+const POWER_ITERATIONS: usize = 200;
+const CONVERGENCE_THRESHOLD: f32 = 1e-6;
+
+#[derive(Serialize)]
+pub struct EmbeddedSong {
+    pub path: PathBuf,
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Serialize)]
+pub struct Embedding {
+    /// Share of the total variance along each axis
+    pub explained_variance: [f32; 2],
+    /// The bliss feature weights making up each axis
+    pub axes: [Vec<f32>; 2],
+    pub songs: Vec<EmbeddedSong>,
+}
+
+/// Project every song onto the two principal components of the bliss analyses. Songs whose
+/// analysis has NaN or infinite features are left out of both the fit and the map.
+pub fn pca(entries: &[StoreEntry]) -> Embedding {
+    let entries: Vec<&StoreEntry> = entries
+        .iter()
+        .filter(|entry| entry.analysis.bliss.iter().all(|value| value.is_finite()))
+        .collect();
+    if entries.len() < 2 {
+        return Embedding {
+            explained_variance: [0.0; 2],
+            axes: [vec![0.0; NUM_BLISS_FEATURES], vec![0.0; NUM_BLISS_FEATURES]],
+            songs: entries
+                .iter()
+                .map(|entry| EmbeddedSong {
+                    path: entry.path.clone(),
+                    x: 0.0,
+                    y: 0.0,
+                })
+                .collect(),
+        };
+    }
+
+    let mut features = Array2::from_shape_fn((entries.len(), NUM_BLISS_FEATURES), |(i, j)| {
+        entries[i].analysis.bliss[j]
+    });
+    let mean = features
+        .mean_axis(Axis(0))
+        .expect("There are at least two songs");
+    features -= &mean;
+
+    let mut covariance = features.t().dot(&features) / (entries.len() - 1) as f32;
+    let total_variance = covariance.diag().sum();
+    let (first_axis, first_variance) = principal_axis(&covariance);
+    // deflate, so the next power iteration finds the second axis
+    let first_column = first_axis.view().insert_axis(Axis(1));
+    covariance -= &(first_column.dot(&first_column.t()) * first_variance);
+    let (second_axis, second_variance) = principal_axis(&covariance);
+
+    let xs = features.dot(&first_axis);
+    let ys = features.dot(&second_axis);
+    Embedding {
+        explained_variance: if total_variance > 0.0 {
+            [
+                first_variance / total_variance,
+                second_variance / total_variance,
+            ]
+        } else {
+            [0.0; 2]
+        },
+        axes: [first_axis.to_vec(), second_axis.to_vec()],
+        songs: entries
+            .iter()
+            .zip(xs.iter().zip(ys.iter()))
+            .map(|(entry, (&x, &y))| EmbeddedSong {
+                path: entry.path.clone(),
+                x,
+                y,
+            })
+            .collect(),
+    }
+}
+
+/// The dominant eigenvector of a covariance matrix and its eigenvalue.
+fn principal_axis(covariance: &Array2<f32>) -> (Array1<f32>, f32) {
+    let dimensions = covariance.nrows();
+    // not uniform, so it's unlikely to start out orthogonal to the answer
+    let mut axis = Array1::from_shape_fn(dimensions, |i| 1.0 + i as f32 / dimensions as f32);
+    axis /= axis.dot(&axis).sqrt();
+    for _ in 0..POWER_ITERATIONS {
+        let next = covariance.dot(&axis);
+        let norm = next.dot(&next).sqrt();
+        if norm == 0.0 {
+            return (axis, 0.0);
+        }
+        let next = next / norm;
+        let change = (&next - &axis).mapv(f32::abs).sum();
+        axis = next;
+        if change < CONVERGENCE_THRESHOLD {
+            break;
+        }
+    }
+    // the sign of an eigenvector is arbitrary, so pin it down to keep the map from flipping
+    // between rebuilds
+    let largest = axis
+        .iter()
+        .copied()
+        .max_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .unwrap_or(0.0);
+    if largest < 0.0 {
+        axis.mapv_inplace(|weight| -weight);
+    }
+    let variance = axis.dot(&covariance.dot(&axis));
+    (axis, variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NUM_GENRE_FEATURES;
+    use crate::server::{SongAnalyses, SongInfo};
+    use std::sync::Arc;
+
+    fn entry(name: &str, first: f32, second: f32) -> StoreEntry {
+        let mut bliss = [0.0; NUM_BLISS_FEATURES];
+        bliss[0] = first;
+        bliss[1] = second;
+        StoreEntry {
+            path: PathBuf::from(name),
+            info: Arc::new(SongInfo {
+                artist: None,
+                title: None,
+                album: None,
+                album_artist: None,
+                genre: None,
+                popularity: 0,
+                duration: 0,
+            }),
+            analysis: Arc::new(SongAnalyses {
+                bliss,
+                genre: [0.0; NUM_GENRE_FEATURES],
+            }),
+        }
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-4, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn finds_known_axes() {
+        // spread three times as far along the first feature as the second
+        let entries = [
+            entry("a.flac", 3.0, 0.0),
+            entry("b.flac", -3.0, 0.0),
+            entry("nan.flac", f32::NAN, 0.0),
+            entry("c.flac", 0.0, 1.0),
+            entry("d.flac", 0.0, -1.0),
+        ];
+        let embedding = pca(&entries);
+        let axis = |feature: usize| {
+            let mut axis = vec![0.0; NUM_BLISS_FEATURES];
+            axis[feature] = 1.0;
+            axis
+        };
+        assert_close(&embedding.axes[0], &axis(0));
+        assert_close(&embedding.axes[1], &axis(1));
+        // variances of 6 and 2/3
+        assert_close(&embedding.explained_variance, &[0.9, 0.1]);
+        let paths: Vec<&str> = embedding
+            .songs
+            .iter()
+            .map(|song| song.path.to_str().unwrap())
+            .collect();
+        assert_eq!(paths, ["a.flac", "b.flac", "c.flac", "d.flac"]);
+        let xs: Vec<f32> = embedding.songs.iter().map(|song| song.x).collect();
+        let ys: Vec<f32> = embedding.songs.iter().map(|song| song.y).collect();
+        assert_close(&xs, &[3.0, -3.0, 0.0, 0.0]);
+        assert_close(&ys, &[0.0, 0.0, 1.0, -1.0]);
+    }
+}
@@ -0,0 +1,270 @@
+//! The read-only view of song analyses and metadata the server hands out.
+//!
+//! Songs are kept sorted by path and split into fixed-size pages, so page boundaries only move
+//! when the library changes. A single index maps each path to its page and slot, and every page
+//! carries an ETag so clients can skip pages they already have. The library map is computed along
+//! with each rebuild.
+
+use super::projection::{Embedding, pca};
+use super::{SongAnalyses, SongInfo};
+use itertools::Itertools;
+use rocket::request::{FromRequest, Outcome, Request};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone)]
+pub struct StoreEntry {
+    pub path: PathBuf,
+    pub info: Arc<SongInfo>,
+    pub analysis: Arc<SongAnalyses>,
+}
+
+/// One page of the store, as handed out to a request.
+pub struct StorePage {
+    pub songs: Vec<StoreEntry>,
+    pub etag: String,
+}
+
+struct StoreContents {
+    /// Sorted by path
+    entries: Vec<StoreEntry>,
+    /// Path -> (page, slot)
+    index: HashMap<PathBuf, (usize, usize)>,
+    etags: Vec<String>,
+    embedding: Arc<Embedding>,
+}
+
+impl StoreContents {
+    fn build(songs: HashMap<PathBuf, (SongInfo, SongAnalyses)>, page_size: usize) -> Self {
+        let entries: Vec<StoreEntry> = songs
+            .into_iter()
+            .sorted_by(|a, b| a.0.cmp(&b.0))
+            .map(|(path, (info, analysis))| StoreEntry {
+                path,
+                info: Arc::new(info),
+                analysis: Arc::new(analysis),
+            })
+            .collect();
+        let index = entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| (entry.path.clone(), (i / page_size, i % page_size)))
+            .collect();
+        let etags = entries.chunks(page_size).map(page_etag).collect();
+        let embedding = Arc::new(pca(&entries));
+        Self {
+            entries,
+            index,
+            etags,
+            embedding,
+        }
+    }
+}
+
+/// Hash everything in a page, so the tag changes whenever any song in it does.
+fn page_etag(page: &[StoreEntry]) -> String {
+    let mut hasher = DefaultHasher::new();
+    for entry in page {
+        let analysis = &entry.analysis;
+        entry.path.hash(&mut hasher);
+        for feature in analysis.bliss.iter().chain(analysis.genre.iter()) {
+            feature.to_bits().hash(&mut hasher);
+        }
+    }
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Song analyses keyed by path (relative to the MPD base path), rebuilt in place when the library
+/// is updated.
+pub struct SongStore {
+    contents: RwLock<StoreContents>,
+    page_size: usize,
+}
+
+impl SongStore {
+    pub fn new(songs: HashMap<PathBuf, (SongInfo, SongAnalyses)>, page_size: usize) -> Self {
+        assert!(page_size > 0, "Page size must be greater than zero");
+        Self {
+            contents: RwLock::new(StoreContents::build(songs, page_size)),
+            page_size,
+        }
+    }
+
+    /// Replace the contents of the store. Requests in flight keep the pages they already have.
+    pub fn rebuild(&self, songs: HashMap<PathBuf, (SongInfo, SongAnalyses)>) {
+        let contents = StoreContents::build(songs, self.page_size);
+        *self.contents.write().expect("Poisoned lock") = contents;
+    }
+
+    pub fn song_count(&self) -> usize {
+        self.contents.read().expect("Poisoned lock").entries.len()
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.contents.read().expect("Poisoned lock").etags.len()
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Arc<SongAnalyses>> {
+        let contents = self.contents.read().expect("Poisoned lock");
+        let (page, slot) = contents.index.get(path)?;
+        Some(Arc::clone(
+            &contents.entries[page * self.page_size + slot].analysis,
+        ))
+    }
+
+    pub fn page(&self, page: usize) -> Option<StorePage> {
+        let contents = self.contents.read().expect("Poisoned lock");
+        let etag = contents.etags.get(page)?.clone();
+        let songs = contents.entries.chunks(self.page_size).nth(page)?.to_vec();
+        Some(StorePage { songs, etag })
+    }
+
+    /// Up to `limit` songs sorted after `cursor`, plus the cursor for the songs after those if
+    /// there are any. Cursors are paths, so they stay valid across rebuilds.
+    pub fn after(&self, cursor: &Path, limit: usize) -> (Vec<StoreEntry>, Option<PathBuf>) {
+        let contents = self.contents.read().expect("Poisoned lock");
+        let start = contents
+            .entries
+            .partition_point(|entry| entry.path.as_path() <= cursor);
+        let songs = contents
+            .entries
+            .iter()
+            .skip(start)
+            .take(limit)
+            .cloned()
+            .collect::<Vec<_>>();
+        let next = if start + songs.len() < contents.entries.len() {
+            songs.last().map(|entry| entry.path.clone())
+        } else {
+            None
+        };
+        (songs, next)
+    }
+
+    pub fn embedding(&self) -> Arc<Embedding> {
+        Arc::clone(&self.contents.read().expect("Poisoned lock").embedding)
+    }
+
+    /// Every song for which `predicate` returns true, in path order.
+    pub fn filter<P: Fn(&StoreEntry) -> bool>(&self, predicate: P) -> Vec<StoreEntry> {
+        self.contents
+            .read()
+            .expect("Poisoned lock")
+            .entries
+            .iter()
+            .filter(|entry| predicate(entry))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The `If-None-Match` header of a request, if any.
+pub struct IfNoneMatch(pub Option<String>);
+
+impl IfNoneMatch {
+    pub fn matches(&self, etag: &str) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|header| header.split(',').any(|tag| tag.trim() == etag))
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfNoneMatch {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(IfNoneMatch(
+            req.headers().get_one("If-None-Match").map(String::from),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NUM_BLISS_FEATURES, NUM_GENRE_FEATURES};
+
+    fn songs(names: &[&str], value: f32) -> HashMap<PathBuf, (SongInfo, SongAnalyses)> {
+        names
+            .iter()
+            .map(|name| {
+                let info = SongInfo {
+                    artist: None,
+                    title: None,
+                    album: None,
+                    album_artist: None,
+                    genre: None,
+                    popularity: 0,
+                    duration: 0,
+                };
+                let analysis = SongAnalyses {
+                    bliss: [value; NUM_BLISS_FEATURES],
+                    genre: [0.0; NUM_GENRE_FEATURES],
+                };
+                (PathBuf::from(name), (info, analysis))
+            })
+            .collect()
+    }
+
+    fn after(store: &SongStore, cursor: &str) -> (Vec<String>, Option<String>) {
+        let (songs, next) = store.after(Path::new(cursor), 2);
+        (
+            songs
+                .iter()
+                .map(|entry| entry.path.display().to_string())
+                .collect(),
+            next.map(|path| path.display().to_string()),
+        )
+    }
+
+    fn etags(store: &SongStore) -> Vec<String> {
+        (0..store.page_count())
+            .map(|page| store.page(page).expect("Page exists").etag)
+            .collect()
+    }
+
+    #[test]
+    fn cursors() {
+        let store = SongStore::new(songs(&["a", "b", "c", "d", "e"], 0.0), 2);
+        let owned =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+        // the end of a page
+        assert_eq!(after(&store, "b"), (owned(&["c", "d"]), Some("d".into())));
+        // running out exactly at the limit
+        assert_eq!(after(&store, "c"), (owned(&["d", "e"]), None));
+        assert_eq!(after(&store, "d"), (owned(&["e"]), None));
+        // past the end
+        assert_eq!(after(&store, "e"), (vec![], None));
+        assert_eq!(after(&store, "z"), (vec![], None));
+        // paths that aren't in the store still sort somewhere
+        assert_eq!(after(&store, ""), (owned(&["a", "b"]), Some("b".into())));
+        assert_eq!(after(&store, "bb"), (owned(&["c", "d"]), Some("d".into())));
+    }
+
+    #[test]
+    fn etags_follow_changes() {
+        let names = ["a", "b", "c", "d", "e"];
+        let store = SongStore::new(songs(&names, 0.0), 2);
+        let before = etags(&store);
+        assert_eq!(before.len(), 3);
+
+        store.rebuild(songs(&names, 0.0));
+        assert_eq!(etags(&store), before);
+
+        let mut changed = songs(&names, 0.0);
+        changed.extend(songs(&["c"], 1.0));
+        store.rebuild(changed);
+        let after = etags(&store);
+        assert_eq!(after[0], before[0]);
+        assert_ne!(after[1], before[1]);
+        assert_eq!(after[2], before[2]);
+        assert_eq!(
+            store.get(Path::new("c")).expect("c is stored").bliss,
+            [1.0; NUM_BLISS_FEATURES]
+        );
+    }
+}
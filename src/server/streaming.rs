@@ -0,0 +1,163 @@
+//! Serving song files to the browser with HTTP range support, so previews can seek without
+//! downloading the whole file first.
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::tokio::fs::File;
+use rocket::tokio::io::{AsyncReadExt, AsyncSeekExt};
+use std::io::SeekFrom;
+use std::path::Path;
+
+/// The `Range` header of a request, if any. Only the first range of a multi-range request is
+/// honored, which is all audio elements ever ask for.
+pub struct RangeHeader(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RangeHeader {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RangeHeader(
+            req.headers().get_one("Range").map(String::from),
+        ))
+    }
+}
+
+impl RangeHeader {
+    /// The inclusive byte range asked for in a file of length `len`. `Ok(None)` means the whole
+    /// file, `Err(())` means the range can't be satisfied.
+    fn resolve(&self, len: u64) -> Result<Option<(u64, u64)>, ()> {
+        let Some(header) = self.0.as_deref() else {
+            return Ok(None);
+        };
+        // ignore units we don't know about, as the spec says to
+        let Some(ranges) = header.trim().strip_prefix("bytes=") else {
+            return Ok(None);
+        };
+        let range = ranges.split(',').next().unwrap_or_default().trim();
+        let (start, end) = range.split_once('-').ok_or(())?;
+        let (start, end) = match (start.trim(), end.trim()) {
+            ("", "") => return Err(()),
+            // the last `suffix` bytes
+            ("", suffix) => {
+                let suffix: u64 = suffix.parse().map_err(|_| ())?;
+                if suffix == 0 {
+                    return Err(());
+                }
+                (len.saturating_sub(suffix), len.saturating_sub(1))
+            }
+            (start, "") => (start.parse().map_err(|_| ())?, len.saturating_sub(1)),
+            (start, end) => {
+                let end: u64 = end.parse().map_err(|_| ())?;
+                (
+                    start.parse().map_err(|_| ())?,
+                    end.min(len.saturating_sub(1)),
+                )
+            }
+        };
+        if start > end || start >= len {
+            return Err(());
+        }
+        Ok(Some((start, end)))
+    }
+}
+
+/// A song file, or the part of it a range request asked for.
+pub struct AudioFile {
+    file: File,
+    len: u64,
+    range: Result<Option<(u64, u64)>, ()>,
+    content_type: ContentType,
+}
+
+impl AudioFile {
+    pub async fn open(path: &Path, range: &RangeHeader) -> std::io::Result<Self> {
+        let mut file = File::open(path).await?;
+        let len = file.metadata().await?.len();
+        let range = range.resolve(len);
+        if let Ok(Some((start, _))) = range {
+            file.seek(SeekFrom::Start(start)).await?;
+        }
+        let content_type = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(ContentType::from_extension)
+            .unwrap_or(ContentType::Binary);
+        Ok(Self {
+            file,
+            len,
+            range,
+            content_type,
+        })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for AudioFile {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build();
+        response
+            .header(self.content_type)
+            .header(Header::new("Accept-Ranges", "bytes"));
+        match self.range {
+            Ok(None) => response.sized_body(None, self.file).ok(),
+            // already seeked to `start` when opening
+            Ok(Some((start, end))) => response
+                .status(Status::PartialContent)
+                .header(Header::new(
+                    "Content-Range",
+                    format!("bytes {start}-{end}/{}", self.len),
+                ))
+                .streamed_body(self.file.take(end - start + 1))
+                .ok(),
+            Err(()) => response
+                .status(Status::RangeNotSatisfiable)
+                .header(Header::new(
+                    "Content-Range",
+                    format!("bytes */{}", self.len),
+                ))
+                .ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(header: Option<&str>, len: u64) -> Result<Option<(u64, u64)>, ()> {
+        RangeHeader(header.map(String::from)).resolve(len)
+    }
+
+    #[test]
+    fn whole_file_without_a_byte_range() {
+        assert_eq!(resolve(None, 100), Ok(None));
+        assert_eq!(resolve(Some("items=0-10"), 100), Ok(None));
+    }
+
+    #[test]
+    fn byte_ranges_are_clamped_to_the_file() {
+        assert_eq!(resolve(Some("bytes=0-9"), 100), Ok(Some((0, 9))));
+        assert_eq!(resolve(Some("bytes=50-"), 100), Ok(Some((50, 99))));
+        assert_eq!(resolve(Some("bytes=90-200"), 100), Ok(Some((90, 99))));
+        assert_eq!(resolve(Some("bytes=-10"), 100), Ok(Some((90, 99))));
+        assert_eq!(resolve(Some("bytes=-500"), 100), Ok(Some((0, 99))));
+        // only the first of several ranges
+        assert_eq!(resolve(Some("bytes=0-1, 5-6"), 100), Ok(Some((0, 1))));
+    }
+
+    #[test]
+    fn unsatisfiable_ranges() {
+        for header in [
+            "bytes=100-",
+            "bytes=10-5",
+            "bytes=-",
+            "bytes=-0",
+            "bytes=a-b",
+            "bytes=5",
+        ] {
+            assert_eq!(resolve(Some(header), 100), Err(()), "{header}");
+        }
+        assert_eq!(resolve(Some("bytes=0-"), 0), Err(()));
+    }
+}